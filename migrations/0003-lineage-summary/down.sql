@@ -0,0 +1 @@
+drop table lineage_summary;
@@ -0,0 +1,7 @@
+create table if not exists lineage_summary (
+    zettel_id  integer not null references zettel(id)
+  , model      text    not null
+  , summary    text    not null
+  , created_at integer not null default (unixepoch('subsec') * 1000)
+  , primary key (zettel_id, model)
+) strict;
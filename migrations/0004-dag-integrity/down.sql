@@ -0,0 +1,20 @@
+drop trigger before_insert_zettel_edge;
+
+drop trigger after_insert_zettel_edge;
+
+create trigger after_insert_zettel_edge after insert on zettel_edge
+when new.parent_id is not null
+begin
+  -- parent
+  insert into zettel_lineage (ancestor_id, descendant_id, separation)
+  values (new.parent_id, new.node_id, 1) on conflict do nothing;
+
+  -- all ancestors
+  insert into zettel_lineage
+  select 
+      ancestor_id
+    , new.node_id as descendant_id
+    , 1 + separation as separation
+  from zettel_lineage ancestor
+  where ancestor.descendant_id = new.parent_id;
+end;
@@ -0,0 +1,43 @@
+-- Edges always point from a new zettel to existing ones, so a valid edge never
+-- refers to itself, to a missing zettel, or to one of the node's descendants.
+create trigger before_insert_zettel_edge before insert on zettel_edge
+when new.parent_id is not null
+begin
+  select raise(abort, 'zettel cannot be its own parent')
+  where new.node_id = new.parent_id;
+
+  select raise(abort, 'parent zettel does not exist')
+  where not exists (select 1 from zettel where id = new.parent_id);
+
+  select raise(abort, 'edge would create a cycle')
+  where exists (
+    select 1
+    from zettel_lineage
+    where ancestor_id = new.node_id
+      and descendant_id = new.parent_id
+  );
+end;
+
+-- The original trigger failed when two parents shared an ancestor.
+-- Keep the longest path in that case, which is what zettel_depth expects.
+drop trigger after_insert_zettel_edge;
+
+create trigger after_insert_zettel_edge after insert on zettel_edge
+when new.parent_id is not null
+begin
+  -- parent
+  insert into zettel_lineage (ancestor_id, descendant_id, separation)
+  values (new.parent_id, new.node_id, 1)
+  on conflict do nothing;
+
+  -- all ancestors
+  insert into zettel_lineage (ancestor_id, descendant_id, separation)
+  select
+      ancestor_id
+    , new.node_id as descendant_id
+    , 1 + separation as separation
+  from zettel_lineage ancestor
+  where ancestor.descendant_id = new.parent_id
+  on conflict (ancestor_id, descendant_id) do update
+  set separation = max(separation, excluded.separation);
+end;
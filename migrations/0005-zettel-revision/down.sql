@@ -0,0 +1,3 @@
+drop index zettel_revision_zettel_id;
+
+drop table zettel_revision;
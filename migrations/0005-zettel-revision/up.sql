@@ -0,0 +1,9 @@
+-- Earlier contents of amended zettels. created_at is when the content was replaced.
+create table if not exists zettel_revision (
+    id         integer primary key
+  , zettel_id  integer not null references zettel(id)
+  , content    text    not null
+  , created_at integer not null default (unixepoch('subsec') * 1000)
+) strict;
+
+create index if not exists zettel_revision_zettel_id on zettel_revision (zettel_id);
@@ -0,0 +1,5 @@
+drop table article_source;
+
+drop table zettel_source;
+
+drop table source;
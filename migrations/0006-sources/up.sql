@@ -0,0 +1,26 @@
+create table if not exists source (
+    id         integer primary key
+  , kind       text    not null default 'misc' -- BibTeX entry type, e.g. article or book
+  , bibtex_key text    unique
+  , author     text    -- BibTeX form, separated by "and"
+  , title      text    not null
+  , year       integer
+  , doi        text    unique -- bare and lowercased, e.g. 10.1000/xyz123
+  , url        text
+  , isbn       text
+  , created_at integer not null default (unixepoch('subsec') * 1000)
+) strict;
+
+create table if not exists zettel_source (
+    zettel_id  integer not null references zettel(id)
+  , source_id  integer not null references source(id)
+  , created_at integer not null default (unixepoch('subsec') * 1000)
+  , primary key (zettel_id, source_id)
+) strict;
+
+create table if not exists article_source (
+    article_id integer not null references article(id)
+  , source_id  integer not null references source(id)
+  , created_at integer not null default (unixepoch('subsec') * 1000)
+  , primary key (article_id, source_id)
+) strict;
@@ -0,0 +1,3 @@
+drop index zettel_review_due_at;
+
+drop table zettel_review;
@@ -0,0 +1,13 @@
+-- Spaced-repetition schedule of zettels, following SM-2. Zettels without a row have
+-- never been reviewed.
+create table if not exists zettel_review (
+    zettel_id     integer not null primary key references zettel(id)
+  , repetitions   integer not null default 0 -- successful reviews in a row
+  , interval_days integer not null default 0
+  , ease_factor   real    not null default 2.5
+  , due_at        integer not null
+  , archived      integer not null default 0 -- archived zettels are never due again
+  , reviewed_at   integer not null default (unixepoch('subsec') * 1000)
+) strict;
+
+create index if not exists zettel_review_due_at on zettel_review (due_at);
@@ -1,130 +1,1114 @@
-use std::error::Error;
+use chrono::{NaiveDate, Utc};
+use tracing::{info, instrument, warn};
 
 use crate::{
-    combine_zettel_contents,
+    bibtex::{parse_bibtex, to_bibtex},
+    clip::{PageFetcher, extract_page, truncate_text},
     db::{
-        self, add_tag_if_not_exists, delete_tag_for_zettel_if_exists, find_n_recent_leaf_zettels,
-        find_zettels_by_embedding, get_db, get_tags_for_zettel, store_zettel,
+        self, Db, add_tag_if_not_exists, delete_tag_for_zettel_if_exists, find_all_articles,
+        find_all_tags, find_ancestors, find_lineage_summary, find_n_recent_leaf_zettels,
+        find_parents, find_revisions, find_zettel_by_id, find_zettels_by_embedding,
+        get_tags_for_zettel, store_lineage_summary, store_zettel,
     },
+    doi::{DoiResolver, normalize_doi},
+    error::TshError,
+    journal::{JOURNAL_TAG_PREFIX, journal_tag, parse_journal_tag},
     llm::LlmClient,
-    model::{Zettel, ZettelTag},
-    open_and_edit_neovim_buffer,
+    model::{
+        Answer, Article, ReviewItem, ReviewSchedule, Source, SourceMetadata, TagSuggestion, Zettel,
+        ZettelRevision, ZettelTag,
+    },
+    review::{
+        DAY_MS, FIRST_REVIEW_AFTER_DAYS, REVIEW_QUEUE_SIZE, ReviewRating, archived_schedule,
+        next_schedule,
+    },
     tui::app::LlmConfig,
 };
 
+/// Stores the content of the editor as a new zettel with the given parents and tags.
+/// Returns `None` if there was nothing to store.
+#[instrument(skip_all, fields(n_parents = parents.len(), n_tags = tags.len()))]
 pub async fn add_zettel(
-    db_path: &str,
+    db: &Db,
     llm_config: &LlmConfig,
-    parents: &Vec<Zettel>,
-) -> Result<(), Box<dyn Error>> {
+    parents: &[Zettel],
+    tags: &[String],
+    edited_content: &str,
+) -> Result<Option<Zettel>, TshError> {
     let mut llm_client = LlmClient::from(llm_config);
 
-    match open_and_edit_neovim_buffer(Some(combine_zettel_contents(parents.to_vec()).as_str())) {
-        Ok(edited_content) => {
-            // Don't save if:
-            // - only one parent and content unchanged
-            // - empty zettel
-            let one_parent_and_content_unchanged =
-                (parents.len() == 1) && (edited_content == parents.first().unwrap().content);
-            if one_parent_and_content_unchanged || edited_content.is_empty() {
-                return Ok(());
-            }
+    // Don't save if:
+    // - only one parent and content unchanged
+    // - empty zettel
+    let one_parent_and_content_unchanged =
+        (parents.len() == 1) && (edited_content == parents.first().unwrap().content);
+    if one_parent_and_content_unchanged || edited_content.is_empty() {
+        info!("Nothing to store");
+        return Ok(None);
+    }
 
-            if let Ok(embedding) = llm_client.embed(&edited_content).await {
-                let parent_ids: Vec<i64> = parents.iter().map(|zettel| zettel.id).collect();
-
-                let mut conn = get_db(db_path).await?;
-                let tx = conn.transaction()?;
-                match store_zettel(&tx, &edited_content, embedding.clone(), parent_ids).await {
-                    Ok(_) => {
-                        tx.commit()?;
-                    }
-                    Err(e) => {
-                        tx.rollback()?;
-                        eprintln!("Error storing content: {}", e); // TODO: logging
-                    }
-                }
-            }
-        }
-        Err(e) => eprintln!("Error interacting with Neovim: {}", e),
+    let embedding = llm_client.embed(edited_content).await?;
+    let parent_ids: Vec<i64> = parents.iter().map(|zettel| zettel.id).collect();
+
+    let mut conn = db.lock().await;
+    let tx = conn.transaction()?;
+    let zettel = store_zettel(&tx, edited_content, embedding, parent_ids)?;
+    for tag in tags {
+        add_tag_if_not_exists(&tx, zettel.id, tag)?;
     }
+    tx.commit()?;
+    info!(zettel_id = zettel.id, "Stored zettel");
 
-    Ok(())
+    Ok(Some(zettel))
+}
+
+/// Edits a zettel in place instead of iterating it. The previous content is kept as a
+/// revision and the edges stay as they are. Returns `None` if nothing was changed.
+/// The content is compared with the stored one, as `zettel` may be a copy from before
+/// earlier amendments.
+#[instrument(skip_all, fields(zettel_id = zettel.id))]
+pub async fn amend_zettel(
+    db: &Db,
+    llm_config: &LlmConfig,
+    zettel: &Zettel,
+    edited_content: &str,
+) -> Result<Option<Zettel>, TshError> {
+    if edited_content.is_empty() || edited_content == get_zettel(db, zettel.id).await?.content {
+        info!("Nothing to store");
+        return Ok(None);
+    }
+
+    let amended = replace_content(db, llm_config, zettel.id, edited_content).await?;
+    Ok(Some(amended))
 }
 
+/// Makes an earlier revision the current content. The content it replaces becomes a
+/// revision itself, so restoring can be undone.
+#[instrument(skip_all, fields(zettel_id = revision.zettel_id, revision_id = revision.id))]
+pub async fn restore_revision(
+    db: &Db,
+    llm_config: &LlmConfig,
+    revision: &ZettelRevision,
+) -> Result<Zettel, TshError> {
+    replace_content(db, llm_config, revision.zettel_id, &revision.content).await
+}
+
+async fn replace_content(
+    db: &Db,
+    llm_config: &LlmConfig,
+    zettel_id: i64,
+    content: &str,
+) -> Result<Zettel, TshError> {
+    let mut llm_client = LlmClient::from(llm_config);
+    let embedding = llm_client.embed(content).await?;
+
+    let mut conn = db.lock().await;
+    let tx = conn.transaction()?;
+    let zettel = db::amend_zettel(&tx, zettel_id, content, embedding)?;
+    tx.commit()?;
+    info!(zettel_id, "Amended zettel");
+
+    Ok(zettel)
+}
+
+#[instrument(skip_all, fields(zettel_id = zettel_id))]
+pub async fn get_revisions(db: &Db, zettel_id: i64) -> Result<Vec<ZettelRevision>, TshError> {
+    let mut conn = db.lock().await;
+    let tx = conn.transaction()?;
+    let revisions = find_revisions(&tx, zettel_id)?;
+    tx.commit()?;
+    Ok(revisions)
+}
+
+#[instrument(skip_all, fields(query_len = query.len()))]
 pub async fn find_zettels(
-    db_path: &str,
+    db: &Db,
     llm_config: &LlmConfig,
     query: &str,
-) -> Result<Vec<Zettel>, Box<dyn Error>> {
+) -> Result<Vec<Zettel>, TshError> {
     let mut llm_client = LlmClient::from(llm_config);
 
     let query_embedding = llm_client.embed(query).await?;
 
-    let mut conn = get_db(db_path).await?;
+    let mut conn = db.lock().await;
     let tx = conn.transaction()?;
-    let zettels: Vec<Zettel> = find_zettels_by_embedding(&tx, query_embedding).await?;
+    let zettels: Vec<Zettel> = find_zettels_by_embedding(&tx, query_embedding)?;
     tx.commit()?;
 
     Ok(zettels)
 }
 
-pub async fn get_n_recent_zettels(db_path: &str, n: i64) -> Result<Vec<Zettel>, Box<dyn Error>> {
-    let mut conn = get_db(db_path).await?;
+#[instrument(skip_all, fields(zettel_id = zettel_id))]
+pub async fn get_zettel(db: &Db, zettel_id: i64) -> Result<Zettel, TshError> {
+    let mut conn = db.lock().await;
+    let tx = conn.transaction()?;
+    let zettel = find_zettel_by_id(&tx, zettel_id)?;
+    tx.commit()?;
+    Ok(zettel)
+}
+
+#[instrument(skip_all, fields(n = n))]
+pub async fn get_n_recent_zettels(db: &Db, n: i64) -> Result<Vec<Zettel>, TshError> {
+    let mut conn = db.lock().await;
     let tx = conn.transaction()?;
-    let zettels: Vec<Zettel> = find_n_recent_leaf_zettels(&tx, n).await?;
+    let zettels: Vec<Zettel> = find_n_recent_leaf_zettels(&tx, n)?;
     tx.commit()?;
 
     Ok(zettels)
 }
 
-pub async fn add_tag_to_zettel(
-    db_path: &str,
-    zettel_id: i64,
-    tag: String,
-) -> Result<(), Box<dyn Error>> {
-    let mut conn = get_db(db_path).await?;
+#[instrument(skip_all, fields(zettel_id = zettel_id))]
+pub async fn add_tag_to_zettel(db: &Db, zettel_id: i64, tag: String) -> Result<(), TshError> {
+    let mut conn = db.lock().await;
     let tx = conn.transaction()?;
-    add_tag_if_not_exists(&tx, zettel_id, &tag).await?;
+    add_tag_if_not_exists(&tx, zettel_id, &tag)?;
     tx.commit()?;
     Ok(())
 }
 
-pub async fn get_tags(db_path: &str, zettel_id: i64) -> Result<Vec<ZettelTag>, Box<dyn Error>> {
-    let mut conn = get_db(db_path).await?;
+#[instrument(skip_all, fields(zettel_id = zettel_id))]
+pub async fn get_tags(db: &Db, zettel_id: i64) -> Result<Vec<ZettelTag>, TshError> {
+    let mut conn = db.lock().await;
     let tx = conn.transaction()?;
-    let tags = get_tags_for_zettel(&tx, zettel_id).await?;
+    let tags = get_tags_for_zettel(&tx, zettel_id)?;
     tx.commit()?;
     Ok(tags)
 }
 
-pub async fn delete_tag_from_zettel(
-    db_path: &str,
-    zettel_id: i64,
-    tag: &str,
-) -> Result<(), Box<dyn Error>> {
-    let mut conn = get_db(db_path).await?;
+#[instrument(skip_all, fields(zettel_id = zettel_id))]
+pub async fn delete_tag_from_zettel(db: &Db, zettel_id: i64, tag: &str) -> Result<(), TshError> {
+    let mut conn = db.lock().await;
     let tx = conn.transaction()?;
-    delete_tag_for_zettel_if_exists(&tx, zettel_id, tag).await?;
+    delete_tag_for_zettel_if_exists(&tx, zettel_id, tag)?;
     tx.commit()?;
     Ok(())
 }
 
-pub async fn find_tags(db_path: &str, search_string: &str) -> Result<Vec<String>, Box<dyn Error>> {
-    let mut conn = get_db(db_path).await?;
+#[instrument(skip_all)]
+pub async fn find_tags(db: &Db, search_string: &str) -> Result<Vec<String>, TshError> {
+    let mut conn = db.lock().await;
     let tx = conn.transaction()?;
-    let tags = db::find_tags_by_search_string(&tx, search_string).await?;
+    let tags = db::find_tags_by_search_string(&tx, search_string)?;
     tx.commit()?;
     Ok(tags)
 }
 
-pub async fn get_zettels_by_tags(
-    db_path: &str,
-    tags: Vec<String>,
-) -> Result<Vec<Zettel>, Box<dyn Error>> {
-    let mut conn = get_db(db_path).await?;
+#[instrument(skip_all, fields(n_tags = tags.len()))]
+pub async fn get_zettels_by_tags(db: &Db, tags: Vec<String>) -> Result<Vec<Zettel>, TshError> {
+    let mut conn = db.lock().await;
     let tx = conn.transaction()?;
-    let zettels = db::find_zettels_by_tags(&tx, tags).await?;
+    let zettels = db::find_zettels_by_tags(&tx, tags)?;
     tx.commit()?;
     Ok(zettels)
 }
+
+/// Returns the journal entry of a day, the most recent one if there are several.
+#[instrument(skip_all)]
+pub async fn get_journal_entry(db: &Db, date: NaiveDate) -> Result<Option<Zettel>, TshError> {
+    let entries = get_zettels_by_tags(db, vec![journal_tag(date)]).await?;
+    Ok(entries.into_iter().max_by_key(|zettel| zettel.created_at))
+}
+
+/// Returns the days that have a journal entry, the most recent first.
+#[instrument(skip_all)]
+pub async fn get_journal_days(db: &Db) -> Result<Vec<NaiveDate>, TshError> {
+    let tags = find_tags(db, JOURNAL_TAG_PREFIX).await?;
+    let mut days: Vec<NaiveDate> = tags
+        .iter()
+        .filter_map(|tag| parse_journal_tag(tag))
+        .collect();
+    days.sort_by(|a, b| b.cmp(a));
+    Ok(days)
+}
+
+/// Returns the zettels due for review today, see `crate::review`.
+#[instrument(skip_all, fields(leaves_only = leaves_only))]
+pub async fn get_review_queue(db: &Db, leaves_only: bool) -> Result<Vec<ReviewItem>, TshError> {
+    let now = Utc::now().timestamp_millis();
+    let mut conn = db.lock().await;
+    let tx = conn.transaction()?;
+    let items = db::find_due_reviews(
+        &tx,
+        now,
+        FIRST_REVIEW_AFTER_DAYS * DAY_MS,
+        leaves_only,
+        REVIEW_QUEUE_SIZE,
+    )?;
+    tx.commit()?;
+    Ok(items)
+}
+
+/// Records a review and schedules the next one.
+#[instrument(skip_all, fields(zettel_id = zettel_id))]
+pub async fn review_zettel(
+    db: &Db,
+    zettel_id: i64,
+    rating: ReviewRating,
+) -> Result<ReviewSchedule, TshError> {
+    let now = Utc::now().timestamp_millis();
+    let mut conn = db.lock().await;
+    let tx = conn.transaction()?;
+    let previous = db::find_review_schedule(&tx, zettel_id)?;
+    let schedule = next_schedule(zettel_id, previous.as_ref(), rating, now);
+    db::store_review_schedule(&tx, &schedule)?;
+    tx.commit()?;
+    info!(
+        zettel_id,
+        interval_days = schedule.interval_days,
+        "Reviewed zettel"
+    );
+    Ok(schedule)
+}
+
+/// Takes a zettel out of the review queue for good. The zettel itself is kept.
+#[instrument(skip_all, fields(zettel_id = zettel_id))]
+pub async fn archive_zettel(db: &Db, zettel_id: i64) -> Result<(), TshError> {
+    let now = Utc::now().timestamp_millis();
+    let mut conn = db.lock().await;
+    let tx = conn.transaction()?;
+    let previous = db::find_review_schedule(&tx, zettel_id)?;
+    let schedule = archived_schedule(zettel_id, previous, now);
+    db::store_review_schedule(&tx, &schedule)?;
+    tx.commit()?;
+    info!(zettel_id, "Archived zettel");
+    Ok(())
+}
+
+/// The model may propose at most this many tags that are not yet in the vocabulary.
+const MAX_NEW_TAG_SUGGESTIONS: usize = 2;
+
+const AUTO_TAG_SYSTEM_PROMPT: &str = "You assign tags to notes in a personal zettelkasten. \
+Prefer tags from the existing vocabulary. Only invent a new tag if none of the existing ones fit. \
+Answer with one tag per line and nothing else.";
+
+#[instrument(skip_all, fields(zettel_id = zettel.id))]
+pub async fn suggest_tags(
+    db: &Db,
+    llm_config: &LlmConfig,
+    zettel: &Zettel,
+) -> Result<Vec<TagSuggestion>, TshError> {
+    let mut llm_client = LlmClient::from(llm_config);
+
+    let mut conn = db.lock().await;
+    let tx = conn.transaction()?;
+    let vocabulary = find_all_tags(&tx)?;
+    let assigned: Vec<String> = get_tags_for_zettel(&tx, zettel.id)?
+        .into_iter()
+        .map(|zettel_tag| zettel_tag.tag)
+        .collect();
+    tx.commit()?;
+    // Don't keep the database locked while waiting for the model.
+    drop(conn);
+
+    let prompt = format!(
+        "Existing tags:\n{}\n\nYou may suggest at most {} new tags.\n\nNote:\n{}",
+        vocabulary.join("\n"),
+        MAX_NEW_TAG_SUGGESTIONS,
+        zettel.content,
+    );
+    let response = llm_client.complete(AUTO_TAG_SYSTEM_PROMPT, &prompt).await?;
+
+    Ok(parse_tag_suggestions(
+        &response,
+        &vocabulary,
+        &assigned,
+        MAX_NEW_TAG_SUGGESTIONS,
+    ))
+}
+
+fn parse_tag_suggestions(
+    response: &str,
+    vocabulary: &[String],
+    assigned: &[String],
+    max_new: usize,
+) -> Vec<TagSuggestion> {
+    let mut suggestions: Vec<TagSuggestion> = vec![];
+    let mut n_new = 0;
+
+    // Models like to decorate their answers, so accept bullet points, hashes and commas.
+    let candidates = response
+        .lines()
+        .flat_map(|line| line.split(','))
+        .map(|candidate| {
+            candidate
+                .trim_start_matches(|c: char| c.is_whitespace() || ['-', '*', '#'].contains(&c))
+                .trim()
+                .to_string()
+        })
+        .filter(|candidate| {
+            !candidate.is_empty()
+                && !candidate.ends_with(':')
+                && !candidate.contains(char::is_whitespace)
+        });
+
+    for tag in candidates {
+        if assigned.contains(&tag) || suggestions.iter().any(|s| s.tag == tag) {
+            continue;
+        }
+        let is_new = !vocabulary.contains(&tag);
+        if is_new {
+            if n_new >= max_new {
+                continue;
+            }
+            n_new += 1;
+        }
+        suggestions.push(TagSuggestion {
+            tag,
+            is_new,
+            accepted: !is_new,
+        });
+    }
+
+    suggestions
+}
+
+/// Number of retrieved zettels that are passed to the chat model as context.
+const ASK_CONTEXT_SIZE: usize = 8;
+
+const ASK_SYSTEM_PROMPT: &str = "You answer questions using only the notes provided by the user. \
+Every note starts with its id in square brackets. \
+Cite the notes you rely on by their id in square brackets, e.g. [12]. \
+If the notes don't contain an answer, say so.";
+
+#[instrument(skip_all, fields(question_len = question.len()))]
+pub async fn ask(db: &Db, llm_config: &LlmConfig, question: &str) -> Result<Answer, TshError> {
+    let mut llm_client = LlmClient::from(llm_config);
+    // Fails before the question is embedded for nothing.
+    llm_client.chat_model()?;
+
+    let mut context = find_zettels(db, llm_config, question).await?;
+    context.truncate(ASK_CONTEXT_SIZE);
+
+    let notes = context
+        .iter()
+        .map(|zettel| format!("[{}] {}", zettel.id, zettel.content))
+        .collect::<Vec<String>>()
+        .join("\n\n");
+    let prompt = format!("Notes:\n\n{}\n\nQuestion: {}", notes, question);
+    let text = llm_client.complete(ASK_SYSTEM_PROMPT, &prompt).await?;
+
+    let citations = parse_citations(&text)
+        .into_iter()
+        .filter_map(|id| context.iter().find(|zettel| zettel.id == id).cloned())
+        .collect();
+
+    Ok(Answer {
+        question: question.to_string(),
+        text,
+        citations,
+    })
+}
+
+/// Collects the ids cited as `[12]` or `[12, 34]`, in order of first appearance.
+fn parse_citations(text: &str) -> Vec<i64> {
+    let mut ids: Vec<i64> = vec![];
+    for (start, _) in text.match_indices('[') {
+        let Some(len) = text[start..].find(']') else {
+            continue;
+        };
+        for candidate in text[start + 1..start + len].split(',') {
+            if let Ok(id) = candidate.trim().parse::<i64>()
+                && !ids.contains(&id)
+            {
+                ids.push(id);
+            }
+        }
+    }
+    ids
+}
+
+/// Stores the answer as a new zettel whose parents are the cited zettels.
+#[instrument(skip_all)]
+pub async fn save_answer(
+    db: &Db,
+    llm_config: &LlmConfig,
+    answer: &Answer,
+) -> Result<Zettel, TshError> {
+    let content = format!("{}\n\n{}", answer.question, answer.text);
+    let parent_ids: Vec<i64> = answer.citations.iter().map(|zettel| zettel.id).collect();
+    create_zettel(db, llm_config, &content, parent_ids).await
+}
+
+/// Embeds and stores content that didn't go through the editor.
+#[instrument(skip_all, fields(parent_ids = ?parent_ids))]
+pub async fn create_zettel(
+    db: &Db,
+    llm_config: &LlmConfig,
+    content: &str,
+    parent_ids: Vec<i64>,
+) -> Result<Zettel, TshError> {
+    let mut llm_client = LlmClient::from(llm_config);
+    let embedding = llm_client.embed(content).await?;
+
+    let mut conn = db.lock().await;
+    let tx = conn.transaction()?;
+    let zettel = store_zettel(&tx, content, embedding, parent_ids)?;
+    tx.commit()?;
+    info!(zettel_id = zettel.id, "Stored zettel");
+
+    Ok(zettel)
+}
+
+/// Returns the zettel's ancestors, most distant first, followed by the zettel itself.
+#[instrument(skip_all, fields(zettel_id = zettel.id))]
+pub async fn get_lineage(db: &Db, zettel: &Zettel) -> Result<Vec<Zettel>, TshError> {
+    let mut conn = db.lock().await;
+    let tx = conn.transaction()?;
+    let mut lineage = find_ancestors(&tx, zettel.id)?;
+    tx.commit()?;
+    lineage.push(zettel.clone());
+    Ok(lineage)
+}
+
+#[instrument(skip_all, fields(zettel_id = zettel_id))]
+pub async fn get_cached_lineage_summary(
+    db: &Db,
+    llm_config: &LlmConfig,
+    zettel_id: i64,
+) -> Result<Option<String>, TshError> {
+    let Some(chat_model) = &llm_config.chat_model else {
+        return Ok(None);
+    };
+    let mut conn = db.lock().await;
+    let tx = conn.transaction()?;
+    let summary = find_lineage_summary(&tx, zettel_id, chat_model)?;
+    tx.commit()?;
+    Ok(summary)
+}
+
+const LINEAGE_SYSTEM_PROMPT: &str = "You are given successive versions of a thought from a \
+personal zettelkasten, oldest first. Explain how the thought developed: what was added, dropped \
+or reframed along the way, and where it stands now. Be concise.";
+
+/// Summarises how a zettel evolved from its ancestors. Summaries are cached per zettel and
+/// chat model, so only the first call for a combination hits the model.
+#[instrument(skip_all, fields(zettel_id = zettel.id))]
+pub async fn summarise_lineage(
+    db: &Db,
+    llm_config: &LlmConfig,
+    zettel: &Zettel,
+) -> Result<String, TshError> {
+    let chat_model = llm_config
+        .chat_model
+        .clone()
+        .ok_or(TshError::Config("No chat_model configured".to_string()))?;
+
+    if let Some(summary) = get_cached_lineage_summary(db, llm_config, zettel.id).await? {
+        info!("Using cached lineage summary");
+        return Ok(summary);
+    }
+
+    let lineage = get_lineage(db, zettel).await?;
+    let versions = lineage
+        .iter()
+        .enumerate()
+        .map(|(i, version)| format!("Version {}:\n{}", i + 1, version.content))
+        .collect::<Vec<String>>()
+        .join("\n\n");
+
+    let mut llm_client = LlmClient::from(llm_config);
+    let summary = llm_client
+        .complete(LINEAGE_SYSTEM_PROMPT, &versions)
+        .await?;
+
+    let mut conn = db.lock().await;
+    let tx = conn.transaction()?;
+    store_lineage_summary(&tx, zettel.id, &chat_model, &summary)?;
+    tx.commit()?;
+
+    Ok(summary)
+}
+
+#[instrument(skip_all, fields(zettel_id = zettel_id))]
+pub async fn get_parents(db: &Db, zettel_id: i64) -> Result<Vec<Zettel>, TshError> {
+    let mut conn = db.lock().await;
+    let tx = conn.transaction()?;
+    let parents = find_parents(&tx, zettel_id)?;
+    tx.commit()?;
+    Ok(parents)
+}
+
+#[instrument(skip_all)]
+pub async fn get_articles(db: &Db) -> Result<Vec<Article>, TshError> {
+    let mut conn = db.lock().await;
+    let tx = conn.transaction()?;
+    let articles = find_all_articles(&tx)?;
+    tx.commit()?;
+    Ok(articles)
+}
+
+/// Stores the entries of a BibTeX file. Entries whose DOI or key is already stored are
+/// skipped. Returns the number of imported and skipped entries.
+#[instrument(skip_all)]
+pub async fn import_bibtex(db: &Db, input: &str) -> Result<(usize, usize), TshError> {
+    let entries = parse_bibtex(input)?;
+
+    let mut conn = db.lock().await;
+    let tx = conn.transaction()?;
+    let mut n_imported = 0;
+    for metadata in entries.iter() {
+        if db::find_matching_source(&tx, metadata)?.is_none() {
+            db::store_source(&tx, metadata)?;
+            n_imported += 1;
+        }
+    }
+    tx.commit()?;
+    info!(
+        n_imported,
+        n_skipped = entries.len() - n_imported,
+        "Imported BibTeX"
+    );
+
+    Ok((n_imported, entries.len() - n_imported))
+}
+
+#[instrument(skip_all)]
+pub async fn export_bibtex(db: &Db) -> Result<String, TshError> {
+    let sources = get_sources(db).await?;
+    Ok(to_bibtex(&sources))
+}
+
+/// Looks up a DOI and stores the source, unless a source with that DOI already exists.
+#[instrument(skip_all)]
+pub async fn add_source_by_doi(
+    db: &Db,
+    resolver: &impl DoiResolver,
+    doi: &str,
+) -> Result<Source, TshError> {
+    let known = SourceMetadata {
+        doi: Some(normalize_doi(doi)),
+        ..Default::default()
+    };
+    {
+        let mut conn = db.lock().await;
+        let tx = conn.transaction()?;
+        let existing = db::find_matching_source(&tx, &known)?;
+        tx.commit()?;
+        if let Some(source) = existing {
+            info!(source_id = source.id, "DOI is already stored");
+            return Ok(source);
+        }
+    }
+
+    let metadata = resolver.resolve(doi).await?;
+
+    let mut conn = db.lock().await;
+    let tx = conn.transaction()?;
+    // The key from the registry may already be taken by an entry without a DOI.
+    let metadata = match db::find_matching_source(&tx, &metadata)? {
+        Some(_) => SourceMetadata {
+            bibtex_key: None,
+            ..metadata
+        },
+        None => metadata,
+    };
+    let source = db::store_source(&tx, &metadata)?;
+    tx.commit()?;
+    info!(source_id = source.id, "Stored source");
+
+    Ok(source)
+}
+
+#[instrument(skip_all)]
+pub async fn get_sources(db: &Db) -> Result<Vec<Source>, TshError> {
+    let mut conn = db.lock().await;
+    let tx = conn.transaction()?;
+    let sources = db::find_all_sources(&tx)?;
+    tx.commit()?;
+    Ok(sources)
+}
+
+#[instrument(skip_all, fields(zettel_id = zettel_id))]
+pub async fn get_sources_for_zettel(db: &Db, zettel_id: i64) -> Result<Vec<Source>, TshError> {
+    let mut conn = db.lock().await;
+    let tx = conn.transaction()?;
+    let sources = db::find_sources_for_zettel(&tx, zettel_id)?;
+    tx.commit()?;
+    Ok(sources)
+}
+
+#[instrument(skip_all, fields(zettel_id = zettel_id, source_id = source_id))]
+pub async fn attach_source(db: &Db, zettel_id: i64, source_id: i64) -> Result<(), TshError> {
+    let mut conn = db.lock().await;
+    let tx = conn.transaction()?;
+    db::add_source_to_zettel(&tx, zettel_id, source_id)?;
+    tx.commit()?;
+    Ok(())
+}
+
+#[instrument(skip_all, fields(zettel_id = zettel_id, source_id = source_id))]
+pub async fn detach_source(db: &Db, zettel_id: i64, source_id: i64) -> Result<(), TshError> {
+    let mut conn = db.lock().await;
+    let tx = conn.transaction()?;
+    db::delete_source_from_zettel(&tx, zettel_id, source_id)?;
+    tx.commit()?;
+    Ok(())
+}
+
+/// Tag added to every clipped page.
+pub const CLIP_TAG: &str = "clip";
+
+/// Clipped text longer than this is cut off, the zettel is a note about the page and not
+/// a copy of it.
+const MAX_CLIP_CHARS: usize = 4000;
+
+/// How much of a page the chat model gets to see for the summary.
+const MAX_SUMMARY_INPUT_CHARS: usize = 12000;
+
+const CLIP_SYSTEM_PROMPT: &str = "You summarise web pages for a personal zettelkasten. \
+Describe what the page is about and its key points in a few short paragraphs. \
+Answer with the summary only.";
+
+/// Downloads a page and stores its readable text, or a summary by the chat model if one is
+/// configured and `summarise` is set, as a new zettel. The page is stored as a source of
+/// the zettel, which is tagged with `clip` and any fitting existing tags.
+#[instrument(skip_all, fields(summarise = summarise))]
+pub async fn clip_page(
+    db: &Db,
+    llm_config: &LlmConfig,
+    fetcher: &impl PageFetcher,
+    url: &str,
+    summarise: bool,
+) -> Result<Zettel, TshError> {
+    let html = fetcher.fetch(url).await?;
+    let page = extract_page(url, &html);
+    if page.text.is_empty() {
+        return Err(TshError::Source(format!(
+            "No readable text found at {}",
+            url
+        )));
+    }
+
+    let body = if summarise && llm_config.chat_model.is_some() {
+        let mut llm_client = LlmClient::from(llm_config);
+        let prompt = format!(
+            "Title: {}\n\n{}",
+            page.title,
+            truncate_text(&page.text, MAX_SUMMARY_INPUT_CHARS)
+        );
+        llm_client.complete(CLIP_SYSTEM_PROMPT, &prompt).await?
+    } else {
+        truncate_text(&page.text, MAX_CLIP_CHARS)
+    };
+    let content = format!("{}\n\n{}\n\n{}", page.title, body.trim(), page.url);
+    let embedding = LlmClient::from(llm_config).embed(&content).await?;
+
+    // The zettel, its source and the clip tag are stored together, so a failure leaves
+    // no clip without a source behind.
+    let zettel = {
+        let mut conn = db.lock().await;
+        let tx = conn.transaction()?;
+        let zettel = store_zettel(&tx, &content, embedding, vec![])?;
+        let source = match db::find_source_by_url(&tx, &page.url)? {
+            Some(source) => source,
+            None => {
+                let metadata = SourceMetadata {
+                    kind: "online".to_string(),
+                    author: page.author.clone(),
+                    title: page.title.clone(),
+                    year: page.year,
+                    url: Some(page.url.clone()),
+                    ..Default::default()
+                };
+                db::store_source(&tx, &metadata)?
+            }
+        };
+        db::add_source_to_zettel(&tx, zettel.id, source.id)?;
+        add_tag_if_not_exists(&tx, zettel.id, CLIP_TAG)?;
+        tx.commit()?;
+        zettel
+    };
+
+    // New tags need a confirmation, so only the ones already in use are applied.
+    if llm_config.chat_model.is_some() {
+        match suggest_tags(db, llm_config, &zettel).await {
+            Ok(suggestions) => {
+                for suggestion in suggestions.iter().filter(|s| !s.is_new) {
+                    add_tag_to_zettel(db, zettel.id, suggestion.tag.clone()).await?;
+                }
+            }
+            // The clip is stored already, tags can still be added by hand.
+            Err(e) => warn!("Could not suggest tags for the clip: {}", e),
+        }
+    }
+
+    info!(zettel_id = zettel.id, "Clipped page");
+    Ok(zettel)
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        Json, Router,
+        http::{StatusCode, header::CONTENT_TYPE},
+        routing::{get, post},
+    };
+    use std::sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    };
+
+    use super::*;
+    use crate::{
+        clip::HttpPageFetcher,
+        test_support::{
+            chat_completion, embedding_response, insert_zettel, serve_stub, stub_llm_config,
+            temp_db,
+        },
+    };
+
+    fn tags(tags: &[&str]) -> Vec<String> {
+        tags.iter().map(|tag| tag.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_tag_suggestions_cleans_up_decorated_answers() {
+        let cases = [
+            ("rust\nsqlite", vec![("rust", false), ("sqlite", false)]),
+            (
+                "- rust\n* #sqlite",
+                vec![("rust", false), ("sqlite", false)],
+            ),
+            ("rust, sqlite", vec![("rust", false), ("sqlite", false)]),
+            ("Tags:\nrust", vec![("rust", false)]),
+            ("two words\nrust", vec![("rust", false)]),
+            ("rust\nrust", vec![("rust", false)]),
+            ("", vec![]),
+        ];
+        for (response, expected) in cases {
+            let suggestions = parse_tag_suggestions(response, &tags(&["rust", "sqlite"]), &[], 2);
+            let actual: Vec<(&str, bool)> = suggestions
+                .iter()
+                .map(|s| (s.tag.as_str(), s.is_new))
+                .collect();
+            assert_eq!(actual, expected, "response {:?}", response);
+        }
+    }
+
+    #[test]
+    fn parse_tag_suggestions_limits_new_tags_and_skips_assigned_ones() {
+        let suggestions = parse_tag_suggestions(
+            "rust\nnew-a\nnew-b\nnew-c\nsqlite",
+            &tags(&["rust", "sqlite"]),
+            &tags(&["sqlite"]),
+            2,
+        );
+        let actual: Vec<(&str, bool, bool)> = suggestions
+            .iter()
+            .map(|s| (s.tag.as_str(), s.is_new, s.accepted))
+            .collect();
+        assert_eq!(
+            actual,
+            vec![
+                ("rust", false, true),
+                ("new-a", true, false),
+                ("new-b", true, false)
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_citations_collects_bracketed_ids_once() {
+        let cases = [
+            ("As noted [12].", vec![12]),
+            ("See [[12]]", vec![12]),
+            ("[12] and [34, 12], again [34]", vec![12, 34]),
+            ("[12, x, 56]", vec![12, 56]),
+            ("Unclosed [12 and [34]", vec![34]),
+            ("12] [] [abc] [1 2] ][", vec![]),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(parse_citations(text), expected, "{:?}", text);
+        }
+    }
+
+    #[tokio::test]
+    async fn ask_cites_only_the_notes_it_was_given() {
+        let (_dir, db) = temp_db().await;
+        let embeddings_base = serve_stub(
+            Router::new().route("/embeddings", post(|| async { Json(embedding_response()) })),
+        )
+        .await;
+        let zettel = add_zettel(
+            &db,
+            &stub_llm_config(&embeddings_base),
+            &[],
+            &[],
+            "Borrowing rules",
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        let text = format!("Yes [{}], see also [{}].", zettel.id, zettel.id + 100);
+        let api_base = serve_stub(
+            Router::new()
+                .route("/embeddings", post(|| async { Json(embedding_response()) }))
+                .route(
+                    "/chat/completions",
+                    post(move || async move { Json(chat_completion(&text)) }),
+                ),
+        )
+        .await;
+
+        let answer = ask(&db, &stub_llm_config(&api_base), "Rules?")
+            .await
+            .unwrap();
+
+        let cited: Vec<i64> = answer.citations.iter().map(|zettel| zettel.id).collect();
+        assert_eq!(cited, [zettel.id]);
+    }
+
+    #[tokio::test]
+    async fn ask_needs_a_chat_model() {
+        let (_dir, db) = temp_db().await;
+        let llm_config = LlmConfig {
+            chat_model: None,
+            ..stub_llm_config("http://127.0.0.1:1")
+        };
+
+        let result = ask(&db, &llm_config, "Rules?").await;
+
+        assert!(
+            matches!(&result, Err(TshError::Config(msg)) if msg == "No chat_model configured"),
+            "{:?}",
+            result.map(|answer| answer.text)
+        );
+    }
+
+    #[tokio::test]
+    async fn suggest_tags_asks_an_openai_compatible_server() {
+        let api_base = serve_stub(Router::new().route(
+            "/chat/completions",
+            post(|| async { Json(chat_completion("- rust\n- databases\n- lifetimes")) }),
+        ))
+        .await;
+        let (_dir, db) = temp_db().await;
+        let tagged = insert_zettel(&db, "Borrowing rules").await;
+        add_tag_to_zettel(&db, tagged, "rust".to_string())
+            .await
+            .unwrap();
+        let id = insert_zettel(&db, "Connection pools in rusqlite").await;
+        let zettel = get_zettel(&db, id).await.unwrap();
+
+        let suggestions = suggest_tags(&db, &stub_llm_config(&api_base), &zettel)
+            .await
+            .unwrap();
+
+        let actual: Vec<(&str, bool)> = suggestions
+            .iter()
+            .map(|s| (s.tag.as_str(), s.is_new))
+            .collect();
+        assert_eq!(
+            actual,
+            vec![("rust", false), ("databases", true), ("lifetimes", true)]
+        );
+    }
+
+    const ARTICLE: &str = include_str!("../fixtures/clip/article.html");
+
+    /// Serves the article fixture next to an OpenAI-compatible API whose embeddings
+    /// endpoint answers with `embeddings_status`.
+    async fn stub_api(embeddings_status: StatusCode) -> String {
+        serve_stub(
+            Router::new()
+                .route(
+                    "/article",
+                    get(|| async { ([(CONTENT_TYPE, "text/html")], ARTICLE) }),
+                )
+                .route(
+                    "/embeddings",
+                    post(move || async move { (embeddings_status, Json(embedding_response())) }),
+                )
+                .route(
+                    "/chat/completions",
+                    post(|| async { Json(chat_completion("reading")) }),
+                ),
+        )
+        .await
+    }
+
+    async fn count(db: &Db, table: &str) -> i64 {
+        let conn = db.lock().await;
+        conn.query_row(&format!("select count(*) from {}", table), [], |row| {
+            row.get(0)
+        })
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn clip_page_stores_the_page_with_its_source_and_tag() {
+        let base_url = stub_api(StatusCode::OK).await;
+        let (_dir, db) = temp_db().await;
+        let url = format!("{}/article", base_url);
+
+        let zettel = clip_page(
+            &db,
+            &stub_llm_config(&base_url),
+            &HttpPageFetcher::new(),
+            &url,
+            false,
+        )
+        .await
+        .unwrap();
+
+        assert!(zettel.content.starts_with("Zettelkasten & Größe\n\n"));
+        assert!(zettel.content.ends_with(&url));
+        let sources = get_sources_for_zettel(&db, zettel.id).await.unwrap();
+        assert_eq!(sources.len(), 1);
+        assert_eq!(sources[0].metadata.url.as_deref(), Some(url.as_str()));
+        assert_eq!(sources[0].metadata.year, Some(2021));
+        let tags: Vec<String> = get_tags(&db, zettel.id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|tag| tag.tag)
+            .collect();
+        assert_eq!(tags, vec![CLIP_TAG.to_string()]);
+    }
+
+    #[tokio::test]
+    async fn clip_page_stores_nothing_when_embedding_fails() {
+        let base_url = stub_api(StatusCode::BAD_REQUEST).await;
+        let (_dir, db) = temp_db().await;
+
+        let result = clip_page(
+            &db,
+            &stub_llm_config(&base_url),
+            &HttpPageFetcher::new(),
+            &format!("{}/article", base_url),
+            false,
+        )
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(count(&db, "zettel").await, 0);
+        assert_eq!(count(&db, "source").await, 0);
+    }
+
+    #[tokio::test]
+    async fn add_zettel_stores_the_tags_with_the_zettel() {
+        let base_url = stub_api(StatusCode::OK).await;
+        let (_dir, db) = temp_db().await;
+
+        let zettel = add_zettel(
+            &db,
+            &stub_llm_config(&base_url),
+            &[],
+            &tags(&["journal/2025-01-02", "daily"]),
+            "# 2025-01-02\n\nWrote tests.",
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+        let stored: Vec<String> = get_tags(&db, zettel.id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|tag| tag.tag)
+            .collect();
+        assert_eq!(stored.len(), 2);
+        assert!(stored.contains(&"daily".to_string()));
+    }
+
+    #[tokio::test]
+    async fn amend_zettel_skips_content_equal_to_the_stored_one() {
+        let embeddings = Arc::new(AtomicUsize::new(0));
+        let counter = embeddings.clone();
+        let base_url = serve_stub(Router::new().route(
+            "/embeddings",
+            post(move || async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Json(embedding_response())
+            }),
+        ))
+        .await;
+        let llm_config = stub_llm_config(&base_url);
+        let (_dir, db) = temp_db().await;
+        let zettel = add_zettel(&db, &llm_config, &[], &[], "First")
+            .await
+            .unwrap()
+            .unwrap();
+
+        let amended = amend_zettel(&db, &llm_config, &zettel, "Second")
+            .await
+            .unwrap();
+        assert_eq!(
+            amended.map(|zettel| zettel.content).as_deref(),
+            Some("Second")
+        );
+        // `zettel` still has the content from before the amendment.
+        for content in ["Second", ""] {
+            let amended = amend_zettel(&db, &llm_config, &zettel, content)
+                .await
+                .unwrap();
+            assert!(amended.is_none(), "{:?}", content);
+        }
+
+        assert_eq!(embeddings.load(Ordering::SeqCst), 2);
+        let revisions: Vec<String> = get_revisions(&db, zettel.id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|revision| revision.content)
+            .collect();
+        assert_eq!(revisions, ["First"]);
+    }
+
+    #[tokio::test]
+    async fn journal_days_come_only_from_dated_journal_tags() {
+        let (_dir, db) = temp_db().await;
+        let id = insert_zettel(&db, "Entries and notes").await;
+        for tag in [
+            "journal/2025-01-02",
+            "journal/notes",
+            "journal/2024-12-31",
+            "daily",
+            "notes/journal/2025-01-03",
+        ] {
+            add_tag_to_zettel(&db, id, tag.to_string()).await.unwrap();
+        }
+
+        let days = get_journal_days(&db).await.unwrap();
+
+        assert_eq!(
+            days,
+            [
+                NaiveDate::from_ymd_opt(2025, 1, 2).unwrap(),
+                NaiveDate::from_ymd_opt(2024, 12, 31).unwrap(),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn reviewed_and_archived_zettels_leave_the_review_queue() {
+        let (_dir, db) = temp_db().await;
+        let mut ids = vec![];
+        for content in ["Reviewed", "Archived", "Still due"] {
+            let id = insert_zettel(&db, content).await;
+            let created_at = Utc::now().timestamp_millis() - 30 * DAY_MS;
+            db.lock()
+                .await
+                .execute(
+                    "update zettel set created_at = ? where id = ?",
+                    [created_at, id],
+                )
+                .unwrap();
+            ids.push(id);
+        }
+        insert_zettel(&db, "Too recent").await;
+
+        review_zettel(&db, ids[0], ReviewRating::Good)
+            .await
+            .unwrap();
+        archive_zettel(&db, ids[1]).await.unwrap();
+
+        let queue: Vec<i64> = get_review_queue(&db, false)
+            .await
+            .unwrap()
+            .iter()
+            .map(|item| item.zettel.id)
+            .collect();
+        assert_eq!(queue, vec![ids[2]]);
+    }
+}
@@ -0,0 +1,412 @@
+use std::collections::HashSet;
+
+use crate::{
+    doi::normalize_doi,
+    error::TshError,
+    model::{Source, SourceMetadata, last_name},
+};
+
+/// Reads the entries of a BibTeX file. Only the fields tsh stores are kept, `@string`
+/// macros are not expanded and `@comment` and `@preamble` blocks are skipped.
+pub fn parse_bibtex(input: &str) -> Result<Vec<SourceMetadata>, TshError> {
+    let mut parser = Parser {
+        chars: input.chars().collect(),
+        pos: 0,
+        line: 1,
+    };
+    let mut entries: Vec<SourceMetadata> = vec![];
+
+    while parser.skip_to('@') {
+        parser.next();
+        let kind = parser.read_identifier().to_lowercase();
+        parser.skip_whitespace();
+        let close = match parser.next() {
+            Some('{') => '}',
+            Some('(') => ')',
+            _ => continue, // an @ in free text between entries
+        };
+
+        if ["comment", "preamble", "string"].contains(&kind.as_str()) {
+            parser.back();
+            parser.read_balanced()?;
+            continue;
+        }
+
+        let line = parser.line();
+        let key = parser.read_until(&[',', close]).trim().to_string();
+        let mut metadata = SourceMetadata {
+            kind,
+            bibtex_key: Some(key.clone()).filter(|key| !key.is_empty()),
+            ..Default::default()
+        };
+        let mut title: Option<String> = None;
+
+        loop {
+            parser.skip_whitespace();
+            match parser.next() {
+                Some(',') => continue,
+                Some(c) if c == close => break,
+                None => return Err(parser.error(line, "entry is not closed")),
+                Some(_) => parser.back(),
+            }
+
+            let name = parser.read_identifier().to_lowercase();
+            parser.skip_whitespace();
+            if name.is_empty() || parser.next() != Some('=') {
+                return Err(parser.error(parser.line(), "expected `field = value`"));
+            }
+            let value = parser.read_value(close)?;
+
+            match name.as_str() {
+                "author" => metadata.author = Some(value),
+                "title" => title = Some(value),
+                "year" => metadata.year = value.get(..4).and_then(|year| year.parse().ok()),
+                "doi" => metadata.doi = Some(normalize_doi(&value)),
+                "url" => metadata.url = Some(value),
+                "isbn" => metadata.isbn = Some(value),
+                _ => {}
+            }
+        }
+
+        metadata.title = title.ok_or_else(|| {
+            TshError::Source(format!(
+                "BibTeX entry '{}' on line {} has no title",
+                key, line
+            ))
+        })?;
+        entries.push(metadata);
+    }
+
+    Ok(entries)
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    /// The line `pos` is on, kept up to date so errors can name it cheaply.
+    line: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<char> {
+        let c = self.chars.get(self.pos).copied();
+        self.pos += 1;
+        if c == Some('\n') {
+            self.line += 1;
+        }
+        c
+    }
+
+    /// Steps back over the character `next` just returned.
+    fn back(&mut self) {
+        self.pos -= 1;
+        if self.chars.get(self.pos) == Some(&'\n') {
+            self.line -= 1;
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn line(&self) -> usize {
+        self.line
+    }
+
+    fn error(&self, line: usize, message: &str) -> TshError {
+        TshError::Source(format!("Invalid BibTeX on line {}: {}", line, message))
+    }
+
+    fn skip_to(&mut self, target: char) -> bool {
+        while let Some(c) = self.peek() {
+            if c == target {
+                return true;
+            }
+            self.next();
+        }
+        false
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.next();
+        }
+    }
+
+    fn read_identifier(&mut self) -> String {
+        let mut identifier = String::new();
+        while let Some(c) = self.peek()
+            && (c.is_alphanumeric() || "_-:.".contains(c))
+        {
+            identifier.push(c);
+            self.next();
+        }
+        identifier
+    }
+
+    fn read_until(&mut self, stops: &[char]) -> String {
+        let mut text = String::new();
+        while let Some(c) = self.peek()
+            && !stops.contains(&c)
+        {
+            text.push(c);
+            self.next();
+        }
+        text
+    }
+
+    /// Reads a `{...}` group including nested braces and returns its inner text.
+    fn read_balanced(&mut self) -> Result<String, TshError> {
+        let line = self.line();
+        let open = self.next();
+        let close = if open == Some('(') { ')' } else { '}' };
+        let mut depth = 0;
+        let mut text = String::new();
+        loop {
+            match self.next() {
+                Some('{') => depth += 1,
+                Some('}') if depth > 0 => depth -= 1,
+                Some(c) if c == close && depth == 0 => return Ok(text),
+                Some(_) => {}
+                None => return Err(self.error(line, "unbalanced braces")),
+            }
+            text.push(self.chars[self.pos - 1]);
+        }
+    }
+
+    fn read_quoted(&mut self) -> Result<String, TshError> {
+        let line = self.line();
+        self.next();
+        let mut depth = 0;
+        let mut text = String::new();
+        loop {
+            match self.next() {
+                Some('{') => depth += 1,
+                Some('}') => depth -= 1,
+                Some('"') if depth == 0 => return Ok(text),
+                Some(_) => {}
+                None => return Err(self.error(line, "unterminated string")),
+            }
+            text.push(self.chars[self.pos - 1]);
+        }
+    }
+
+    /// Reads a field value, which may be several parts joined with `#`.
+    fn read_value(&mut self, close: char) -> Result<String, TshError> {
+        let mut value = String::new();
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some('{') => value.push_str(&self.read_balanced()?),
+                Some('"') => value.push_str(&self.read_quoted()?),
+                Some(_) => value.push_str(self.read_until(&[',', '#', close]).trim()),
+                None => return Err(self.error(self.line(), "missing value")),
+            }
+            self.skip_whitespace();
+            if self.peek() == Some('#') {
+                self.next();
+            } else {
+                return Ok(clean_value(&value));
+            }
+        }
+    }
+}
+
+/// Accents as written in LaTeX, e.g. `\"o` for `ö`.
+const LATEX_ACCENTS: [(char, &str, &str); 5] = [
+    ('"', "aeiouyAEIOU", "äëïöüÿÄËÏÖÜ"),
+    ('\'', "aeiouyAEIOUY", "áéíóúýÁÉÍÓÚÝ"),
+    ('`', "aeiouAEIOU", "àèìòùÀÈÌÒÙ"),
+    ('^', "aeiouAEIOU", "âêîôûÂÊÎÔÛ"),
+    ('~', "anoANO", "ãñõÃÑÕ"),
+];
+
+/// Drops the braces that protect capitalization and undoes the common escapes.
+fn clean_value(value: &str) -> String {
+    let mut value = value
+        .replace(['{', '}'], "")
+        .replace("\\&", "&")
+        .replace("\\%", "%")
+        .replace("\\_", "_")
+        .replace("\\ss", "ß");
+    for (accent, plain, accented) in LATEX_ACCENTS {
+        for (plain, accented) in plain.chars().zip(accented.chars()) {
+            value = value.replace(&format!("\\{}{}", accent, plain), &accented.to_string());
+        }
+    }
+    value.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+fn escape_value(value: &str) -> String {
+    value.replace('&', "\\&").replace('%', "\\%")
+}
+
+/// Writes the sources as BibTeX. Sources without a key get one from the first author's
+/// last name and the year, made unique with a letter suffix.
+pub fn to_bibtex(sources: &[Source]) -> String {
+    let mut used_keys: HashSet<String> = sources
+        .iter()
+        .filter_map(|source| source.metadata.bibtex_key.clone())
+        .collect();
+
+    sources
+        .iter()
+        .map(|source| {
+            let metadata = &source.metadata;
+            let key = match &metadata.bibtex_key {
+                Some(key) => key.clone(),
+                None => {
+                    let key = unique_key(&generate_key(source), &used_keys);
+                    used_keys.insert(key.clone());
+                    key
+                }
+            };
+
+            let fields = [
+                ("author", metadata.author.as_deref().map(escape_value)),
+                ("title", Some(escape_value(&metadata.title))),
+                ("year", metadata.year.map(|year| year.to_string())),
+                ("doi", metadata.doi.clone()),
+                ("url", metadata.url.clone()),
+                ("isbn", metadata.isbn.clone()),
+            ];
+            let fields: Vec<String> = fields
+                .into_iter()
+                .filter_map(|(name, value)| {
+                    value.map(|value| format!("  {} = {{{}}}", name, value))
+                })
+                .collect();
+
+            format!("@{}{{{},\n{}\n}}\n", metadata.kind, key, fields.join(",\n"))
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+fn generate_key(source: &Source) -> String {
+    let metadata = &source.metadata;
+    let author: String = metadata
+        .author
+        .as_deref()
+        .and_then(|author| author.split(" and ").next())
+        .map(last_name)
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .collect::<String>()
+        .to_lowercase();
+    match (author.is_empty(), metadata.year) {
+        (false, Some(year)) => format!("{}{}", author, year),
+        (false, None) => author,
+        (true, _) => format!("source{}", source.id),
+    }
+}
+
+fn unique_key(key: &str, used_keys: &HashSet<String>) -> String {
+    if !used_keys.contains(key) {
+        return key.to_string();
+    }
+    ('a'..='z')
+        .map(|suffix| format!("{}{}", key, suffix))
+        .find(|candidate| !used_keys.contains(candidate))
+        .unwrap_or_else(|| format!("{}_{}", key, used_keys.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_bibtex_reads_the_stored_fields() {
+        let input = r#"
+% exported from a reference manager
+@comment{ignored, title = {Not an entry}}
+@Article{doe2020,
+  author = {Doe, Jane and Roe, Richard},
+  title = {{The} Art of {\"O}sterreich \& Co},
+  year = {2020},
+  doi = {https://doi.org/10.1000/XYZ123},
+  journal = "Journal " # "of Notes",
+}
+
+@book(smith1999, title = "Zettels", isbn = 978-3-16-148410-0, year = 1999-05)
+"#;
+        let entries = parse_bibtex(input).unwrap();
+
+        assert_eq!(
+            entries,
+            vec![
+                SourceMetadata {
+                    kind: "article".to_string(),
+                    bibtex_key: Some("doe2020".to_string()),
+                    author: Some("Doe, Jane and Roe, Richard".to_string()),
+                    title: "The Art of Österreich & Co".to_string(),
+                    year: Some(2020),
+                    doi: Some("10.1000/xyz123".to_string()),
+                    url: None,
+                    isbn: None,
+                },
+                SourceMetadata {
+                    kind: "book".to_string(),
+                    bibtex_key: Some("smith1999".to_string()),
+                    title: "Zettels".to_string(),
+                    year: Some(1999),
+                    isbn: Some("978-3-16-148410-0".to_string()),
+                    ..Default::default()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_bibtex_reports_the_line_of_errors() {
+        let cases = [
+            ("@misc{a,\n  title = {Unbalanced\n", "line 2"),
+            ("\n\n@misc{a,\n  author = {Doe}\n}", "line 3"),
+            (
+                "@misc{a, title = {A}}\n\n@misc{b,\n  title {B}\n}",
+                "line 4",
+            ),
+        ];
+        for (input, line) in cases {
+            let error = parse_bibtex(input).unwrap_err().to_string();
+            assert!(error.contains(line), "{:?} should mention {}", error, line);
+        }
+    }
+
+    #[test]
+    fn parse_bibtex_handles_large_files() {
+        let entry = "@misc{key,\n  title = {A title},\n  year = {2001}\n}\n";
+        let input = entry.repeat(20_000);
+        assert_eq!(parse_bibtex(&input).unwrap().len(), 20_000);
+    }
+
+    #[test]
+    fn to_bibtex_generates_unique_keys_and_parses_back() {
+        let source = |id, author: &str| Source {
+            id,
+            metadata: SourceMetadata {
+                kind: "book".to_string(),
+                author: Some(author.to_string()),
+                title: "Notes & Boxes".to_string(),
+                year: Some(1981),
+                ..Default::default()
+            },
+            created_at: 0,
+        };
+        let sources = [source(1, "Niklas Luhmann"), source(2, "Luhmann, Niklas")];
+
+        let bibtex = to_bibtex(&sources);
+        let entries = parse_bibtex(&bibtex).unwrap();
+
+        let keys: Vec<Option<String>> = entries.iter().map(|e| e.bibtex_key.clone()).collect();
+        assert_eq!(
+            keys,
+            vec![
+                Some("luhmann1981".to_string()),
+                Some("luhmann1981a".to_string())
+            ]
+        );
+        assert!(entries.iter().all(|e| e.title == "Notes & Boxes"));
+    }
+}
@@ -0,0 +1,317 @@
+use clap::{Parser, Subcommand};
+use std::io::{self, IsTerminal, Write};
+
+use crate::{
+    api::{
+        add_source_by_doi, ask, clip_page, export_bibtex, get_sources, import_bibtex, save_answer,
+    },
+    clip::HttpPageFetcher,
+    config::{ResolvedConfig, Vault},
+    db::{Db, is_plaintext, reencrypt},
+    doctor::{Check, audit, repair},
+    doi::HttpDoiResolver,
+    error::TshError,
+    logging::{log_dir, read_recent_log_lines},
+    tui::app::LlmConfig,
+};
+
+#[derive(Debug, Parser)]
+#[command(version, about = "A simple tool to help you think.")]
+pub struct Cli {
+    /// Path to the config file [env: TSH_CONFIG]
+    #[arg(short, long, global = true)]
+    pub config: Option<String>,
+
+    /// Name of the vault to open, as configured under [vaults.<name>]
+    #[arg(long, global = true)]
+    pub vault: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Option<CliCommand>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum CliCommand {
+    /// Answer a question based on your notes
+    Ask {
+        question: String,
+        /// Store the answer as a new zettel with the cited zettels as parents
+        #[arg(long)]
+        save: bool,
+    },
+    /// Store a web page as a zettel, with the page as its source
+    Clip {
+        url: String,
+        /// Store the page's text even if a chat model could summarise it
+        #[arg(long)]
+        extract: bool,
+    },
+    /// Print the most recent log entries
+    Log {
+        /// Number of lines to print
+        #[arg(short = 'n', long, default_value_t = 50)]
+        lines: usize,
+    },
+    /// Check the database for inconsistencies and offer to repair them
+    Doctor {
+        /// Repair everything that was found without asking
+        #[arg(long)]
+        fix: bool,
+    },
+    /// Encrypt the vault's database with a passphrase
+    Encrypt,
+    /// Remove the encryption from the vault's database
+    Decrypt,
+    /// Serve the vault as a JSON API on localhost
+    Serve {
+        #[arg(short, long, default_value_t = 7777)]
+        port: u16,
+    },
+    /// Run a language server for zettel references and tags on stdin/stdout
+    Lsp,
+    /// Manage the bibliography
+    Sources {
+        #[command(subcommand)]
+        command: SourcesCommand,
+    },
+    /// Inspect the configuration
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum SourcesCommand {
+    /// List the stored sources
+    List,
+    /// Add the entries of a BibTeX file, skipping ones already stored
+    Import { path: String },
+    /// Write all sources as BibTeX
+    Export {
+        /// File to write to instead of stdout
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+    /// Look up a DOI and store its metadata
+    Doi { doi: String },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigCommand {
+    /// Print the effective settings and where each one comes from
+    Show,
+}
+
+pub async fn run_ask(
+    db: &Db,
+    llm_config: &LlmConfig,
+    question: &str,
+    save: bool,
+) -> Result<(), TshError> {
+    let answer = ask(db, llm_config, question).await?;
+
+    println!("{}", answer.text);
+    if !answer.citations.is_empty() {
+        println!();
+        for zettel in answer.citations.iter() {
+            println!(
+                "[{}] {}  {}",
+                zettel.id,
+                zettel.get_datetime_string(),
+                zettel.get_shim(60)
+            );
+        }
+    }
+
+    if save {
+        let zettel = save_answer(db, llm_config, &answer).await?;
+        println!();
+        println!("Saved answer as zettel {}", zettel.id);
+    }
+
+    Ok(())
+}
+
+pub async fn run_clip(
+    db: &Db,
+    llm_config: &LlmConfig,
+    url: &str,
+    extract: bool,
+) -> Result<(), TshError> {
+    let zettel = clip_page(db, llm_config, &HttpPageFetcher::new(), url, !extract).await?;
+    println!("Clipped as zettel {}: {}", zettel.id, zettel.get_shim(60));
+    Ok(())
+}
+
+pub fn run_log(data_dir: &str, lines: usize) -> Result<(), TshError> {
+    for line in read_recent_log_lines(&log_dir(data_dir), lines)? {
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+pub fn run_config_show(resolved: &ResolvedConfig) -> Result<(), TshError> {
+    let settings = resolved.settings();
+    let key_width = settings
+        .iter()
+        .map(|(key, _, _)| key.len())
+        .max()
+        .unwrap_or(0);
+    for (key, value, source) in settings {
+        println!("{:<key_width$}  {}  ({})", key, value, source);
+    }
+
+    println!();
+    println!("vaults");
+    for (name, db_path) in resolved.vault_db_paths()? {
+        println!("  {:<width$}  {}", name, db_path, width = key_width - 2);
+    }
+    Ok(())
+}
+
+pub async fn run_sources(
+    db: &Db,
+    llm_config: &LlmConfig,
+    command: SourcesCommand,
+) -> Result<(), TshError> {
+    match command {
+        SourcesCommand::List => {
+            for source in get_sources(db).await? {
+                println!("{:>4}  {}", source.id, source.get_citation());
+            }
+        }
+        SourcesCommand::Import { path } => {
+            let input = std::fs::read_to_string(&path)?;
+            let (n_imported, n_skipped) = import_bibtex(db, &input).await?;
+            println!(
+                "Imported {} sources, skipped {} already stored",
+                n_imported, n_skipped
+            );
+        }
+        SourcesCommand::Export { output } => {
+            let bibtex = export_bibtex(db).await?;
+            match output {
+                Some(path) => std::fs::write(path, bibtex)?,
+                None => print!("{}", bibtex),
+            }
+        }
+        SourcesCommand::Doi { doi } => {
+            let resolver = HttpDoiResolver::new(&llm_config.doi_resolver_url);
+            let source = add_source_by_doi(db, &resolver, &doi).await?;
+            println!("{:>4}  {}", source.id, source.get_citation());
+        }
+    }
+    Ok(())
+}
+
+/// Number of items listed per finding, the rest is summarised.
+const DOCTOR_MAX_ITEMS: usize = 10;
+
+/// Asks a yes/no question on the terminal, defaulting to no.
+fn confirm(question: &str) -> Result<bool, TshError> {
+    print!("{} [y/N] ", question);
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+/// Prints what the checks found and offers to fix each category.
+/// With `fix`, everything is repaired without asking. Without a terminal to ask on,
+/// nothing is changed.
+pub async fn run_doctor(db: &Db, llm_config: &LlmConfig, fix: bool) -> Result<(), TshError> {
+    let findings = audit(db).await?;
+    if findings.is_empty() {
+        println!("No problems found");
+        return Ok(());
+    }
+
+    let interactive = io::stdin().is_terminal();
+    let mut skipped: Vec<Check> = vec![];
+    for finding in findings.iter() {
+        println!("{} ({})", finding.check, finding.items.len());
+        for item in finding.items.iter().take(DOCTOR_MAX_ITEMS) {
+            println!("  {}", item);
+        }
+        if finding.items.len() > DOCTOR_MAX_ITEMS {
+            println!("  ... and {} more", finding.items.len() - DOCTOR_MAX_ITEMS);
+        }
+
+        let should_fix = if fix {
+            true
+        } else if interactive {
+            confirm(&format!(
+                "Fix? This will {}.",
+                finding.check.repair_description()
+            ))?
+        } else {
+            false
+        };
+        if should_fix {
+            let n_fixed = repair(db, llm_config, finding.check).await?;
+            println!("Fixed {}", n_fixed);
+        } else {
+            skipped.push(finding.check);
+        }
+        println!();
+    }
+
+    if !skipped.is_empty() && !interactive {
+        println!("Run `tsh doctor --fix` to repair");
+    }
+    // A restored zettel, for example, still needs an embedding.
+    if skipped.len() < findings.len()
+        && audit(db)
+            .await?
+            .iter()
+            .any(|finding| !skipped.contains(&finding.check))
+    {
+        println!("The repairs revealed further problems, run `tsh doctor` again");
+    }
+
+    Ok(())
+}
+
+pub async fn run_encrypt(vault: &Vault) -> Result<(), TshError> {
+    if !is_plaintext(&vault.db_path)? {
+        return Err(TshError::Encryption(format!(
+            "{} is already encrypted",
+            vault.db_path
+        )));
+    }
+    // Creates and migrates the database if this is the first run.
+    drop(Db::open(&vault.db_path, None).await?);
+
+    let passphrase = vault.passphrase(true)?;
+    reencrypt(&vault.db_path, None, Some(&passphrase))?;
+
+    println!("Encrypted {}", vault.db_path);
+    if !vault.encrypted {
+        println!(
+            "Set `encrypted = true` for the vault '{}' in your config to open it",
+            vault.name
+        );
+    }
+    Ok(())
+}
+
+pub fn run_decrypt(vault: &Vault) -> Result<(), TshError> {
+    if is_plaintext(&vault.db_path)? {
+        return Err(TshError::Encryption(format!(
+            "{} is not encrypted",
+            vault.db_path
+        )));
+    }
+    let passphrase = vault.passphrase(false)?;
+    reencrypt(&vault.db_path, Some(&passphrase), None)?;
+
+    println!("Decrypted {}", vault.db_path);
+    if vault.encrypted {
+        println!(
+            "Remove `encrypted = true` for the vault '{}' from your config to open it",
+            vault.name
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,437 @@
+use reqwest::header::{CONTENT_TYPE, USER_AGENT};
+use tracing::{info, instrument};
+
+use crate::{error::TshError, model::Page};
+
+/// Downloads web pages for clipping.
+#[trait_variant::make(Send)]
+pub trait PageFetcher {
+    /// Returns the HTML of the page at `url`.
+    async fn fetch(&self, url: &str) -> Result<String, TshError>;
+}
+
+pub struct HttpPageFetcher {
+    client: reqwest::Client,
+}
+
+impl HttpPageFetcher {
+    pub fn new() -> Self {
+        Self {
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+impl Default for HttpPageFetcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PageFetcher for HttpPageFetcher {
+    #[instrument(skip_all)]
+    async fn fetch(&self, url: &str) -> Result<String, TshError> {
+        let request_error = |e: reqwest::Error| TshError::Source(format!("{}: {}", url, e));
+
+        let response = self
+            .client
+            .get(url)
+            .header(USER_AGENT, concat!("tsh/", env!("CARGO_PKG_VERSION")))
+            .send()
+            .await
+            .map_err(request_error)?;
+        if !response.status().is_success() {
+            return Err(TshError::Source(format!(
+                "Could not fetch {}: {}",
+                url,
+                response.status()
+            )));
+        }
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("text/html")
+            .to_string();
+        if !content_type.contains("html") && !content_type.starts_with("text/") {
+            return Err(TshError::Source(format!(
+                "{} is not a web page ({})",
+                url, content_type
+            )));
+        }
+
+        let html = response.text().await.map_err(request_error)?;
+        info!(n_bytes = html.len(), "Fetched page");
+        Ok(html)
+    }
+}
+
+/// Elements whose text is not part of the readable content.
+const SKIPPED_ELEMENTS: [&str; 12] = [
+    "script", "style", "noscript", "svg", "nav", "header", "footer", "form", "aside", "template",
+    "iframe", "button",
+];
+
+/// Elements that start a new paragraph in the extracted text.
+const BLOCK_ELEMENTS: [&str; 22] = [
+    "p",
+    "div",
+    "br",
+    "li",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "tr",
+    "section",
+    "article",
+    "main",
+    "blockquote",
+    "pre",
+    "ul",
+    "ol",
+    "table",
+    "hr",
+    "figcaption",
+    "dd",
+];
+
+/// Extracts the title, metadata and readable text of a page. The text is taken from the
+/// `<article>` or `<main>` element if there is one, without navigation, scripts and the like.
+pub fn extract_page(url: &str, html: &str) -> Page {
+    let mut title = None;
+    let mut author = None;
+    let mut description = None;
+    let mut published = None;
+
+    for (tag, _) in find_tags(html, "meta") {
+        let key = attribute(tag, "property").or_else(|| attribute(tag, "name"));
+        let Some(content) = attribute(tag, "content").map(|content| decode_entities(&content))
+        else {
+            continue;
+        };
+        match key.as_deref().map(str::to_lowercase).as_deref() {
+            Some("og:title") => title = Some(content),
+            Some("author" | "article:author") => author = Some(content),
+            Some("description" | "og:description") if description.is_none() => {
+                description = Some(content)
+            }
+            Some("article:published_time") => published = Some(content),
+            _ => {}
+        }
+    }
+
+    let title = title
+        .or_else(|| element_content(html, "title").map(|title| collapse(&decode_entities(title))))
+        .filter(|title| !title.is_empty())
+        .unwrap_or_else(|| url.to_string());
+
+    let body = element_content(html, "article")
+        .or_else(|| element_content(html, "main"))
+        .or_else(|| element_content(html, "body"))
+        .unwrap_or(html);
+
+    Page {
+        url: url.to_string(),
+        title,
+        author,
+        description,
+        year: published.and_then(|date| date.get(..4).and_then(|year| year.parse().ok())),
+        text: readable_text(body),
+    }
+}
+
+/// Finds the opening tags of an element. Returns each tag without the angle brackets,
+/// together with the position right after it.
+fn find_tags<'a>(html: &'a str, name: &str) -> Vec<(&'a str, usize)> {
+    let lowercase = html.to_ascii_lowercase();
+    let needle = format!("<{}", name);
+    let mut tags = vec![];
+    let mut pos = 0;
+    while let Some(start) = lowercase[pos..].find(&needle).map(|i| pos + i) {
+        let after_name = start + needle.len();
+        let Some(end) = html[after_name..].find('>').map(|i| after_name + i) else {
+            break;
+        };
+        if html[after_name..].starts_with(|c: char| c.is_whitespace() || c == '>' || c == '/') {
+            tags.push((&html[start + 1..end], end + 1));
+        }
+        pos = end;
+    }
+    tags
+}
+
+/// The value of an attribute in an opening tag, e.g. `content` in `meta content="..."`.
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let lowercase = tag.to_ascii_lowercase();
+    let mut pos = 0;
+    while let Some(start) = lowercase[pos..].find(name).map(|i| pos + i) {
+        pos = start + name.len();
+        let preceded_by_space = tag[..start].ends_with(char::is_whitespace);
+        let rest = tag[pos..].trim_start();
+        if !preceded_by_space || !rest.starts_with('=') {
+            continue;
+        }
+        let value = rest[1..].trim_start();
+        return match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => value[1..].split(quote).next().map(str::to_string),
+            _ => value
+                .split(|c: char| c.is_whitespace() || c == '/')
+                .next()
+                .map(str::to_string),
+        };
+    }
+    None
+}
+
+/// The inner HTML of the first element with the given name.
+fn element_content<'a>(html: &'a str, name: &str) -> Option<&'a str> {
+    let lowercase = html.to_ascii_lowercase();
+    let (_, start) = find_tags(html, name).into_iter().next()?;
+    let end = lowercase[start..]
+        .find(&format!("</{}", name))
+        .map(|i| start + i)
+        .unwrap_or(html.len());
+    Some(&html[start..end])
+}
+
+/// Turns HTML into plain text paragraphs.
+fn readable_text(html: &str) -> String {
+    let lowercase = html.to_ascii_lowercase();
+    let mut text = String::new();
+    let mut pos = 0;
+    // Line breaks in the source are just whitespace, only block elements start a paragraph.
+    let push_text = |text: &mut String, raw: &str| text.push_str(&raw.replace(['\r', '\n'], " "));
+
+    while pos < html.len() {
+        let Some(tag_start) = html[pos..].find('<').map(|i| pos + i) else {
+            push_text(&mut text, &html[pos..]);
+            break;
+        };
+        push_text(&mut text, &html[pos..tag_start]);
+
+        // A `<` that doesn't start a tag, as in `a < b`.
+        if !html[tag_start + 1..]
+            .starts_with(|c: char| c.is_ascii_alphabetic() || c == '/' || c == '!')
+        {
+            text.push('<');
+            pos = tag_start + 1;
+            continue;
+        }
+
+        if html[tag_start..].starts_with("<!--") {
+            pos = html[tag_start..]
+                .find("-->")
+                .map(|i| tag_start + i + 3)
+                .unwrap_or(html.len());
+            continue;
+        }
+
+        let Some(tag_end) = html[tag_start..].find('>').map(|i| tag_start + i + 1) else {
+            break;
+        };
+        let tag = &lowercase[tag_start + 1..tag_end - 1];
+        let is_closing = tag.starts_with('/');
+        let name: String = tag
+            .trim_start_matches('/')
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect();
+        pos = tag_end;
+
+        if !is_closing && !tag.ends_with('/') && SKIPPED_ELEMENTS.contains(&name.as_str()) {
+            pos = lowercase[pos..]
+                .find(&format!("</{}", name))
+                .and_then(|i| lowercase[pos + i..].find('>').map(|j| pos + i + j + 1))
+                .unwrap_or(html.len());
+        } else if BLOCK_ELEMENTS.contains(&name.as_str()) {
+            text.push('\n');
+        }
+    }
+
+    decode_entities(&text)
+        .lines()
+        .map(collapse)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<String>>()
+        .join("\n\n")
+}
+
+fn collapse(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let entity = rest[1..]
+            .find(';')
+            .filter(|len| *len <= 10)
+            .map(|len| &rest[1..len + 1]);
+        let replacement = entity.and_then(|entity| match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" | "#39" => Some('\''),
+            "nbsp" => Some(' '),
+            "ndash" => Some('–'),
+            "mdash" => Some('—'),
+            "hellip" => Some('…'),
+            "lsquo" => Some('‘'),
+            "rsquo" => Some('’'),
+            "ldquo" => Some('“'),
+            "rdquo" => Some('”'),
+            "copy" => Some('©'),
+            "eacute" => Some('é'),
+            "egrave" => Some('è'),
+            "auml" => Some('ä'),
+            "ouml" => Some('ö'),
+            "uuml" => Some('ü'),
+            "szlig" => Some('ß'),
+            _ => entity
+                .strip_prefix("#x")
+                .or_else(|| entity.strip_prefix("#X"))
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(str::parse))
+                .and_then(Result::ok)
+                .and_then(char::from_u32),
+        });
+        match (entity, replacement) {
+            (Some(entity), Some(c)) => {
+                decoded.push(c);
+                rest = &rest[entity.len() + 2..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+/// Shortens text to at most `max_chars`, preferably at the end of a paragraph.
+pub fn truncate_text(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let cut = text
+        .char_indices()
+        .nth(max_chars)
+        .map(|(i, _)| i)
+        .unwrap_or(text.len());
+    let truncated = &text[..cut];
+    let truncated = match truncated.rfind("\n\n") {
+        Some(i) if i > cut / 2 => &truncated[..i],
+        _ => truncated,
+    };
+    format!("{} [...]", truncated.trim_end())
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{Router, http::header::CONTENT_TYPE, routing::get};
+
+    use super::*;
+    use crate::test_support::serve_stub;
+
+    const ARTICLE: &str = include_str!("../fixtures/clip/article.html");
+
+    #[test]
+    fn decode_entities_handles_named_and_numeric_entities() {
+        let cases = [
+            ("Fish &amp; Chips", "Fish & Chips"),
+            (
+                "&lt;p&gt; &quot;quoted&quot; &#39;single&#39;",
+                "<p> \"quoted\" 'single'",
+            ),
+            ("Gr&ouml;&szlig;e &mdash; &#8230; &#x1F600;", "Größe — … 😀"),
+            ("AT&T & co", "AT&T & co"),
+            ("&unknown; &#xZZ;", "&unknown; &#xZZ;"),
+            ("trailing &", "trailing &"),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(decode_entities(text), expected, "text {:?}", text);
+        }
+    }
+
+    #[test]
+    fn readable_text_drops_markup_and_keeps_paragraphs() {
+        let html = "<div>One <b>bold</b>\n word</div><script>var x = '<p>';</script>\
+            <!-- comment --><p>a < b</p><br/><style>p {}</style>Tail";
+        assert_eq!(readable_text(html), "One bold word\n\na < b\n\nTail");
+    }
+
+    #[test]
+    fn extract_page_reads_metadata_and_the_article() {
+        let page = extract_page("https://example.com/post", ARTICLE);
+
+        assert_eq!(page.url, "https://example.com/post");
+        assert_eq!(page.title, "Zettelkasten & Größe");
+        assert_eq!(page.author.as_deref(), Some("Jane Doe"));
+        assert_eq!(page.description.as_deref(), Some("How a slip box grows."));
+        assert_eq!(page.year, Some(2021));
+        assert_eq!(
+            page.text,
+            "Zettelkasten & Größe\n\n\
+             Every note links to at least one other note.\n\n\
+             Growth is non-linear: 1 < 2 — and a < b still reads fine.\n\n\
+             First idea\n\n\
+             Second idea …"
+        );
+    }
+
+    #[test]
+    fn extract_page_falls_back_to_the_title_element_and_url() {
+        let page = extract_page("https://example.com", "<title> A &amp; B </title><p>x</p>");
+        assert_eq!(page.title, "A & B");
+
+        let page = extract_page("https://example.com", "<p>Only text</p>");
+        assert_eq!(page.title, "https://example.com");
+        assert_eq!(page.text, "Only text");
+    }
+
+    async fn fixture_server() -> String {
+        let router = Router::new()
+            .route(
+                "/article",
+                get(|| async { ([(CONTENT_TYPE, "text/html; charset=utf-8")], ARTICLE) }),
+            )
+            .route(
+                "/paper.pdf",
+                get(|| async { ([(CONTENT_TYPE, "application/pdf")], "%PDF-1.7") }),
+            );
+        serve_stub(router).await
+    }
+
+    #[tokio::test]
+    async fn http_fetcher_returns_html_pages() {
+        let base_url = fixture_server().await;
+
+        let html = HttpPageFetcher::new()
+            .fetch(&format!("{}/article", base_url))
+            .await
+            .unwrap();
+
+        assert_eq!(html, ARTICLE);
+    }
+
+    #[tokio::test]
+    async fn http_fetcher_rejects_missing_pages_and_other_content() {
+        let base_url = fixture_server().await;
+        let fetcher = HttpPageFetcher::new();
+
+        let missing = fetcher.fetch(&format!("{}/missing", base_url)).await;
+        let pdf = fetcher.fetch(&format!("{}/paper.pdf", base_url)).await;
+
+        assert!(missing.unwrap_err().to_string().contains("404"));
+        assert!(pdf.unwrap_err().to_string().contains("application/pdf"));
+    }
+}
@@ -0,0 +1,769 @@
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    env, fmt, fs,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::{Arc, OnceLock},
+};
+
+use crate::{
+    datetime::{DEFAULT_DATETIME_FORMAT, DEFAULT_TIMEZONE},
+    db::Db,
+    doi::DEFAULT_DOI_RESOLVER_URL,
+    error::TshError,
+    tui::app::LlmConfig,
+};
+
+/// Environment variable pointing to a config file, overridden by `--config`.
+pub const CONFIG_ENV_VAR: &str = "TSH_CONFIG";
+/// Environment variable pointing to the data directory, overrides `data_dir` in the config.
+pub const DATA_DIR_ENV_VAR: &str = "TSH_DATA_DIR";
+
+/// Written to the default config location on first run.
+const DEFAULT_CONFIG: &str = r#"# Configuration for tsh.
+# Run `tsh config show` to see the settings in effect and where each one comes from.
+
+# Directory for the database and logs.
+# Defaults to $XDG_DATA_HOME/tsh (or ~/.local/share/tsh). TSH_DATA_DIR takes precedence.
+# data_dir = "/path/to/data"
+
+# Any OpenAI-compatible provider works. These defaults assume a local ollama.
+api_base = "http://localhost:11434/v1"
+
+# Set exactly one of these. Prefer the last three to keep the key out of the config.
+api_key = "ollama"
+# api_key_env = "OPENAI_API_KEY"
+# api_key_cmd = "pass show openai"
+# api_key_file = "~/.config/tsh/api_key" # must be chmod 600
+
+# Model used to embed notes for search.
+embeddings_model = "all-minilm:latest"
+
+# Optional, enables LLM features like auto-tagging, Ask, and lineage summaries.
+# chat_model = "llama3.2:latest"
+
+# Optional, where DOIs of sources are looked up. Any server answering
+# GET <url>/<doi> with BibTeX works.
+# doi_resolver_url = "https://doi.org"
+
+# Optional, one of error, warn, info, debug, trace. Defaults to info.
+# log_level = "info"
+
+# Optional, how times are shown: "local", "UTC" or an offset like "+02:00", and a strftime
+# format string. Defaults to local and "%Y-%m-%d %H:%M".
+# timezone = "local"
+# datetime_format = "%Y-%m-%d %H:%M"
+
+# Optional, separate knowledge bases. Open one with `tsh --vault work` or switch in the main menu.
+# The top-level settings above form the vault "default".
+# default_vault = "work"
+#
+# [vaults.work]
+# db_path = "work.db" # relative to data_dir, this is also the default
+# embeddings_model = "all-minilm:latest" # optional, like api_base and chat_model
+# encrypted = true # optional, see below
+
+# Optional, only available when tsh is built with the `encryption` feature.
+# Convert an existing database with `tsh encrypt` first.
+# encrypted = true
+# passphrase_cmd = "pass show tsh" # prompted for at startup when unset
+"#;
+
+#[derive(Debug, Deserialize)]
+pub struct AppConfig {
+    pub data_dir: Option<String>,
+    pub api_base: String,
+    pub api_key: Option<String>,
+    /// Name of an environment variable holding the API key.
+    pub api_key_env: Option<String>,
+    /// Shell command printing the API key, e.g. `pass show openai`.
+    pub api_key_cmd: Option<String>,
+    /// File containing only the API key. Must not be readable by group or others.
+    pub api_key_file: Option<String>,
+    pub embeddings_model: String,
+    pub chat_model: Option<String>,
+    pub log_level: Option<String>,
+    /// Base URL DOIs are resolved against, defaults to doi.org.
+    pub doi_resolver_url: Option<String>,
+    /// Timezone times are shown in: `local`, `UTC` or an offset like `+02:00`.
+    pub timezone: Option<String>,
+    /// strftime format string for times, defaults to `%Y-%m-%d %H:%M`.
+    pub datetime_format: Option<String>,
+    /// Whether the default vault's database is encrypted. Needs the `encryption` feature.
+    #[serde(default)]
+    pub encrypted: bool,
+    /// Shell command printing the database passphrase. Prompted for when unset.
+    pub passphrase_cmd: Option<String>,
+    /// Vault opened when `--vault` is not given. Defaults to the top-level settings.
+    pub default_vault: Option<String>,
+    #[serde(default)]
+    pub vaults: BTreeMap<String, VaultConfig>,
+}
+
+/// A named knowledge base with its own database. Unset fields fall back to the
+/// top-level settings, the API key is always shared.
+#[derive(Debug, Deserialize)]
+pub struct VaultConfig {
+    /// Path to the database, relative paths are resolved against the data directory.
+    /// Defaults to `<data_dir>/<name>.db`.
+    pub db_path: Option<String>,
+    pub api_base: Option<String>,
+    pub embeddings_model: Option<String>,
+    pub chat_model: Option<String>,
+    /// Whether this vault's database is encrypted, independent of the top-level setting.
+    #[serde(default)]
+    pub encrypted: bool,
+    pub passphrase_cmd: Option<String>,
+}
+
+/// Name of the vault built from the top-level settings.
+pub const DEFAULT_VAULT: &str = "default";
+
+/// A vault ready to be opened: the database and the LLM settings that go with it.
+#[derive(Debug, Clone)]
+pub struct Vault {
+    pub name: String,
+    pub db_path: String,
+    pub llm_config: LlmConfig,
+    pub encrypted: bool,
+    pub passphrase_cmd: Option<String>,
+}
+
+/// Where a resolved setting came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigSource {
+    Flag,
+    Env(&'static str),
+    ConfigFile,
+    Default,
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigSource::Flag => write!(f, "--config"),
+            ConfigSource::Env(var) => write!(f, "${}", var),
+            ConfigSource::ConfigFile => write!(f, "config file"),
+            ConfigSource::Default => write!(f, "default"),
+        }
+    }
+}
+
+/// The loaded config together with the paths derived from it.
+#[derive(Debug)]
+pub struct ResolvedConfig {
+    pub config: AppConfig,
+    pub config_path: PathBuf,
+    pub config_source: ConfigSource,
+    pub data_dir: PathBuf,
+    pub data_dir_source: ConfigSource,
+}
+
+/// Returns `$XDG_<name>` if it is set to an absolute path, as the spec demands,
+/// and `$HOME/<fallback>` otherwise.
+fn xdg_dir(name: &str, fallback: &str) -> Result<PathBuf, TshError> {
+    if let Some(dir) = env::var_os(name).map(PathBuf::from)
+        && dir.is_absolute()
+    {
+        return Ok(dir);
+    }
+    let home_dir =
+        env::var_os("HOME").ok_or_else(|| TshError::Config("HOME is not set".to_string()))?;
+    Ok(Path::new(&home_dir).join(fallback))
+}
+
+pub fn config_dir() -> Result<PathBuf, TshError> {
+    Ok(xdg_dir("XDG_CONFIG_HOME", ".config")?.join("tsh"))
+}
+
+pub fn default_data_dir() -> Result<PathBuf, TshError> {
+    Ok(xdg_dir("XDG_DATA_HOME", ".local/share")?.join("tsh"))
+}
+
+fn non_empty_env(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+/// Resolves the config file from `--config`, then `TSH_CONFIG`, then the XDG config dir.
+fn resolve_config_path(
+    cfg_path: Option<String>,
+    env_path: Option<String>,
+) -> Result<(PathBuf, ConfigSource), TshError> {
+    if let Some(path) = cfg_path {
+        return Ok((PathBuf::from(path), ConfigSource::Flag));
+    }
+    if let Some(path) = env_path {
+        return Ok((PathBuf::from(path), ConfigSource::Env(CONFIG_ENV_VAR)));
+    }
+    Ok((config_dir()?.join("config.toml"), ConfigSource::Default))
+}
+
+/// Resolves the data directory from `TSH_DATA_DIR`, then `data_dir` in the config, then the
+/// XDG data dir.
+fn resolve_data_dir(
+    env_dir: Option<String>,
+    config: &AppConfig,
+) -> Result<(PathBuf, ConfigSource), TshError> {
+    match (env_dir, &config.data_dir) {
+        (Some(dir), _) => Ok((PathBuf::from(dir), ConfigSource::Env(DATA_DIR_ENV_VAR))),
+        (None, Some(dir)) => Ok((PathBuf::from(dir), ConfigSource::ConfigFile)),
+        (None, None) => Ok((default_data_dir()?, ConfigSource::Default)),
+    }
+}
+
+/// Loads the config and resolves the data directory.
+/// A commented default config is written if none exists at the default location.
+/// Explicitly requested config files are never created.
+pub fn load_config(cfg_path: Option<String>) -> Result<ResolvedConfig, TshError> {
+    let (config_path, config_source) =
+        resolve_config_path(cfg_path, non_empty_env(CONFIG_ENV_VAR))?;
+
+    if config_source == ConfigSource::Default && !config_path.exists() {
+        if let Some(parent) = config_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&config_path, DEFAULT_CONFIG)?;
+        eprintln!("Created default config at {}", config_path.display());
+    }
+
+    let content = fs::read_to_string(&config_path).map_err(|e| {
+        TshError::Config(format!("Could not read {}: {}", config_path.display(), e))
+    })?;
+    let config: AppConfig = toml::from_str(&content).map_err(|e| {
+        TshError::Config(format!("Could not parse {}: {}", config_path.display(), e))
+    })?;
+
+    let (data_dir, data_dir_source) = resolve_data_dir(non_empty_env(DATA_DIR_ENV_VAR), &config)?;
+
+    Ok(ResolvedConfig {
+        config,
+        config_path,
+        config_source,
+        data_dir,
+        data_dir_source,
+    })
+}
+
+impl ResolvedConfig {
+    /// Note templates are kept next to the config file.
+    pub fn templates_dir(&self) -> PathBuf {
+        self.config_path
+            .parent()
+            .unwrap_or(Path::new("."))
+            .join("templates")
+    }
+
+    /// Lists every effective setting with a printable value and its source.
+    /// The API key is masked so the output can be shared.
+    pub fn settings(&self) -> Vec<(&'static str, String, ConfigSource)> {
+        let optional = |value: &Option<String>, default: &str| match value {
+            Some(value) => (value.clone(), ConfigSource::ConfigFile),
+            None => (default.to_string(), ConfigSource::Default),
+        };
+        let (chat_model, chat_model_source) = optional(&self.config.chat_model, "(none)");
+        let (log_level, log_level_source) = optional(&self.config.log_level, "info");
+        let (doi_resolver_url, doi_resolver_url_source) =
+            optional(&self.config.doi_resolver_url, DEFAULT_DOI_RESOLVER_URL);
+        let (timezone, timezone_source) = optional(&self.config.timezone, DEFAULT_TIMEZONE);
+        let (datetime_format, datetime_format_source) =
+            optional(&self.config.datetime_format, DEFAULT_DATETIME_FORMAT);
+        let (default_vault, default_vault_source) =
+            optional(&self.config.default_vault, DEFAULT_VAULT);
+
+        vec![
+            (
+                "config",
+                self.config_path.display().to_string(),
+                self.config_source,
+            ),
+            (
+                "data_dir",
+                self.data_dir.display().to_string(),
+                self.data_dir_source,
+            ),
+            (
+                "api_base",
+                self.config.api_base.clone(),
+                ConfigSource::ConfigFile,
+            ),
+            (
+                "api_key",
+                describe_api_key(&self.config),
+                ConfigSource::ConfigFile,
+            ),
+            (
+                "embeddings_model",
+                self.config.embeddings_model.clone(),
+                ConfigSource::ConfigFile,
+            ),
+            ("chat_model", chat_model, chat_model_source),
+            ("log_level", log_level, log_level_source),
+            (
+                "doi_resolver_url",
+                doi_resolver_url,
+                doi_resolver_url_source,
+            ),
+            ("timezone", timezone, timezone_source),
+            ("datetime_format", datetime_format, datetime_format_source),
+            ("default_vault", default_vault, default_vault_source),
+        ]
+    }
+}
+
+impl ResolvedConfig {
+    /// Builds the default vault from the top-level settings followed by the named vaults.
+    /// The API key is only looked up once an LLM call needs it.
+    pub fn vaults(&self) -> Result<Vec<Vault>, TshError> {
+        let base = LlmConfig::try_from(&self.config)?;
+        let mut vaults = vec![Vault {
+            name: DEFAULT_VAULT.to_string(),
+            db_path: self.default_db_path(),
+            llm_config: base.clone(),
+            encrypted: self.config.encrypted,
+            passphrase_cmd: self.config.passphrase_cmd.clone(),
+        }];
+
+        for (name, vault) in self.config.vaults.iter() {
+            if name == DEFAULT_VAULT {
+                return Err(TshError::Config(format!(
+                    "The vault name '{}' is reserved for the top-level settings",
+                    DEFAULT_VAULT
+                )));
+            }
+            vaults.push(Vault {
+                name: name.clone(),
+                db_path: self.vault_db_path(name, vault)?,
+                llm_config: LlmConfig {
+                    api_base: vault.api_base.clone().unwrap_or(base.api_base.clone()),
+                    api_key: base.api_key.clone(),
+                    embeddings_model: vault
+                        .embeddings_model
+                        .clone()
+                        .unwrap_or(base.embeddings_model.clone()),
+                    chat_model: vault.chat_model.clone().or(base.chat_model.clone()),
+                    doi_resolver_url: base.doi_resolver_url.clone(),
+                },
+                encrypted: vault.encrypted,
+                passphrase_cmd: vault
+                    .passphrase_cmd
+                    .clone()
+                    .or(self.config.passphrase_cmd.clone()),
+            });
+        }
+
+        Ok(vaults)
+    }
+
+    fn default_db_path(&self) -> String {
+        self.data_dir
+            .join("zettelkasten.db")
+            .to_string_lossy()
+            .to_string()
+    }
+
+    fn vault_db_path(&self, name: &str, vault: &VaultConfig) -> Result<String, TshError> {
+        let db_path = match &vault.db_path {
+            Some(path) => self.data_dir.join(expand_home(path)?),
+            None => self.data_dir.join(format!("{}.db", name)),
+        };
+        Ok(db_path.to_string_lossy().to_string())
+    }
+
+    /// Lists the database of every vault without resolving the API key.
+    pub fn vault_db_paths(&self) -> Result<Vec<(String, String)>, TshError> {
+        let mut paths = vec![(DEFAULT_VAULT.to_string(), self.default_db_path())];
+        for (name, vault) in self.config.vaults.iter() {
+            paths.push((name.clone(), self.vault_db_path(name, vault)?));
+        }
+        Ok(paths)
+    }
+
+    /// Picks the vault given by `--vault`, then `default_vault`, then the default vault.
+    pub fn select_vault(&self, vaults: &[Vault], name: Option<&str>) -> Result<usize, TshError> {
+        let name = name
+            .or(self.config.default_vault.as_deref())
+            .unwrap_or(DEFAULT_VAULT);
+        vaults
+            .iter()
+            .position(|vault| vault.name == name)
+            .ok_or_else(|| {
+                let names: Vec<&str> = vaults.iter().map(|vault| vault.name.as_str()).collect();
+                TshError::Config(format!(
+                    "Unknown vault '{}', available: {}",
+                    name,
+                    names.join(", ")
+                ))
+            })
+    }
+}
+
+impl Vault {
+    /// Creates the database directory if needed and opens the migrated database,
+    /// asking for the passphrase if it is encrypted.
+    pub async fn open(&self) -> Result<Db, TshError> {
+        if let Some(parent) = Path::new(&self.db_path).parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let passphrase = if self.encrypted {
+            Some(self.passphrase(false)?)
+        } else {
+            None
+        };
+        Db::open(&self.db_path, passphrase.as_deref()).await
+    }
+
+    /// Whether opening the vault asks for a passphrase on the terminal.
+    pub fn needs_passphrase_prompt(&self) -> bool {
+        self.encrypted && self.passphrase_cmd.is_none()
+    }
+
+    /// Reads the passphrase from `passphrase_cmd` or prompts for it.
+    /// A new passphrase has to be entered twice.
+    pub fn passphrase(&self, is_new: bool) -> Result<String, TshError> {
+        if let Some(cmd) = &self.passphrase_cmd {
+            return read_secret_from_command("passphrase_cmd", cmd);
+        }
+
+        let passphrase =
+            rpassword::prompt_password(format!("Passphrase for vault '{}': ", self.name))?;
+        if passphrase.is_empty() {
+            return Err(TshError::Encryption("The passphrase is empty".to_string()));
+        }
+        if is_new && rpassword::prompt_password("Repeat passphrase: ")? != passphrase {
+            return Err(TshError::Encryption(
+                "The passphrases don't match".to_string(),
+            ));
+        }
+        Ok(passphrase)
+    }
+}
+
+/// Describes where the API key will be read from without resolving it,
+/// so the output can be shared and no command is run.
+fn describe_api_key(config: &AppConfig) -> String {
+    if let Some(key) = &config.api_key {
+        if key.is_empty() {
+            "(empty)".to_string()
+        } else {
+            "********".to_string()
+        }
+    } else if let Some(var) = &config.api_key_env {
+        format!("from ${}", var)
+    } else if let Some(cmd) = &config.api_key_cmd {
+        format!("from `{}`", cmd)
+    } else if let Some(path) = &config.api_key_file {
+        format!("from {}", path)
+    } else {
+        "(not set)".to_string()
+    }
+}
+
+/// Where the API key is read from, one of `api_key`, `api_key_env`, `api_key_cmd` and
+/// `api_key_file`.
+#[derive(Debug, Clone)]
+enum ApiKeySource {
+    Value(String),
+    Env(String),
+    Command(String),
+    File(String),
+}
+
+/// The API key, looked up the first time an LLM call needs it so that commands which don't
+/// call the LLM never run `api_key_cmd`. Clones share the key once it has been looked up.
+#[derive(Debug, Clone)]
+pub struct ApiKey {
+    source: ApiKeySource,
+    resolved: Arc<OnceLock<String>>,
+}
+
+impl TryFrom<&AppConfig> for ApiKey {
+    type Error = TshError;
+
+    /// Setting more than one source is rejected to avoid surprises.
+    fn try_from(config: &AppConfig) -> Result<Self, Self::Error> {
+        let mut sources = [
+            config.api_key.clone().map(ApiKeySource::Value),
+            config.api_key_env.clone().map(ApiKeySource::Env),
+            config.api_key_cmd.clone().map(ApiKeySource::Command),
+            config.api_key_file.clone().map(ApiKeySource::File),
+        ]
+        .into_iter()
+        .flatten();
+
+        let source = sources.next().ok_or_else(|| {
+            TshError::Config(
+                "No API key configured, set one of api_key, api_key_env, api_key_cmd or api_key_file"
+                    .to_string(),
+            )
+        })?;
+        if sources.next().is_some() {
+            return Err(TshError::Config(
+                "Set only one of api_key, api_key_env, api_key_cmd and api_key_file".to_string(),
+            ));
+        }
+
+        Ok(ApiKey {
+            source,
+            resolved: Arc::new(OnceLock::new()),
+        })
+    }
+}
+
+impl From<&str> for ApiKey {
+    fn from(key: &str) -> Self {
+        ApiKey {
+            source: ApiKeySource::Value(key.to_string()),
+            resolved: Arc::new(OnceLock::new()),
+        }
+    }
+}
+
+impl ApiKey {
+    /// Returns the key, reading it from its source the first time.
+    pub fn resolve(&self) -> Result<String, TshError> {
+        if let Some(key) = self.resolved.get() {
+            return Ok(key.clone());
+        }
+        let key = match &self.source {
+            ApiKeySource::Value(key) => key.clone(),
+            ApiKeySource::Env(var) => env::var(var)
+                .map_err(|_| TshError::Config(format!("api_key_env: ${} is not set", var)))?,
+            ApiKeySource::Command(cmd) => read_secret_from_command("api_key_cmd", cmd)?,
+            ApiKeySource::File(path) => read_api_key_from_file(&expand_home(path)?)?,
+        };
+        Ok(self.resolved.get_or_init(|| key).clone())
+    }
+}
+
+/// Runs `cmd` and returns the first line it prints. `setting` names the config
+/// key in error messages.
+fn read_secret_from_command(setting: &str, cmd: &str) -> Result<String, TshError> {
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(cmd)
+        .stdin(Stdio::null())
+        .stderr(Stdio::inherit())
+        .output()
+        .map_err(|e| TshError::Config(format!("{}: could not run `{}`: {}", setting, cmd, e)))?;
+    if !output.status.success() {
+        return Err(TshError::Config(format!(
+            "{}: `{}` failed with {}",
+            setting, cmd, output.status
+        )));
+    }
+    // Only the first line counts, `pass` for example stores metadata below the secret.
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let secret = stdout.lines().next().unwrap_or("").trim().to_string();
+    if secret.is_empty() {
+        return Err(TshError::Config(format!(
+            "{}: `{}` printed nothing",
+            setting, cmd
+        )));
+    }
+    Ok(secret)
+}
+
+fn read_api_key_from_file(path: &Path) -> Result<String, TshError> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mode = fs::metadata(path)
+            .map_err(|e| TshError::Config(format!("api_key_file {}: {}", path.display(), e)))?
+            .permissions()
+            .mode();
+        if mode & 0o077 != 0 {
+            return Err(TshError::Config(format!(
+                "api_key_file {} is accessible by other users (mode {:o}), run `chmod 600 {}`",
+                path.display(),
+                mode & 0o777,
+                path.display()
+            )));
+        }
+    }
+
+    let content = fs::read_to_string(path)
+        .map_err(|e| TshError::Config(format!("api_key_file {}: {}", path.display(), e)))?;
+    let key = content.trim().to_string();
+    if key.is_empty() {
+        return Err(TshError::Config(format!(
+            "api_key_file {} is empty",
+            path.display()
+        )));
+    }
+    Ok(key)
+}
+
+fn expand_home(path: &str) -> Result<PathBuf, TshError> {
+    match path.strip_prefix("~/") {
+        Some(rest) => {
+            let home_dir = env::var_os("HOME")
+                .ok_or_else(|| TshError::Config("HOME is not set".to_string()))?;
+            Ok(Path::new(&home_dir).join(rest))
+        }
+        None => Ok(PathBuf::from(path)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(toml: &str) -> AppConfig {
+        toml::from_str(&format!(
+            "api_base = \"http://localhost\"\nembeddings_model = \"m\"\n{}",
+            toml
+        ))
+        .unwrap()
+    }
+
+    fn resolved(toml: &str) -> ResolvedConfig {
+        ResolvedConfig {
+            config: parse(toml),
+            config_path: PathBuf::from("/config/tsh/config.toml"),
+            config_source: ConfigSource::Flag,
+            data_dir: PathBuf::from("/data"),
+            data_dir_source: ConfigSource::Flag,
+        }
+    }
+
+    fn api_key(toml: &str) -> Result<String, TshError> {
+        ApiKey::try_from(&parse(toml))?.resolve()
+    }
+
+    #[cfg(unix)]
+    fn key_file(mode: u32) -> (tempfile::TempDir, String) {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("api_key");
+        fs::write(&path, "from-file\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(mode)).unwrap();
+        (dir, path.to_string_lossy().to_string())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn api_key_comes_from_exactly_one_source() {
+        let (_dir, path) = key_file(0o600);
+        let home = env::var("HOME").unwrap();
+
+        assert_eq!(api_key(r#"api_key = "inline""#).unwrap(), "inline");
+        assert_eq!(api_key(r#"api_key_env = "HOME""#).unwrap(), home);
+        assert_eq!(
+            api_key(r#"api_key_cmd = "printf 'from-cmd\nmetadata'""#).unwrap(),
+            "from-cmd"
+        );
+        assert_eq!(
+            api_key(&format!("api_key_file = {:?}", path)).unwrap(),
+            "from-file"
+        );
+
+        for toml in [
+            "api_key = \"inline\"\napi_key_env = \"HOME\"",
+            "api_key_env = \"HOME\"\napi_key_cmd = \"echo key\"",
+            &format!("api_key_cmd = \"echo key\"\napi_key_file = {:?}", path),
+            "",
+        ] {
+            assert!(
+                matches!(api_key(toml), Err(TshError::Config(_))),
+                "{}",
+                toml
+            );
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn api_key_file_readable_by_others_is_rejected() {
+        for mode in [0o640, 0o604, 0o660] {
+            let (_dir, path) = key_file(mode);
+            let Err(TshError::Config(message)) = api_key(&format!("api_key_file = {:?}", path))
+            else {
+                panic!("Expected mode {:o} to be rejected", mode);
+            };
+            assert!(message.contains("chmod 600"), "{}", message);
+        }
+    }
+
+    #[test]
+    fn api_key_cmd_only_runs_when_the_key_is_needed() {
+        let resolved = resolved("api_key_cmd = \"exit 1\"\n[vaults.work]\n");
+
+        let vaults = resolved.vaults().unwrap();
+
+        assert_eq!(vaults.len(), 2);
+        assert!(vaults[1].llm_config.api_key.resolve().is_err());
+    }
+
+    #[test]
+    fn config_flag_overrides_environment() {
+        let (path, source) =
+            resolve_config_path(Some("flag.toml".into()), Some("env.toml".into())).unwrap();
+        assert_eq!((path, source), ("flag.toml".into(), ConfigSource::Flag));
+
+        let (path, source) = resolve_config_path(None, Some("env.toml".into())).unwrap();
+        assert_eq!(
+            (path, source),
+            ("env.toml".into(), ConfigSource::Env(CONFIG_ENV_VAR))
+        );
+    }
+
+    #[test]
+    fn data_dir_environment_overrides_config() {
+        let config = parse(r#"data_dir = "/from/config""#);
+
+        let (dir, source) = resolve_data_dir(Some("/from/env".into()), &config).unwrap();
+        assert_eq!(
+            (dir, source),
+            ("/from/env".into(), ConfigSource::Env(DATA_DIR_ENV_VAR))
+        );
+
+        let (dir, source) = resolve_data_dir(None, &config).unwrap();
+        assert_eq!(
+            (dir, source),
+            ("/from/config".into(), ConfigSource::ConfigFile)
+        );
+    }
+
+    #[test]
+    fn vault_databases_resolve_against_the_data_dir() {
+        let resolved = resolved(
+            r#"
+            api_key = "key"
+            [vaults.absolute]
+            db_path = "/elsewhere/abs.db"
+            [vaults.relative]
+            db_path = "nested/rel.db"
+            [vaults.work]
+            "#,
+        );
+
+        let paths: Vec<(String, String)> = resolved
+            .vaults()
+            .unwrap()
+            .into_iter()
+            .map(|vault| (vault.name, vault.db_path))
+            .collect();
+
+        assert_eq!(
+            paths,
+            [
+                ("default", "/data/zettelkasten.db"),
+                ("absolute", "/elsewhere/abs.db"),
+                ("relative", "/data/nested/rel.db"),
+                ("work", "/data/work.db"),
+            ]
+            .map(|(name, path)| (name.to_string(), path.to_string()))
+        );
+        assert_eq!(resolved.vault_db_paths().unwrap(), paths);
+    }
+
+    #[test]
+    fn default_vault_name_is_reserved() {
+        let resolved = resolved("api_key = \"key\"\n[vaults.default]\n");
+
+        assert!(matches!(resolved.vaults(), Err(TshError::Config(_))));
+    }
+}
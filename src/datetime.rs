@@ -0,0 +1,227 @@
+use chrono::{DateTime, FixedOffset, Local, NaiveDate, Utc, format::Item, format::StrftimeItems};
+use std::{str::FromStr, sync::OnceLock};
+
+use crate::{config::AppConfig, error::TshError};
+
+pub const DEFAULT_TIMEZONE: &str = "local";
+pub const DEFAULT_DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+/// Set once at startup, so that every list, export and command shows times the same way.
+static DISPLAY: OnceLock<DateTimeDisplay> = OnceLock::new();
+
+/// The timezone timestamps are shown in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DisplayTimezone {
+    /// The system's timezone, which respects `TZ`.
+    Local,
+    Utc,
+    Fixed(FixedOffset),
+}
+
+impl FromStr for DisplayTimezone {
+    type Err = TshError;
+
+    /// Accepts `local`, `UTC` or an offset like `+02:00`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "local" => Ok(DisplayTimezone::Local),
+            "utc" | "z" => Ok(DisplayTimezone::Utc),
+            offset => offset
+                .parse::<FixedOffset>()
+                .map(DisplayTimezone::Fixed)
+                .map_err(|_| {
+                    TshError::Config(format!(
+                        "Invalid timezone '{}', expected local, UTC or an offset like +02:00",
+                        s
+                    ))
+                }),
+        }
+    }
+}
+
+/// How timestamps (milliseconds since the epoch, as stored) are rendered.
+#[derive(Debug, Clone)]
+pub struct DateTimeDisplay {
+    pub timezone: DisplayTimezone,
+    /// A strftime format string, see `chrono::format::strftime`.
+    pub format: String,
+}
+
+impl Default for DateTimeDisplay {
+    fn default() -> Self {
+        Self {
+            timezone: DisplayTimezone::Local,
+            format: DEFAULT_DATETIME_FORMAT.to_string(),
+        }
+    }
+}
+
+impl TryFrom<&AppConfig> for DateTimeDisplay {
+    type Error = TshError;
+
+    fn try_from(config: &AppConfig) -> Result<Self, Self::Error> {
+        let timezone = config
+            .timezone
+            .as_deref()
+            .unwrap_or(DEFAULT_TIMEZONE)
+            .parse()?;
+        let format = config
+            .datetime_format
+            .clone()
+            .unwrap_or(DEFAULT_DATETIME_FORMAT.to_string());
+        // chrono only notices a broken format string when it is rendered, and then panics.
+        if StrftimeItems::new(&format).any(|item| item == Item::Error) {
+            return Err(TshError::Config(format!(
+                "Invalid datetime_format '{}'",
+                format
+            )));
+        }
+        Ok(Self { timezone, format })
+    }
+}
+
+impl DateTimeDisplay {
+    pub fn format(&self, timestamp_ms: i64) -> String {
+        let Some(datetime) = DateTime::<Utc>::from_timestamp_millis(timestamp_ms) else {
+            return timestamp_ms.to_string();
+        };
+        match self.timezone {
+            DisplayTimezone::Local => datetime
+                .with_timezone(&Local)
+                .format(&self.format)
+                .to_string(),
+            DisplayTimezone::Utc => datetime.format(&self.format).to_string(),
+            DisplayTimezone::Fixed(offset) => datetime
+                .with_timezone(&offset)
+                .format(&self.format)
+                .to_string(),
+        }
+    }
+
+    /// The calendar date at `datetime` in the configured timezone.
+    pub fn date(&self, datetime: DateTime<Utc>) -> NaiveDate {
+        match self.timezone {
+            DisplayTimezone::Local => datetime.with_timezone(&Local).date_naive(),
+            DisplayTimezone::Utc => datetime.date_naive(),
+            DisplayTimezone::Fixed(offset) => datetime.with_timezone(&offset).date_naive(),
+        }
+    }
+}
+
+/// Makes `display` the way timestamps are shown for the rest of the process.
+pub fn init_datetime_display(display: DateTimeDisplay) {
+    let _ = DISPLAY.set(display);
+}
+
+fn display() -> &'static DateTimeDisplay {
+    DISPLAY.get_or_init(DateTimeDisplay::default)
+}
+
+/// Renders a stored timestamp in the configured timezone and format.
+pub fn format_timestamp(timestamp_ms: i64) -> String {
+    display().format(timestamp_ms)
+}
+
+/// The current date in the configured timezone, e.g. the day a journal entry belongs to.
+pub fn today() -> NaiveDate {
+    display().date(Utc::now())
+}
+
+/// Renders how long ago a stored timestamp was, e.g. `3 days ago`.
+pub fn format_relative(timestamp_ms: i64) -> String {
+    relative_to(timestamp_ms, Utc::now().timestamp_millis())
+}
+
+fn relative_to(timestamp_ms: i64, now_ms: i64) -> String {
+    let seconds = (now_ms - timestamp_ms) / 1000;
+    if seconds < 0 {
+        return "in the future".to_string();
+    }
+    let (amount, unit) = match seconds {
+        0..60 => return "just now".to_string(),
+        60..3_600 => (seconds / 60, "minute"),
+        3_600..86_400 => (seconds / 3_600, "hour"),
+        86_400..2_592_000 => (seconds / 86_400, "day"),
+        2_592_000..31_536_000 => (seconds / 2_592_000, "month"),
+        _ => (seconds / 31_536_000, "year"),
+    };
+    if amount == 1 {
+        format!("1 {} ago", unit)
+    } else {
+        format!("{} {}s ago", amount, unit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relative_to_picks_the_largest_whole_unit() {
+        let now = 1_700_000_000_000;
+        let ago = |seconds: i64| now - seconds * 1000;
+        let cases = [
+            (now + 5_000, "in the future"),
+            (now, "just now"),
+            (ago(59), "just now"),
+            (ago(60), "1 minute ago"),
+            (ago(119), "1 minute ago"),
+            (ago(120), "2 minutes ago"),
+            (ago(3_599), "59 minutes ago"),
+            (ago(3_600), "1 hour ago"),
+            (ago(86_399), "23 hours ago"),
+            (ago(86_400), "1 day ago"),
+            (ago(29 * 86_400), "29 days ago"),
+            (ago(30 * 86_400), "1 month ago"),
+            (ago(364 * 86_400), "12 months ago"),
+            (ago(365 * 86_400), "1 year ago"),
+            (ago(3 * 365 * 86_400), "3 years ago"),
+        ];
+        for (timestamp_ms, expected) in cases {
+            assert_eq!(
+                relative_to(timestamp_ms, now),
+                expected,
+                "{} ms before now",
+                now - timestamp_ms
+            );
+        }
+    }
+
+    #[test]
+    fn format_uses_the_configured_timezone_and_format() {
+        let display = DateTimeDisplay {
+            timezone: "+02:00".parse().unwrap(),
+            format: "%Y-%m-%d %H:%M %z".to_string(),
+        };
+        assert_eq!(display.format(0), "1970-01-01 02:00 +0200");
+
+        let display = DateTimeDisplay {
+            timezone: "UTC".parse().unwrap(),
+            format: DEFAULT_DATETIME_FORMAT.to_string(),
+        };
+        assert_eq!(display.format(1_700_000_000_000), "2023-11-14 22:13");
+        assert!("Mars/Olympus".parse::<DisplayTimezone>().is_err());
+    }
+
+    #[test]
+    fn date_is_taken_in_the_configured_timezone() {
+        let before_midnight_utc = DateTime::from_timestamp(1_700_006_000, 0).unwrap();
+        let date = |timezone: &str| {
+            DateTimeDisplay {
+                timezone: timezone.parse().unwrap(),
+                format: DEFAULT_DATETIME_FORMAT.to_string(),
+            }
+            .date(before_midnight_utc)
+        };
+
+        assert_eq!(date("UTC"), NaiveDate::from_ymd_opt(2023, 11, 14).unwrap());
+        assert_eq!(
+            date("+02:00"),
+            NaiveDate::from_ymd_opt(2023, 11, 15).unwrap()
+        );
+        assert_eq!(
+            date("-10:00"),
+            NaiveDate::from_ymd_opt(2023, 11, 14).unwrap()
+        );
+    }
+}
@@ -1,12 +1,19 @@
 use include_dir::{Dir, include_dir};
 use rusqlite::ffi::sqlite3_auto_extension;
-use rusqlite::{Connection, Transaction, params, params_from_iter};
+use rusqlite::{Connection, Row, Transaction, params, params_from_iter};
 use rusqlite_migration::Migrations;
 use sqlite_vec::sqlite3_vec_init;
-use std::sync::LazyLock;
+use std::sync::{Arc, LazyLock, Once};
+use std::time::Duration;
+use tokio::sync::{Mutex, MutexGuard};
+use tracing::instrument;
 use zerocopy::IntoBytes;
 
-use crate::model::{Article, Zettel, ZettelTag};
+use crate::error::TshError;
+use crate::model::{
+    Article, LineageEntry, ReviewItem, ReviewSchedule, Source, SourceMetadata, Zettel, ZettelEdge,
+    ZettelRevision, ZettelTag,
+};
 
 // TODO: move migrations dir to canonical location or specify in config.toml
 static MIGRATIONS_DIR: Dir = include_dir!("$CARGO_MANIFEST_DIR/migrations");
@@ -14,26 +21,171 @@ static MIGRATIONS_DIR: Dir = include_dir!("$CARGO_MANIFEST_DIR/migrations");
 static MIGRATIONS: LazyLock<Migrations<'static>> =
     LazyLock::new(|| Migrations::from_directory(&MIGRATIONS_DIR).unwrap());
 
-pub async fn migrate_to_latest(db_url: &str) -> Result<(), rusqlite::Error> {
-    let mut conn = get_db(db_url).await?;
-    MIGRATIONS.to_latest(&mut conn).unwrap();
+/// How long a write waits for another process (e.g. a CLI capture next to the
+/// running TUI) to release the database before failing with `database is locked`.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+static REGISTER_SQLITE_VEC: Once = Once::new();
+
+/// Registers sqlite-vec for every connection opened afterwards. Only needs to happen once.
+fn register_sqlite_vec() {
+    REGISTER_SQLITE_VEC.call_once(|| unsafe {
+        sqlite3_auto_extension(Some(std::mem::transmute::<
+            *const (),
+            unsafe extern "C" fn(
+                *mut rusqlite::ffi::sqlite3,
+                *mut *mut std::os::raw::c_char,
+                *const rusqlite::ffi::sqlite3_api_routines,
+            ) -> std::os::raw::c_int,
+        >(sqlite3_vec_init as *const ())));
+    });
+}
+
+/// A migrated connection shared by everything working on one database.
+/// Cloning is cheap, all clones use the same connection.
+#[derive(Debug, Clone)]
+pub struct Db {
+    path: String,
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl Db {
+    /// Opens the database, migrates it to the latest version and enables WAL mode,
+    /// a busy timeout and foreign key checks. Encrypted databases need their passphrase.
+    #[instrument(skip(passphrase))]
+    pub async fn open(db_path: &str, passphrase: Option<&str>) -> Result<Db, TshError> {
+        let mut conn = open_connection(db_path, passphrase)?;
+        // WAL lets readers and a writer in another process work at the same time.
+        conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0))?;
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        MIGRATIONS.to_latest(&mut conn)?;
+        // Enabled after migrating, since migrations may have to rebuild tables.
+        conn.pragma_update(None, "foreign_keys", "ON")?;
+
+        Ok(Db {
+            path: db_path.to_string(),
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    /// Waits for exclusive access to the connection.
+    pub async fn lock(&self) -> MutexGuard<'_, Connection> {
+        self.conn.lock().await
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+/// Opens a connection and unlocks it if a passphrase is given. Fails early with a
+/// helpful message if the database is encrypted but no or the wrong passphrase was given.
+fn open_connection(db_path: &str, passphrase: Option<&str>) -> Result<Connection, TshError> {
+    register_sqlite_vec();
+    let conn = Connection::open(db_path)?;
+    if let Some(passphrase) = passphrase {
+        apply_passphrase(&conn, passphrase)?;
+    }
+
+    // SQLCipher only checks the key once the database is read.
+    match conn.query_row("select count(*) from sqlite_master", [], |_| Ok(())) {
+        Err(rusqlite::Error::SqliteFailure(e, _))
+            if e.code == rusqlite::ErrorCode::NotADatabase =>
+        {
+            Err(TshError::Encryption(match passphrase {
+                Some(_) => format!("Wrong passphrase for {}", db_path),
+                None => format!(
+                    "{} is encrypted or not a database, set `encrypted = true` for its vault",
+                    db_path
+                ),
+            }))
+        }
+        result => Ok(result.map(|_| conn)?),
+    }
+}
+
+#[cfg(feature = "encryption")]
+fn apply_passphrase(conn: &Connection, passphrase: &str) -> Result<(), TshError> {
+    conn.pragma_update(None, "key", passphrase)?;
     Ok(())
 }
 
-pub async fn get_db(db_url: &str) -> Result<Connection, rusqlite::Error> {
-    unsafe {
-        sqlite3_auto_extension(Some(std::mem::transmute(sqlite3_vec_init as *const ())));
+#[cfg(not(feature = "encryption"))]
+fn apply_passphrase(_conn: &Connection, _passphrase: &str) -> Result<(), TshError> {
+    Err(encryption_unavailable())
+}
+
+#[cfg(not(feature = "encryption"))]
+fn encryption_unavailable() -> TshError {
+    TshError::Encryption("tsh was built without the `encryption` feature".to_string())
+}
+
+/// Whether the database can be read without a passphrase.
+pub fn is_plaintext(db_path: &str) -> Result<bool, TshError> {
+    match open_connection(db_path, None) {
+        Ok(_) => Ok(true),
+        Err(TshError::Encryption(_)) => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+/// Copies the database at `db_path` into a new database with a different passphrase,
+/// `None` meaning unencrypted, and replaces the original with it.
+/// The copy is verified before it replaces the original.
+#[cfg(feature = "encryption")]
+#[instrument(skip(from_passphrase, to_passphrase))]
+pub fn reencrypt(
+    db_path: &str,
+    from_passphrase: Option<&str>,
+    to_passphrase: Option<&str>,
+) -> Result<(), TshError> {
+    let export_path = format!("{}.export", db_path);
+    // Leftovers of an earlier failed attempt.
+    let _ = std::fs::remove_file(&export_path);
+
+    {
+        let conn = open_connection(db_path, from_passphrase)?;
+        // Everything has to be in the main file, not the write-ahead log.
+        conn.query_row("pragma wal_checkpoint(truncate)", [], |_| Ok(()))?;
+        conn.execute(
+            "attach database ?1 as export key ?2",
+            params![export_path, to_passphrase.unwrap_or("")],
+        )?;
+        conn.query_row("select sqlcipher_export('export')", [], |_| Ok(()))?;
+        // The export leaves out the schema version the migrations are tracked by.
+        let user_version: i64 = conn.query_row("pragma main.user_version", [], |row| row.get(0))?;
+        conn.execute_batch(&format!("pragma export.user_version = {}", user_version))?;
+        conn.execute("detach database export", [])?;
+    }
+
+    {
+        let conn = open_connection(&export_path, to_passphrase)?;
+        conn.query_row("select count(*) from zettel", [], |_| Ok(()))?;
+    }
+
+    std::fs::rename(&export_path, db_path)?;
+    for suffix in ["-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{}", db_path, suffix));
     }
-    let conn = Connection::open(db_url)?;
-    Ok(conn)
+    Ok(())
+}
+
+#[cfg(not(feature = "encryption"))]
+pub fn reencrypt(
+    _db_path: &str,
+    _from_passphrase: Option<&str>,
+    _to_passphrase: Option<&str>,
+) -> Result<(), TshError> {
+    Err(encryption_unavailable())
 }
 
-pub async fn store_zettel(
+#[instrument(level = "debug", skip_all, fields(parent_ids = ?parent_ids))]
+pub fn store_zettel(
     tx: &Transaction<'_>,
     content: &str,
     embedding: Vec<f32>,
     parent_ids: Vec<i64>,
-) -> Result<(), rusqlite::Error> {
+) -> Result<Zettel, rusqlite::Error> {
     let zettel: Zettel = tx
         .prepare("insert into zettel (content) values (?) returning id, content, created_at")?
         .query_one((content,), |row| {
@@ -50,16 +202,99 @@ pub async fn store_zettel(
     tx.prepare("insert into zettel_edge (node_id) values (?)")?
         .execute(rusqlite::params![zettel.id])?;
 
+    let mut parent_ids = parent_ids;
+    parent_ids.sort();
+    parent_ids.dedup();
+
+    // The `before_insert_zettel_edge` trigger rejects self-edges, unknown parents and cycles.
     let mut insert_zettel_edge_stmt =
         tx.prepare("insert into zettel_edge (node_id, parent_id) values (?, ?)")?;
     for id in parent_ids {
         insert_zettel_edge_stmt.execute(rusqlite::params![zettel.id, id])?;
     }
 
-    Ok(())
+    Ok(zettel)
 }
 
-pub async fn find_zettel_by_id(tx: &Transaction<'_>, id: i64) -> Result<Zettel, rusqlite::Error> {
+/// Replaces the content of a zettel and keeps the previous one as a revision.
+/// Edges are left alone, and cached lineage summaries that included the old content are
+/// dropped.
+#[instrument(level = "debug", skip_all, fields(zettel_id = zettel_id))]
+pub fn amend_zettel(
+    tx: &Transaction<'_>,
+    zettel_id: i64,
+    content: &str,
+    embedding: Vec<f32>,
+) -> Result<Zettel, rusqlite::Error> {
+    tx.prepare(
+        "
+        insert into zettel_revision (zettel_id, content)
+        select id, content
+        from zettel
+        where id = ?
+        ",
+    )?
+    .execute([zettel_id])?;
+
+    let zettel: Zettel = tx
+        .prepare("update zettel set content = ? where id = ? returning id, content, created_at")?
+        .query_one(params![content, zettel_id], |row| {
+            Ok(Zettel {
+                id: row.get(0)?,
+                content: row.get(1)?,
+                created_at: row.get(2)?,
+            })
+        })?;
+
+    // zettel_id isn't the rowid of the vec0 table, so the row is replaced instead of updated.
+    tx.prepare("delete from zettel_embedding where zettel_id = ?")?
+        .execute([zettel_id])?;
+    tx.prepare("insert into zettel_embedding (zettel_id, embedding) values (?, ?)")?
+        .execute(params![zettel_id, embedding.as_bytes()])?;
+
+    tx.prepare(
+        "
+        delete from lineage_summary
+        where zettel_id = ?1
+        or zettel_id in (select descendant_id from zettel_lineage where ancestor_id = ?1)
+        ",
+    )?
+    .execute([zettel_id])?;
+
+    Ok(zettel)
+}
+
+/// Returns the earlier contents of a zettel, most recently replaced first.
+#[instrument(level = "debug", skip_all, fields(zettel_id = zettel_id))]
+pub fn find_revisions(
+    tx: &Transaction<'_>,
+    zettel_id: i64,
+) -> Result<Vec<ZettelRevision>, rusqlite::Error> {
+    let mut stmt = tx.prepare(
+        "
+        select id, zettel_id, content, created_at
+        from zettel_revision
+        where zettel_id = ?
+        order by created_at desc, id desc
+        ",
+    )?;
+
+    let revisions = stmt
+        .query_map([zettel_id], |row| {
+            Ok(ZettelRevision {
+                id: row.get(0)?,
+                zettel_id: row.get(1)?,
+                content: row.get(2)?,
+                created_at: row.get(3)?,
+            })
+        })?
+        .collect::<Result<Vec<ZettelRevision>, rusqlite::Error>>()?;
+
+    Ok(revisions)
+}
+
+#[instrument(level = "debug", skip_all, fields(id = id))]
+pub fn find_zettel_by_id(tx: &Transaction<'_>, id: i64) -> Result<Zettel, rusqlite::Error> {
     let mut stmt = tx.prepare(
         "
         select id, content, created_at
@@ -79,7 +314,8 @@ pub async fn find_zettel_by_id(tx: &Transaction<'_>, id: i64) -> Result<Zettel,
     Ok(zettel)
 }
 
-pub async fn find_zettels_by_embedding(
+#[instrument(level = "debug", skip_all)]
+pub fn find_zettels_by_embedding(
     tx: &Transaction<'_>,
     embedding: Vec<f32>,
 ) -> Result<Vec<Zettel>, rusqlite::Error> {
@@ -106,7 +342,8 @@ pub async fn find_zettels_by_embedding(
     Ok(thoughts)
 }
 
-pub async fn find_n_recent_leaf_zettels(
+#[instrument(level = "debug", skip_all, fields(n = n))]
+pub fn find_n_recent_leaf_zettels(
     tx: &Transaction<'_>,
     n: i64,
 ) -> Result<Vec<Zettel>, rusqlite::Error> {
@@ -143,7 +380,8 @@ pub async fn find_n_recent_leaf_zettels(
     Ok(n_recent_zettels)
 }
 
-pub async fn store_article(
+#[instrument(level = "debug", skip_all, fields(zettel_id = zettel_id))]
+pub fn store_article(
     tx: &Transaction<'_>,
     zettel_id: i64,
     title: &str,
@@ -169,7 +407,33 @@ pub async fn store_article(
     Ok(article)
 }
 
-pub async fn add_tag_if_not_exists(
+#[instrument(level = "debug", skip_all)]
+pub fn find_all_articles(tx: &Transaction<'_>) -> Result<Vec<Article>, rusqlite::Error> {
+    let mut stmt = tx.prepare(
+        "
+        select id, zettel_id, coalesce(title, ''), content, created_at
+        from article
+        order by created_at desc
+        ",
+    )?;
+
+    let articles: Vec<Article> = stmt
+        .query_map([], |row| {
+            Ok(Article {
+                id: row.get(0)?,
+                zettel_id: row.get(1)?,
+                title: row.get(2)?,
+                content: row.get(3)?,
+                created_at: row.get(4)?,
+            })
+        })?
+        .collect::<Result<Vec<Article>, rusqlite::Error>>()?;
+
+    Ok(articles)
+}
+
+#[instrument(level = "debug", skip_all, fields(zettel_id = zettel_id))]
+pub fn add_tag_if_not_exists(
     tx: &Transaction<'_>,
     zettel_id: i64,
     tag: &str,
@@ -194,7 +458,8 @@ pub async fn add_tag_if_not_exists(
     Ok(zettel_tag)
 }
 
-pub async fn get_tags_for_zettel(
+#[instrument(level = "debug", skip_all, fields(zettel_id = zettel_id))]
+pub fn get_tags_for_zettel(
     tx: &Transaction<'_>,
     zettel_id: i64,
 ) -> Result<Vec<ZettelTag>, rusqlite::Error> {
@@ -219,7 +484,8 @@ pub async fn get_tags_for_zettel(
     Ok(tags)
 }
 
-pub async fn delete_tag_for_zettel_if_exists(
+#[instrument(level = "debug", skip_all, fields(zettel_id = zettel_id))]
+pub fn delete_tag_for_zettel_if_exists(
     tx: &Transaction<'_>,
     zettel_id: i64,
     tag: &str,
@@ -237,7 +503,8 @@ pub async fn delete_tag_for_zettel_if_exists(
     Ok(())
 }
 
-pub async fn find_zettels_by_tags(
+#[instrument(level = "debug", skip_all, fields(n_tags = tags.len()))]
+pub fn find_zettels_by_tags(
     tx: &Transaction<'_>,
     tags: Vec<String>,
 ) -> Result<Vec<Zettel>, rusqlite::Error> {
@@ -271,7 +538,8 @@ pub async fn find_zettels_by_tags(
     Ok(zettels)
 }
 
-pub async fn find_tags_by_search_string(
+#[instrument(level = "debug", skip_all)]
+pub fn find_tags_by_search_string(
     tx: &Transaction<'_>,
     search_string: &str,
 ) -> Result<Vec<String>, rusqlite::Error> {
@@ -284,7 +552,791 @@ pub async fn find_tags_by_search_string(
         ",
     )?;
     let tags: Vec<String> = stmt
-        .query_map([search_pattern], |row| Ok(row.get(0)?))?
+        .query_map([search_pattern], |row| row.get(0))?
+        .collect::<Result<Vec<String>, rusqlite::Error>>()?;
+    Ok(tags)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub fn find_all_tags(tx: &Transaction<'_>) -> Result<Vec<String>, rusqlite::Error> {
+    let mut stmt = tx.prepare(
+        "
+        select tag
+        from distinct_tags
+        ",
+    )?;
+    let tags: Vec<String> = stmt
+        .query_map([], |row| row.get(0))?
         .collect::<Result<Vec<String>, rusqlite::Error>>()?;
     Ok(tags)
 }
+
+/// Returns the ancestors of a zettel, starting with the most distant one.
+#[instrument(level = "debug", skip_all, fields(zettel_id = zettel_id))]
+pub fn find_ancestors(
+    tx: &Transaction<'_>,
+    zettel_id: i64,
+) -> Result<Vec<Zettel>, rusqlite::Error> {
+    let mut stmt = tx.prepare(
+        "
+        select z.id, z.content, z.created_at
+        from zettel z
+        join zettel_lineage zl on z.id = zl.ancestor_id
+        where zl.descendant_id = ?
+        order by zl.separation desc, z.created_at
+        ",
+    )?;
+
+    let ancestors: Vec<Zettel> = stmt
+        .query_map([zettel_id], |row| {
+            Ok(Zettel {
+                id: row.get(0)?,
+                content: row.get(1)?,
+                created_at: row.get(2)?,
+            })
+        })?
+        .collect::<Result<Vec<Zettel>, rusqlite::Error>>()?;
+
+    Ok(ancestors)
+}
+
+#[instrument(level = "debug", skip_all, fields(zettel_id = zettel_id))]
+pub fn find_lineage_summary(
+    tx: &Transaction<'_>,
+    zettel_id: i64,
+    model: &str,
+) -> Result<Option<String>, rusqlite::Error> {
+    let mut stmt = tx.prepare(
+        "
+        select summary
+        from lineage_summary
+        where zettel_id = ?
+        and model = ?
+        ",
+    )?;
+    let summary = stmt
+        .query_map(params![zettel_id, model], |row| row.get(0))?
+        .next()
+        .transpose()?;
+    Ok(summary)
+}
+
+#[instrument(level = "debug", skip_all, fields(zettel_id = zettel_id))]
+pub fn store_lineage_summary(
+    tx: &Transaction<'_>,
+    zettel_id: i64,
+    model: &str,
+    summary: &str,
+) -> Result<(), rusqlite::Error> {
+    tx.prepare(
+        "
+        insert into lineage_summary (zettel_id, model, summary)
+        values (?, ?, ?)
+        on conflict(zettel_id, model) do update
+        set summary = excluded.summary
+        ",
+    )?
+    .execute(params![zettel_id, model, summary])?;
+    Ok(())
+}
+
+#[instrument(level = "debug", skip_all, fields(zettel_id = zettel_id))]
+pub fn find_parents(tx: &Transaction<'_>, zettel_id: i64) -> Result<Vec<Zettel>, rusqlite::Error> {
+    let mut stmt = tx.prepare(
+        "
+        select z.id, z.content, z.created_at
+        from zettel z
+        join zettel_edge ze on z.id = ze.parent_id
+        where ze.node_id = ?
+        order by z.created_at
+        ",
+    )?;
+
+    let parents: Vec<Zettel> = stmt
+        .query_map([zettel_id], |row| {
+            Ok(Zettel {
+                id: row.get(0)?,
+                content: row.get(1)?,
+                created_at: row.get(2)?,
+            })
+        })?
+        .collect::<Result<Vec<Zettel>, rusqlite::Error>>()?;
+
+    Ok(parents)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub fn find_all_edges(tx: &Transaction<'_>) -> Result<Vec<ZettelEdge>, rusqlite::Error> {
+    let mut stmt = tx.prepare(
+        "
+        select node_id, parent_id
+        from zettel_edge
+        where parent_id is not null
+        order by node_id, parent_id
+        ",
+    )?;
+
+    let edges: Vec<ZettelEdge> = stmt
+        .query_map([], |row| {
+            Ok(ZettelEdge {
+                node_id: row.get(0)?,
+                parent_id: row.get(1)?,
+            })
+        })?
+        .collect::<Result<Vec<ZettelEdge>, rusqlite::Error>>()?;
+
+    Ok(edges)
+}
+
+/// Finds edges from a zettel to itself.
+#[instrument(level = "debug", skip_all)]
+pub fn find_self_edges(tx: &Transaction<'_>) -> Result<Vec<ZettelEdge>, rusqlite::Error> {
+    let mut stmt = tx.prepare(
+        "
+        select node_id, parent_id
+        from zettel_edge
+        where node_id = parent_id
+        ",
+    )?;
+
+    let edges: Vec<ZettelEdge> = stmt
+        .query_map([], |row| {
+            Ok(ZettelEdge {
+                node_id: row.get(0)?,
+                parent_id: row.get(1)?,
+            })
+        })?
+        .collect::<Result<Vec<ZettelEdge>, rusqlite::Error>>()?;
+
+    Ok(edges)
+}
+
+/// Finds edges where the node or the parent doesn't exist.
+#[instrument(level = "debug", skip_all)]
+pub fn find_dangling_edges(tx: &Transaction<'_>) -> Result<Vec<ZettelEdge>, rusqlite::Error> {
+    let mut stmt = tx.prepare(
+        "
+        select ze.node_id, ze.parent_id
+        from zettel_edge ze
+        left join zettel node on node.id = ze.node_id
+        left join zettel parent on parent.id = ze.parent_id
+        where node.id is null
+        or (ze.parent_id is not null and parent.id is null)
+        ",
+    )?;
+
+    let edges: Vec<ZettelEdge> = stmt
+        .query_map([], |row| {
+            Ok(ZettelEdge {
+                node_id: row.get(0)?,
+                parent_id: row.get(1)?,
+            })
+        })?
+        .collect::<Result<Vec<ZettelEdge>, rusqlite::Error>>()?;
+
+    Ok(edges)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub fn delete_edge(tx: &Transaction<'_>, edge: &ZettelEdge) -> Result<(), rusqlite::Error> {
+    tx.prepare("delete from zettel_edge where node_id = ? and parent_id is ?")?
+        .execute(params![edge.node_id, edge.parent_id])?;
+    Ok(())
+}
+
+/// Computes `zettel_lineage` from `zettel_edge` as the CTE `expected_lineage`, keeping
+/// the longest path between two zettels like the insert trigger does. The edges should
+/// not contain cycles, paths are cut off at the number of zettels so it terminates regardless.
+const EXPECTED_LINEAGE_CTE: &str = "
+    with recursive path(ancestor_id, descendant_id, separation) as (
+      select parent_id, node_id, 1
+      from zettel_edge
+      where parent_id is not null
+      union
+      select p.ancestor_id, ze.node_id, p.separation + 1
+      from path p
+      join zettel_edge ze on ze.parent_id = p.descendant_id
+      where p.separation < (select count(*) from zettel)
+    )
+    , expected_lineage as (
+      select ancestor_id, descendant_id, max(separation) as separation
+      from path
+      group by ancestor_id, descendant_id
+    )
+";
+
+/// Recomputes `zettel_lineage` from scratch.
+#[instrument(level = "debug", skip_all)]
+pub fn rebuild_lineage(tx: &Transaction<'_>) -> Result<usize, rusqlite::Error> {
+    tx.execute("delete from zettel_lineage", [])?;
+    let n_rows = tx.execute(
+        &format!(
+            "
+            insert into zettel_lineage (ancestor_id, descendant_id, separation)
+            {}
+            select ancestor_id, descendant_id, separation
+            from expected_lineage
+            ",
+            EXPECTED_LINEAGE_CTE
+        ),
+        [],
+    )?;
+    Ok(n_rows)
+}
+
+/// Compares `zettel_lineage` with what the edges imply.
+/// Returns the rows that are missing and the rows that shouldn't be there.
+#[instrument(level = "debug", skip_all)]
+pub fn find_lineage_drift(
+    tx: &Transaction<'_>,
+) -> Result<(Vec<LineageEntry>, Vec<LineageEntry>), rusqlite::Error> {
+    let query = |sql: &str| -> Result<Vec<LineageEntry>, rusqlite::Error> {
+        tx.prepare(&format!("{} {}", EXPECTED_LINEAGE_CTE, sql))?
+            .query_map([], |row| {
+                Ok(LineageEntry {
+                    ancestor_id: row.get(0)?,
+                    descendant_id: row.get(1)?,
+                    separation: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<LineageEntry>, rusqlite::Error>>()
+    };
+
+    let missing = query(
+        "
+        select ancestor_id, descendant_id, separation from expected_lineage
+        except
+        select ancestor_id, descendant_id, separation from zettel_lineage
+        ",
+    )?;
+    let stale = query(
+        "
+        select ancestor_id, descendant_id, separation from zettel_lineage
+        except
+        select ancestor_id, descendant_id, separation from expected_lineage
+        ",
+    )?;
+
+    Ok((missing, stale))
+}
+
+#[instrument(level = "debug", skip_all)]
+pub fn find_zettels_without_embedding(
+    tx: &Transaction<'_>,
+) -> Result<Vec<Zettel>, rusqlite::Error> {
+    let mut stmt = tx.prepare(
+        "
+        select id, content, created_at
+        from zettel
+        where id not in (select zettel_id from zettel_embedding)
+        order by id
+        ",
+    )?;
+
+    let zettels: Vec<Zettel> = stmt
+        .query_map([], |row| {
+            Ok(Zettel {
+                id: row.get(0)?,
+                content: row.get(1)?,
+                created_at: row.get(2)?,
+            })
+        })?
+        .collect::<Result<Vec<Zettel>, rusqlite::Error>>()?;
+
+    Ok(zettels)
+}
+
+#[instrument(level = "debug", skip_all, fields(zettel_id = zettel_id))]
+pub fn store_embedding(
+    tx: &Transaction<'_>,
+    zettel_id: i64,
+    embedding: Vec<f32>,
+) -> Result<(), rusqlite::Error> {
+    tx.prepare("insert into zettel_embedding (zettel_id, embedding) values (?, ?)")?
+        .execute(params![zettel_id, embedding.as_bytes()])?;
+    Ok(())
+}
+
+/// Returns the ids of embeddings whose zettel doesn't exist.
+#[instrument(level = "debug", skip_all)]
+pub fn find_orphan_embeddings(tx: &Transaction<'_>) -> Result<Vec<i64>, rusqlite::Error> {
+    let mut stmt = tx.prepare(
+        "
+        select zettel_id
+        from zettel_embedding
+        where zettel_id not in (select id from zettel)
+        order by zettel_id
+        ",
+    )?;
+    let zettel_ids = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<Vec<i64>, rusqlite::Error>>()?;
+    Ok(zettel_ids)
+}
+
+#[instrument(level = "debug", skip_all, fields(zettel_id = zettel_id))]
+pub fn delete_embedding(tx: &Transaction<'_>, zettel_id: i64) -> Result<(), rusqlite::Error> {
+    tx.prepare("delete from zettel_embedding where zettel_id = ?")?
+        .execute([zettel_id])?;
+    Ok(())
+}
+
+/// Returns tags assigned to zettels that don't exist.
+#[instrument(level = "debug", skip_all)]
+pub fn find_orphan_tags(tx: &Transaction<'_>) -> Result<Vec<ZettelTag>, rusqlite::Error> {
+    let mut stmt = tx.prepare(
+        "
+        select zettel_id, tag, created_at
+        from zettel_tag
+        where zettel_id not in (select id from zettel)
+        order by zettel_id, tag
+        ",
+    )?;
+
+    let tags: Vec<ZettelTag> = stmt
+        .query_map([], |row| {
+            Ok(ZettelTag {
+                zettel_id: row.get(0)?,
+                tag: row.get(1)?,
+                created_at: row.get(2)?,
+            })
+        })?
+        .collect::<Result<Vec<ZettelTag>, rusqlite::Error>>()?;
+
+    Ok(tags)
+}
+
+/// Returns articles whose zettel doesn't exist.
+#[instrument(level = "debug", skip_all)]
+pub fn find_dangling_articles(tx: &Transaction<'_>) -> Result<Vec<Article>, rusqlite::Error> {
+    let mut stmt = tx.prepare(
+        "
+        select id, zettel_id, coalesce(title, ''), content, created_at
+        from article
+        where zettel_id not in (select id from zettel)
+        order by id
+        ",
+    )?;
+
+    let articles: Vec<Article> = stmt
+        .query_map([], |row| {
+            Ok(Article {
+                id: row.get(0)?,
+                zettel_id: row.get(1)?,
+                title: row.get(2)?,
+                content: row.get(3)?,
+                created_at: row.get(4)?,
+            })
+        })?
+        .collect::<Result<Vec<Article>, rusqlite::Error>>()?;
+
+    Ok(articles)
+}
+
+/// Recreates the missing zettel of an article from the article's content, as a root zettel
+/// with the original id. The zettel still needs an embedding afterwards.
+#[instrument(level = "debug", skip_all, fields(article_id = article.id))]
+pub fn restore_zettel_from_article(
+    tx: &Transaction<'_>,
+    article: &Article,
+) -> Result<(), rusqlite::Error> {
+    tx.prepare("insert into zettel (id, content) values (?, ?)")?
+        .execute(params![article.zettel_id, article.content])?;
+    tx.prepare(
+        "
+        insert into zettel_edge (node_id)
+        select ?1
+        where not exists (
+          select 1 from zettel_edge where node_id = ?1 and parent_id is null
+        )
+        ",
+    )?
+    .execute([article.zettel_id])?;
+    Ok(())
+}
+
+const SOURCE_COLUMNS: &str =
+    "id, kind, bibtex_key, author, title, year, doi, url, isbn, created_at";
+
+fn source_from_row(row: &Row<'_>) -> Result<Source, rusqlite::Error> {
+    Ok(Source {
+        id: row.get(0)?,
+        metadata: SourceMetadata {
+            kind: row.get(1)?,
+            bibtex_key: row.get(2)?,
+            author: row.get(3)?,
+            title: row.get(4)?,
+            year: row.get(5)?,
+            doi: row.get(6)?,
+            url: row.get(7)?,
+            isbn: row.get(8)?,
+        },
+        created_at: row.get(9)?,
+    })
+}
+
+#[instrument(level = "debug", skip_all, fields(title = metadata.title))]
+pub fn store_source(
+    tx: &Transaction<'_>,
+    metadata: &SourceMetadata,
+) -> Result<Source, rusqlite::Error> {
+    tx.prepare(&format!(
+        "
+        insert into source (kind, bibtex_key, author, title, year, doi, url, isbn)
+        values (?, ?, ?, ?, ?, ?, ?, ?)
+        returning {}
+        ",
+        SOURCE_COLUMNS
+    ))?
+    .query_one(
+        params![
+            metadata.kind,
+            metadata.bibtex_key,
+            metadata.author,
+            metadata.title,
+            metadata.year,
+            metadata.doi,
+            metadata.url,
+            metadata.isbn,
+        ],
+        source_from_row,
+    )
+}
+
+/// Finds a stored source with the same DOI or BibTeX key, the two identifiers that must be
+/// unique.
+#[instrument(level = "debug", skip_all)]
+pub fn find_matching_source(
+    tx: &Transaction<'_>,
+    metadata: &SourceMetadata,
+) -> Result<Option<Source>, rusqlite::Error> {
+    let mut stmt = tx.prepare(&format!(
+        "
+        select {}
+        from source
+        where doi = ? or bibtex_key = ?
+        ",
+        SOURCE_COLUMNS
+    ))?;
+    let source = stmt
+        .query_map(params![metadata.doi, metadata.bibtex_key], source_from_row)?
+        .next()
+        .transpose()?;
+    Ok(source)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub fn find_all_sources(tx: &Transaction<'_>) -> Result<Vec<Source>, rusqlite::Error> {
+    let mut stmt = tx.prepare(&format!(
+        "
+        select {}
+        from source
+        order by author collate nocase, year, title collate nocase
+        ",
+        SOURCE_COLUMNS
+    ))?;
+    let sources = stmt
+        .query_map([], source_from_row)?
+        .collect::<Result<Vec<Source>, rusqlite::Error>>()?;
+    Ok(sources)
+}
+
+#[instrument(level = "debug", skip_all, fields(zettel_id = zettel_id))]
+pub fn find_sources_for_zettel(
+    tx: &Transaction<'_>,
+    zettel_id: i64,
+) -> Result<Vec<Source>, rusqlite::Error> {
+    let mut stmt = tx.prepare(&format!(
+        "
+        select {}
+        from source
+        where id in (select source_id from zettel_source where zettel_id = ?)
+        order by author collate nocase, year, title collate nocase
+        ",
+        SOURCE_COLUMNS
+    ))?;
+    let sources = stmt
+        .query_map([zettel_id], source_from_row)?
+        .collect::<Result<Vec<Source>, rusqlite::Error>>()?;
+    Ok(sources)
+}
+
+#[instrument(level = "debug", skip_all, fields(zettel_id = zettel_id, source_id = source_id))]
+pub fn add_source_to_zettel(
+    tx: &Transaction<'_>,
+    zettel_id: i64,
+    source_id: i64,
+) -> Result<(), rusqlite::Error> {
+    tx.prepare("insert or ignore into zettel_source (zettel_id, source_id) values (?, ?)")?
+        .execute(params![zettel_id, source_id])?;
+    Ok(())
+}
+
+#[instrument(level = "debug", skip_all, fields(zettel_id = zettel_id, source_id = source_id))]
+pub fn delete_source_from_zettel(
+    tx: &Transaction<'_>,
+    zettel_id: i64,
+    source_id: i64,
+) -> Result<(), rusqlite::Error> {
+    tx.prepare("delete from zettel_source where zettel_id = ? and source_id = ?")?
+        .execute(params![zettel_id, source_id])?;
+    Ok(())
+}
+
+/// Cites the zettel's sources in an article made from it.
+#[instrument(level = "debug", skip_all, fields(zettel_id = zettel_id, article_id = article_id))]
+pub fn copy_sources_to_article(
+    tx: &Transaction<'_>,
+    zettel_id: i64,
+    article_id: i64,
+) -> Result<(), rusqlite::Error> {
+    tx.prepare(
+        "
+        insert or ignore into article_source (article_id, source_id)
+        select ?, source_id
+        from zettel_source
+        where zettel_id = ?
+        ",
+    )?
+    .execute(params![article_id, zettel_id])?;
+    Ok(())
+}
+
+#[instrument(level = "debug", skip_all)]
+pub fn find_source_by_url(
+    tx: &Transaction<'_>,
+    url: &str,
+) -> Result<Option<Source>, rusqlite::Error> {
+    let mut stmt = tx.prepare(&format!(
+        "
+        select {}
+        from source
+        where url = ?
+        order by id
+        ",
+        SOURCE_COLUMNS
+    ))?;
+    let source = stmt.query_map([url], source_from_row)?.next().transpose()?;
+    Ok(source)
+}
+
+/// Zettels whose review is due at `now`, those with the fewest descendants first so that
+/// undeveloped ideas come up before well-explored ones. Zettels that were never reviewed
+/// are first due `first_review_after_ms` after they were written.
+#[instrument(level = "debug", skip_all, fields(now = now, first_review_after_ms = first_review_after_ms, leaves_only = leaves_only, limit = limit))]
+pub fn find_due_reviews(
+    tx: &Transaction<'_>,
+    now: i64,
+    first_review_after_ms: i64,
+    leaves_only: bool,
+    limit: i64,
+) -> Result<Vec<ReviewItem>, rusqlite::Error> {
+    let mut stmt = tx.prepare(
+        "
+        with descendants as (
+            select ancestor_id as zettel_id, count(*) as n_descendants
+            from zettel_lineage
+            group by ancestor_id
+        )
+        select
+              z.id
+            , z.content
+            , z.created_at
+            , coalesce(d.n_descendants, 0)
+            , r.zettel_id
+            , r.repetitions
+            , r.interval_days
+            , r.ease_factor
+            , r.due_at
+            , r.archived
+        from zettel z
+        left join descendants d on d.zettel_id = z.id
+        left join zettel_review r on r.zettel_id = z.id
+        where coalesce(r.archived, 0) = 0
+          and coalesce(r.due_at, z.created_at + ?2) <= ?1
+          and (?3 = 0 or d.zettel_id is null)
+        order by coalesce(d.n_descendants, 0), coalesce(r.due_at, z.created_at + ?2)
+        limit ?4
+        ",
+    )?;
+
+    let items = stmt
+        .query_map(
+            params![now, first_review_after_ms, leaves_only, limit],
+            |row| {
+                let zettel_id: Option<i64> = row.get(4)?;
+                let schedule = match zettel_id {
+                    Some(zettel_id) => Some(ReviewSchedule {
+                        zettel_id,
+                        repetitions: row.get(5)?,
+                        interval_days: row.get(6)?,
+                        ease_factor: row.get(7)?,
+                        due_at: row.get(8)?,
+                        archived: row.get(9)?,
+                    }),
+                    None => None,
+                };
+                Ok(ReviewItem {
+                    zettel: Zettel {
+                        id: row.get(0)?,
+                        content: row.get(1)?,
+                        created_at: row.get(2)?,
+                    },
+                    n_descendants: row.get(3)?,
+                    schedule,
+                })
+            },
+        )?
+        .collect::<Result<Vec<ReviewItem>, rusqlite::Error>>()?;
+
+    Ok(items)
+}
+
+#[instrument(level = "debug", skip_all, fields(zettel_id = zettel_id))]
+pub fn find_review_schedule(
+    tx: &Transaction<'_>,
+    zettel_id: i64,
+) -> Result<Option<ReviewSchedule>, rusqlite::Error> {
+    let mut stmt = tx.prepare(
+        "
+        select zettel_id, repetitions, interval_days, ease_factor, due_at, archived
+        from zettel_review
+        where zettel_id = ?
+        ",
+    )?;
+    let schedule = stmt
+        .query_map([zettel_id], |row| {
+            Ok(ReviewSchedule {
+                zettel_id: row.get(0)?,
+                repetitions: row.get(1)?,
+                interval_days: row.get(2)?,
+                ease_factor: row.get(3)?,
+                due_at: row.get(4)?,
+                archived: row.get(5)?,
+            })
+        })?
+        .next()
+        .transpose()?;
+    Ok(schedule)
+}
+
+#[instrument(level = "debug", skip_all)]
+pub fn store_review_schedule(
+    tx: &Transaction<'_>,
+    schedule: &ReviewSchedule,
+) -> Result<(), rusqlite::Error> {
+    tx.prepare(
+        "
+        insert into zettel_review (zettel_id, repetitions, interval_days, ease_factor, due_at, archived)
+        values (?, ?, ?, ?, ?, ?)
+        on conflict(zettel_id) do update
+        set repetitions = excluded.repetitions
+          , interval_days = excluded.interval_days
+          , ease_factor = excluded.ease_factor
+          , due_at = excluded.due_at
+          , archived = excluded.archived
+          , reviewed_at = unixepoch('subsec') * 1000
+        ",
+    )?
+    .execute(params![
+        schedule.zettel_id,
+        schedule.repetitions,
+        schedule.interval_days,
+        schedule.ease_factor,
+        schedule.due_at,
+        schedule.archived,
+    ])?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_db;
+
+    fn lineage(tx: &Transaction<'_>) -> Vec<(i64, i64, i64)> {
+        tx.prepare(
+            "select ancestor_id, descendant_id, separation from zettel_lineage order by 1, 2",
+        )
+        .unwrap()
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap()
+    }
+
+    fn insert_edge(tx: &Transaction<'_>, node_id: i64, parent_id: i64) -> TshError {
+        tx.execute(
+            "insert into zettel_edge (node_id, parent_id) values (?, ?)",
+            [node_id, parent_id],
+        )
+        .unwrap_err()
+        .into()
+    }
+
+    #[tokio::test]
+    async fn edges_breaking_the_dag_are_rejected() {
+        let (_dir, db) = temp_db().await;
+        let mut conn = db.lock().await;
+        let tx = conn.transaction().unwrap();
+        let root = store_zettel(&tx, "Root", vec![0.1; 384], vec![]).unwrap();
+        let child = store_zettel(&tx, "Child", vec![0.1; 384], vec![root.id]).unwrap();
+        let before = lineage(&tx);
+        assert_eq!(before, [(root.id, child.id, 1)]);
+
+        let self_edge = insert_edge(&tx, child.id, child.id);
+        let missing_parent = store_zettel(&tx, "Orphan", vec![0.1; 384], vec![child.id + 100])
+            .unwrap_err()
+            .into();
+        let cycle = insert_edge(&tx, root.id, child.id);
+
+        for (error, message) in [
+            (self_edge, "zettel cannot be its own parent"),
+            (missing_parent, "parent zettel does not exist"),
+            (cycle, "edge would create a cycle"),
+        ] {
+            assert!(
+                matches!(&error, TshError::Integrity(msg) if msg == message),
+                "{}",
+                error
+            );
+        }
+        assert_eq!(lineage(&tx), before);
+    }
+
+    #[cfg(feature = "encryption")]
+    #[tokio::test]
+    async fn databases_can_be_encrypted_and_decrypted() {
+        async fn contents(db: &Db) -> Vec<String> {
+            let conn = db.lock().await;
+            conn.prepare("select content from zettel")
+                .unwrap()
+                .query_map([], |row| row.get(0))
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap()
+        }
+
+        let (_dir, db) = temp_db().await;
+        crate::test_support::insert_zettel(&db, "Secret").await;
+        let path = db.path().to_string();
+        drop(db);
+
+        reencrypt(&path, None, Some("right")).unwrap();
+        assert!(!is_plaintext(&path).unwrap());
+        let db = Db::open(&path, Some("right")).await.unwrap();
+        assert_eq!(contents(&db).await, ["Secret"]);
+        drop(db);
+        for passphrase in [Some("wrong"), None] {
+            assert!(matches!(
+                Db::open(&path, passphrase).await,
+                Err(TshError::Encryption(_))
+            ));
+        }
+
+        reencrypt(&path, Some("right"), None).unwrap();
+        assert!(is_plaintext(&path).unwrap());
+        let db = Db::open(&path, None).await.unwrap();
+        assert_eq!(contents(&db).await, ["Secret"]);
+    }
+}
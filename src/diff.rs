@@ -0,0 +1,165 @@
+use similar::{ChangeTag, TextDiff};
+
+use crate::model::Zettel;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffKind {
+    Equal,
+    Insert,
+    Delete,
+}
+
+impl From<ChangeTag> for DiffKind {
+    fn from(tag: ChangeTag) -> Self {
+        match tag {
+            ChangeTag::Equal => DiffKind::Equal,
+            ChangeTag::Insert => DiffKind::Insert,
+            ChangeTag::Delete => DiffKind::Delete,
+        }
+    }
+}
+
+/// A changed line, split into segments. Emphasized segments are the words that changed
+/// within the line.
+#[derive(Debug, Clone)]
+pub struct DiffLine {
+    pub kind: DiffKind,
+    pub segments: Vec<(bool, String)>,
+}
+
+/// A line of a zettel together with the parent it was taken from, if any.
+#[derive(Debug, Clone)]
+pub struct AttributedLine {
+    pub line: String,
+    pub parent_id: Option<i64>,
+}
+
+/// Diffs two versions of a zettel line by line, with word level emphasis on changed lines.
+pub fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    let diff = TextDiff::from_lines(old, new);
+    let mut lines: Vec<DiffLine> = vec![];
+
+    for op in diff.ops() {
+        for change in diff.iter_inline_changes(op) {
+            let segments = change
+                .iter_strings_lossy()
+                .map(|(emphasized, value)| (emphasized, value.trim_end_matches('\n').to_string()))
+                .collect();
+            lines.push(DiffLine {
+                kind: DiffKind::from(change.tag()),
+                segments,
+            });
+        }
+    }
+
+    lines
+}
+
+/// Attributes every line of a remixed zettel to the first parent that contains it.
+/// Lines that don't appear in any parent were written in the zettel itself.
+pub fn attribute_lines(zettel: &Zettel, parents: &[Zettel]) -> Vec<AttributedLine> {
+    zettel
+        .content
+        .lines()
+        .map(|line| {
+            let needle = line.trim();
+            let parent_id = if needle.is_empty() {
+                None
+            } else {
+                parents
+                    .iter()
+                    .find(|parent| parent.content.lines().any(|l| l.trim() == needle))
+                    .map(|parent| parent.id)
+            };
+            AttributedLine {
+                line: line.to_string(),
+                parent_id,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn zettel(id: i64, content: &str) -> Zettel {
+        Zettel {
+            id,
+            content: content.to_string(),
+            created_at: 0,
+        }
+    }
+
+    fn emphasized(line: &DiffLine) -> Vec<&str> {
+        line.segments
+            .iter()
+            .filter(|(emphasized, _)| *emphasized)
+            .map(|(_, text)| text.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn diff_lines_marks_changed_lines_and_words() {
+        let lines = diff_lines("same\nold word\ngone\n", "same\nnew word\n");
+
+        let kinds: Vec<DiffKind> = lines.iter().map(|line| line.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                DiffKind::Equal,
+                DiffKind::Delete,
+                DiffKind::Delete,
+                DiffKind::Insert
+            ]
+        );
+        let text: Vec<String> = lines
+            .iter()
+            .map(|line| {
+                line.segments
+                    .iter()
+                    .map(|(_, text)| text.as_str())
+                    .collect()
+            })
+            .collect();
+        assert_eq!(text, ["same", "old word", "gone", "new word"]);
+        assert!(emphasized(&lines[0]).is_empty());
+        assert_eq!(emphasized(&lines[1]), ["old"]);
+        assert_eq!(emphasized(&lines[3]), ["new"]);
+    }
+
+    #[test]
+    fn diff_lines_of_equal_text_has_no_changes() {
+        let lines = diff_lines("a\nb", "a\nb");
+
+        assert!(lines.iter().all(|line| line.kind == DiffKind::Equal));
+        assert_eq!(lines.len(), 2);
+    }
+
+    #[test]
+    fn attribute_lines_picks_the_first_parent_containing_the_line() {
+        let parents = [
+            zettel(1, "shared\nfrom one"),
+            zettel(2, "  shared  \nfrom two"),
+        ];
+        let remix = zettel(3, "from two\n\nshared\nwritten here\n  from one");
+
+        let lines = attribute_lines(&remix, &parents);
+
+        let attributed: Vec<(&str, Option<i64>)> = lines
+            .iter()
+            .map(|line| (line.line.as_str(), line.parent_id))
+            .collect();
+
+        assert_eq!(
+            attributed,
+            [
+                ("from two", Some(2)),
+                ("", None),
+                ("shared", Some(1)),
+                ("written here", None),
+                ("  from one", Some(1)),
+            ]
+        );
+    }
+}
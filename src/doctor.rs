@@ -0,0 +1,345 @@
+use std::collections::{HashMap, HashSet};
+
+use rusqlite::Transaction;
+use strum::{Display, EnumIter, IntoEnumIterator};
+use tracing::{info, instrument};
+
+use crate::{
+    db::{
+        Db, delete_edge, delete_embedding, delete_tag_for_zettel_if_exists, find_all_edges,
+        find_dangling_articles, find_dangling_edges, find_lineage_drift, find_orphan_embeddings,
+        find_orphan_tags, find_self_edges, find_zettels_without_embedding, rebuild_lineage,
+        restore_zettel_from_article, store_embedding,
+    },
+    error::TshError,
+    llm::LlmClient,
+    model::{LineageEntry, ZettelEdge},
+    tui::app::LlmConfig,
+};
+
+/// Problems `tsh doctor` looks for, in the order they are checked and repaired.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumIter)]
+pub enum Check {
+    #[strum(to_string = "Edges from a zettel to itself")]
+    SelfEdges,
+    #[strum(to_string = "Edges to missing zettels")]
+    DanglingEdges,
+    #[strum(to_string = "Edges closing a cycle")]
+    CycleEdges,
+    #[strum(to_string = "Lineage out of sync with the edges")]
+    LineageDrift,
+    #[strum(to_string = "Articles pointing to missing zettels")]
+    DanglingArticles,
+    #[strum(to_string = "Embeddings without a zettel")]
+    OrphanEmbeddings,
+    #[strum(to_string = "Zettels without an embedding")]
+    MissingEmbeddings,
+    #[strum(to_string = "Tags on missing zettels")]
+    OrphanTags,
+}
+
+impl Check {
+    /// What a repair does, shown before asking for confirmation.
+    pub fn repair_description(&self) -> &'static str {
+        match self {
+            Check::SelfEdges | Check::DanglingEdges | Check::CycleEdges => {
+                "delete the edges and rebuild the lineage"
+            }
+            Check::LineageDrift => "rebuild the lineage from the edges",
+            Check::DanglingArticles => "restore the zettels from the article contents",
+            Check::OrphanEmbeddings => "delete the embeddings",
+            Check::MissingEmbeddings => "embed the zettels",
+            Check::OrphanTags => "delete the tags",
+        }
+    }
+}
+
+/// Everything one check found, described for printing.
+#[derive(Debug)]
+pub struct Finding {
+    pub check: Check,
+    pub items: Vec<String>,
+}
+
+fn describe_edge(edge: &ZettelEdge) -> String {
+    match edge.parent_id {
+        Some(parent_id) => format!("{} -> {}", edge.node_id, parent_id),
+        None => format!("{} (root)", edge.node_id),
+    }
+}
+
+fn describe_lineage_entry(state: &str, entry: &LineageEntry) -> String {
+    format!(
+        "{}: {} -> {} (separation {})",
+        state, entry.descendant_id, entry.ancestor_id, entry.separation
+    )
+}
+
+/// Finds the broken edges for the edge checks.
+fn find_broken_edges(tx: &Transaction<'_>, check: Check) -> Result<Vec<ZettelEdge>, TshError> {
+    let edges = match check {
+        Check::SelfEdges => find_self_edges(tx)?,
+        Check::DanglingEdges => find_dangling_edges(tx)?,
+        Check::CycleEdges => find_cycle_edges(&find_all_edges(tx)?),
+        _ => vec![],
+    };
+    Ok(edges)
+}
+
+/// Finds edges that close a cycle. Zettels are only ever linked to older ones, so every
+/// cycle contains an edge to a newer parent, and those are the ones reported.
+/// Removing all of them leaves a DAG. Self-edges are left to their own check.
+fn find_cycle_edges(edges: &[ZettelEdge]) -> Vec<ZettelEdge> {
+    let mut parents: HashMap<i64, Vec<i64>> = HashMap::new();
+    for edge in edges {
+        if let Some(parent_id) = edge.parent_id {
+            parents.entry(edge.node_id).or_default().push(parent_id);
+        }
+    }
+
+    // Whether `target` can be reached from `start` by following parent edges.
+    let reaches = |start: i64, target: i64| {
+        let mut visited: HashSet<i64> = HashSet::new();
+        let mut stack = vec![start];
+        while let Some(node_id) = stack.pop() {
+            if node_id == target {
+                return true;
+            }
+            if visited.insert(node_id) {
+                stack.extend(parents.get(&node_id).into_iter().flatten());
+            }
+        }
+        false
+    };
+
+    edges
+        .iter()
+        .filter(|edge| {
+            edge.parent_id.is_some_and(|parent_id| {
+                parent_id > edge.node_id && reaches(parent_id, edge.node_id)
+            })
+        })
+        .cloned()
+        .collect()
+}
+
+/// Runs every check without changing anything. Checks that found nothing are omitted.
+#[instrument(skip_all)]
+pub async fn audit(db: &Db) -> Result<Vec<Finding>, TshError> {
+    let mut conn = db.lock().await;
+    let tx = conn.transaction()?;
+
+    let mut findings: Vec<Finding> = vec![];
+    for check in Check::iter() {
+        let items: Vec<String> = match check {
+            Check::SelfEdges | Check::DanglingEdges | Check::CycleEdges => {
+                find_broken_edges(&tx, check)?
+                    .iter()
+                    .map(describe_edge)
+                    .collect()
+            }
+            Check::LineageDrift => {
+                let (missing, stale) = find_lineage_drift(&tx)?;
+                missing
+                    .iter()
+                    .map(|entry| describe_lineage_entry("missing", entry))
+                    .chain(
+                        stale
+                            .iter()
+                            .map(|entry| describe_lineage_entry("stale", entry)),
+                    )
+                    .collect()
+            }
+            Check::DanglingArticles => find_dangling_articles(&tx)?
+                .iter()
+                .map(|article| format!("article {} -> zettel {}", article.id, article.zettel_id))
+                .collect(),
+            Check::OrphanEmbeddings => find_orphan_embeddings(&tx)?
+                .iter()
+                .map(|zettel_id| format!("zettel {}", zettel_id))
+                .collect(),
+            Check::MissingEmbeddings => find_zettels_without_embedding(&tx)?
+                .iter()
+                .map(|zettel| format!("{}: {}", zettel.id, zettel.get_shim(60)))
+                .collect(),
+            Check::OrphanTags => find_orphan_tags(&tx)?
+                .iter()
+                .map(|zettel_tag| format!("#{} on zettel {}", zettel_tag.tag, zettel_tag.zettel_id))
+                .collect(),
+        };
+        if !items.is_empty() {
+            findings.push(Finding { check, items });
+        }
+    }
+    tx.commit()?;
+
+    Ok(findings)
+}
+
+/// Repairs everything a check finds and returns the number of fixed items.
+/// Problems are looked up again, since earlier repairs may have fixed some of them.
+#[instrument(skip(db, llm_config))]
+pub async fn repair(db: &Db, llm_config: &LlmConfig, check: Check) -> Result<usize, TshError> {
+    // Embedding needs the model, so it must not hold the database in the meantime.
+    if check == Check::MissingEmbeddings {
+        return embed_missing(db, llm_config).await;
+    }
+
+    let mut conn = db.lock().await;
+    let tx = conn.transaction()?;
+
+    let n_fixed = match check {
+        Check::SelfEdges | Check::DanglingEdges | Check::CycleEdges => {
+            let edges = find_broken_edges(&tx, check)?;
+            for edge in edges.iter() {
+                delete_edge(&tx, edge)?;
+            }
+            // With cycles left the lineage would be wrong anyway, it's rebuilt once they are gone.
+            let is_acyclic = find_cycle_edges(&find_all_edges(&tx)?).is_empty();
+            if !edges.is_empty() && is_acyclic {
+                let n_rows = rebuild_lineage(&tx)?;
+                info!(n_rows, "Rebuilt zettel lineage");
+            }
+            edges.len()
+        }
+        Check::LineageDrift => {
+            let (missing, stale) = find_lineage_drift(&tx)?;
+            if !missing.is_empty() || !stale.is_empty() {
+                rebuild_lineage(&tx)?;
+            }
+            missing.len() + stale.len()
+        }
+        Check::DanglingArticles => {
+            let articles = find_dangling_articles(&tx)?;
+            // Several articles can share a zettel, restore it only once.
+            let mut restored: HashSet<i64> = HashSet::new();
+            for article in articles.iter() {
+                if restored.insert(article.zettel_id) {
+                    restore_zettel_from_article(&tx, article)?;
+                }
+            }
+            articles.len()
+        }
+        Check::OrphanEmbeddings => {
+            let zettel_ids = find_orphan_embeddings(&tx)?;
+            for zettel_id in zettel_ids.iter() {
+                delete_embedding(&tx, *zettel_id)?;
+            }
+            zettel_ids.len()
+        }
+        Check::OrphanTags => {
+            let zettel_tags = find_orphan_tags(&tx)?;
+            for zettel_tag in zettel_tags.iter() {
+                delete_tag_for_zettel_if_exists(&tx, zettel_tag.zettel_id, &zettel_tag.tag)?;
+            }
+            zettel_tags.len()
+        }
+        Check::MissingEmbeddings => 0,
+    };
+    tx.commit()?;
+
+    info!(%check, n_fixed, "Repaired");
+    Ok(n_fixed)
+}
+
+async fn embed_missing(db: &Db, llm_config: &LlmConfig) -> Result<usize, TshError> {
+    let zettels = {
+        let mut conn = db.lock().await;
+        let tx = conn.transaction()?;
+        let zettels = find_zettels_without_embedding(&tx)?;
+        tx.commit()?;
+        zettels
+    };
+
+    let mut llm_client = LlmClient::from(llm_config);
+    for zettel in zettels.iter() {
+        let embedding = llm_client.embed(&zettel.content).await?;
+        let mut conn = db.lock().await;
+        let tx = conn.transaction()?;
+        store_embedding(&tx, zettel.id, embedding)?;
+        tx.commit()?;
+    }
+
+    info!(check = %Check::MissingEmbeddings, n_fixed = zettels.len(), "Repaired");
+    Ok(zettels.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{Json, Router, routing::post};
+
+    use super::*;
+    use crate::{
+        db::store_zettel,
+        test_support::{embedding_response, insert_zettel, serve_stub, stub_llm_config, temp_db},
+    };
+
+    fn checks(findings: &[Finding]) -> Vec<Check> {
+        findings.iter().map(|finding| finding.check).collect()
+    }
+
+    #[tokio::test]
+    async fn lineage_drift_is_reported_and_rebuilt() {
+        let (_dir, db) = temp_db().await;
+        let (root, grandchild) = {
+            let mut conn = db.lock().await;
+            let tx = conn.transaction().unwrap();
+            let root = store_zettel(&tx, "Root", vec![0.1; 384], vec![]).unwrap();
+            let child = store_zettel(&tx, "Child", vec![0.1; 384], vec![root.id]).unwrap();
+            let grandchild =
+                store_zettel(&tx, "Grandchild", vec![0.1; 384], vec![child.id]).unwrap();
+            tx.execute(
+                "delete from zettel_lineage where ancestor_id = ? and descendant_id = ?",
+                [root.id, grandchild.id],
+            )
+            .unwrap();
+            tx.execute(
+                "insert into zettel_lineage (ancestor_id, descendant_id, separation) values (?, ?, 1)",
+                [grandchild.id, root.id],
+            )
+            .unwrap();
+            tx.commit().unwrap();
+            (root, grandchild)
+        };
+        let llm_config = stub_llm_config("http://127.0.0.1:1");
+
+        let findings = audit(&db).await.unwrap();
+        assert_eq!(checks(&findings), [Check::LineageDrift]);
+        assert_eq!(
+            findings[0].items,
+            [
+                format!("missing: {} -> {} (separation 2)", grandchild.id, root.id),
+                format!("stale: {} -> {} (separation 1)", root.id, grandchild.id),
+            ]
+        );
+
+        assert_eq!(
+            repair(&db, &llm_config, Check::LineageDrift).await.unwrap(),
+            2
+        );
+        assert!(audit(&db).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn missing_embeddings_are_embedded() {
+        let api_base = serve_stub(
+            Router::new().route("/embeddings", post(|| async { Json(embedding_response()) })),
+        )
+        .await;
+        let (_dir, db) = temp_db().await;
+        let zettel_id = insert_zettel(&db, "Not embedded yet").await;
+
+        let findings = audit(&db).await.unwrap();
+        assert_eq!(checks(&findings), [Check::MissingEmbeddings]);
+        assert_eq!(
+            findings[0].items,
+            [format!("{}: Not embedded yet", zettel_id)]
+        );
+
+        let n_fixed = repair(&db, &stub_llm_config(&api_base), Check::MissingEmbeddings)
+            .await
+            .unwrap();
+        assert_eq!(n_fixed, 1);
+        assert!(audit(&db).await.unwrap().is_empty());
+    }
+}
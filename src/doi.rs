@@ -0,0 +1,152 @@
+use reqwest::header::ACCEPT;
+use tracing::{info, instrument};
+
+use crate::{bibtex::parse_bibtex, error::TshError, model::SourceMetadata};
+
+/// Used when `doi_resolver_url` is not configured.
+pub const DEFAULT_DOI_RESOLVER_URL: &str = "https://doi.org";
+
+/// Looks up the metadata registered for a DOI.
+#[trait_variant::make(Send)]
+pub trait DoiResolver {
+    async fn resolve(&self, doi: &str) -> Result<SourceMetadata, TshError>;
+}
+
+/// Resolves DOIs through doi.org content negotiation, which redirects to the registration
+/// agency (Crossref, DataCite, ...) and returns BibTeX. Any server answering
+/// `GET <base_url>/<doi>` with BibTeX works, e.g. a local stub.
+pub struct HttpDoiResolver {
+    base_url: String,
+    client: reqwest::Client,
+}
+
+impl HttpDoiResolver {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+impl DoiResolver for HttpDoiResolver {
+    #[instrument(skip_all)]
+    async fn resolve(&self, doi: &str) -> Result<SourceMetadata, TshError> {
+        let doi = normalize_doi(doi);
+        let url = format!("{}/{}", self.base_url, doi);
+        let request_error = |e: reqwest::Error| TshError::Source(format!("{}: {}", url, e));
+
+        let response = self
+            .client
+            .get(&url)
+            .header(ACCEPT, "application/x-bibtex")
+            .send()
+            .await
+            .map_err(request_error)?;
+        if !response.status().is_success() {
+            return Err(TshError::Source(format!(
+                "Could not resolve DOI {}: {}",
+                doi,
+                response.status()
+            )));
+        }
+        let bibtex = response.text().await.map_err(request_error)?;
+
+        let mut metadata = parse_bibtex(&bibtex)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| TshError::Source(format!("No metadata found for DOI {}", doi)))?;
+        metadata.doi = Some(doi);
+        info!("Resolved DOI");
+        Ok(metadata)
+    }
+}
+
+/// DOIs are case-insensitive and often pasted as links, so they are stored as the bare,
+/// lowercased identifier.
+pub fn normalize_doi(doi: &str) -> String {
+    let doi = doi.trim();
+    let lowercase = doi.to_lowercase();
+    let prefixes = [
+        "https://doi.org/",
+        "http://doi.org/",
+        "https://dx.doi.org/",
+        "http://dx.doi.org/",
+        "doi:",
+    ];
+    let bare = prefixes
+        .iter()
+        .find_map(|prefix| lowercase.strip_prefix(prefix))
+        .unwrap_or(&lowercase);
+    bare.trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        Router,
+        extract::Path,
+        http::{HeaderMap, StatusCode, header::ACCEPT},
+        routing::get,
+    };
+
+    use super::*;
+    use crate::test_support::serve_stub;
+
+    #[test]
+    fn normalize_doi_strips_links_and_case() {
+        let cases = [
+            ("10.1000/XYZ123", "10.1000/xyz123"),
+            ("  10.1000/xyz123\n", "10.1000/xyz123"),
+            ("https://doi.org/10.1000/xyz123", "10.1000/xyz123"),
+            ("HTTP://DX.DOI.ORG/10.1000/xyz123", "10.1000/xyz123"),
+            ("doi:10.1000/xyz123", "10.1000/xyz123"),
+        ];
+        for (doi, expected) in cases {
+            assert_eq!(normalize_doi(doi), expected, "doi {:?}", doi);
+        }
+    }
+
+    /// Answers like doi.org content negotiation, but only knows a single DOI.
+    async fn stub_resolver() -> HttpDoiResolver {
+        let router = Router::new().route(
+            "/{*doi}",
+            get(|Path(doi): Path<String>, headers: HeaderMap| async move {
+                let wants_bibtex = headers
+                    .get(ACCEPT)
+                    .is_some_and(|accept| accept == "application/x-bibtex");
+                if doi != "10.1000/xyz123" || !wants_bibtex {
+                    return (StatusCode::NOT_FOUND, String::new());
+                }
+                let bibtex = "@article{Doe_2020, title={Stub Paper}, author={Doe, Jane}, \
+                    year={2020}, DOI={10.1000/XYZ123}}";
+                (StatusCode::OK, bibtex.to_string())
+            }),
+        );
+        HttpDoiResolver::new(&format!("{}/", serve_stub(router).await))
+    }
+
+    #[tokio::test]
+    async fn http_resolver_reads_bibtex_from_the_server() {
+        let resolver = stub_resolver().await;
+
+        let metadata = resolver
+            .resolve("https://doi.org/10.1000/XYZ123")
+            .await
+            .unwrap();
+
+        assert_eq!(metadata.title, "Stub Paper");
+        assert_eq!(metadata.author.as_deref(), Some("Doe, Jane"));
+        assert_eq!(metadata.year, Some(2020));
+        assert_eq!(metadata.doi.as_deref(), Some("10.1000/xyz123"));
+    }
+
+    #[tokio::test]
+    async fn http_resolver_reports_unknown_dois() {
+        let resolver = stub_resolver().await;
+
+        let error = resolver.resolve("10.1000/unknown").await.unwrap_err();
+
+        assert!(error.to_string().contains("10.1000/unknown"));
+    }
+}
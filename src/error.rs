@@ -0,0 +1,78 @@
+use std::fmt;
+
+#[derive(Debug)]
+pub enum TshError {
+    Config(String),
+    Database(rusqlite::Error),
+    /// A change the database triggers reject because it would break the zettel DAG.
+    Integrity(String),
+    Migration(rusqlite_migration::Error),
+    Embedding(String),
+    Chat(String),
+    Editor(String),
+    Encryption(String),
+    Source(String),
+    Lsp(String),
+    Io(std::io::Error),
+}
+
+impl fmt::Display for TshError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TshError::Config(msg) => write!(f, "Config error: {}", msg),
+            TshError::Database(e) => write!(f, "Database error: {}", e),
+            TshError::Integrity(msg) => write!(f, "Integrity error: {}", msg),
+            TshError::Migration(e) => write!(f, "Migration error: {}", e),
+            TshError::Embedding(msg) => write!(f, "Embedding error: {}", msg),
+            TshError::Chat(msg) => write!(f, "Chat model error: {}", msg),
+            TshError::Editor(msg) => write!(f, "Editor error: {}", msg),
+            TshError::Encryption(msg) => write!(f, "Encryption error: {}", msg),
+            TshError::Source(msg) => write!(f, "Source error: {}", msg),
+            TshError::Lsp(msg) => write!(f, "Language server error: {}", msg),
+            TshError::Io(e) => write!(f, "IO error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for TshError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TshError::Database(e) => Some(e),
+            TshError::Migration(e) => Some(e),
+            TshError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<rusqlite::Error> for TshError {
+    fn from(e: rusqlite::Error) -> Self {
+        match e {
+            // Raised by `raise(abort, ...)` in a trigger.
+            rusqlite::Error::SqliteFailure(failure, Some(msg))
+                if failure.extended_code == rusqlite::ffi::SQLITE_CONSTRAINT_TRIGGER =>
+            {
+                TshError::Integrity(msg)
+            }
+            e => TshError::Database(e),
+        }
+    }
+}
+
+impl From<rusqlite_migration::Error> for TshError {
+    fn from(e: rusqlite_migration::Error) -> Self {
+        TshError::Migration(e)
+    }
+}
+
+impl From<std::io::Error> for TshError {
+    fn from(e: std::io::Error) -> Self {
+        TshError::Io(e)
+    }
+}
+
+impl From<toml::de::Error> for TshError {
+    fn from(e: toml::de::Error) -> Self {
+        TshError::Config(e.to_string())
+    }
+}
@@ -0,0 +1,43 @@
+use chrono::NaiveDate;
+
+/// Journal entries are tagged `journal/YYYY-MM-DD`.
+pub const JOURNAL_TAG_PREFIX: &str = "journal/";
+const JOURNAL_DATE_FORMAT: &str = "%Y-%m-%d";
+
+pub fn journal_tag(date: NaiveDate) -> String {
+    format!("{}{}", JOURNAL_TAG_PREFIX, date.format(JOURNAL_DATE_FORMAT))
+}
+
+/// Returns the date of a journal tag, `None` for any other tag.
+pub fn parse_journal_tag(tag: &str) -> Option<NaiveDate> {
+    let date = tag.strip_prefix(JOURNAL_TAG_PREFIX)?;
+    NaiveDate::parse_from_str(date, JOURNAL_DATE_FORMAT).ok()
+}
+
+/// The content a new journal entry starts with.
+pub fn journal_heading(date: NaiveDate) -> String {
+    format!("# {}\n\n", date.format(JOURNAL_DATE_FORMAT))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_dated_journal_tags_are_parsed() {
+        let date = NaiveDate::from_ymd_opt(2025, 1, 2).unwrap();
+        assert_eq!(journal_tag(date), "journal/2025-01-02");
+        assert_eq!(parse_journal_tag("journal/2025-01-02"), Some(date));
+
+        for tag in [
+            "journal/notes",
+            "journal/",
+            "journal/2025-13-01",
+            "journal/2025-01-02/extra",
+            "2025-01-02",
+            "notes/journal/2025-01-02",
+        ] {
+            assert_eq!(parse_journal_tag(tag), None, "{}", tag);
+        }
+    }
+}
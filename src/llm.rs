@@ -1,15 +1,19 @@
+use tracing::instrument;
+
+use crate::{config::ApiKey, error::TshError, tui::app::LlmConfig};
 use async_openai::Client;
 use async_openai::config::OpenAIConfig;
-use async_openai::types::CreateEmbeddingRequestArgs;
-use std::error::Error;
-
-use crate::tui::app::LlmConfig;
+use async_openai::types::{
+    ChatCompletionRequestSystemMessageArgs, ChatCompletionRequestUserMessageArgs,
+    CreateChatCompletionRequestArgs, CreateEmbeddingRequestArgs,
+};
 
 #[derive(Debug, Clone)]
 pub struct LlmClient {
     pub api_base: String,
-    pub api_key: String,
+    pub api_key: ApiKey,
     pub embedding_model: String,
+    pub chat_model: Option<String>,
 }
 
 impl From<&LlmConfig> for LlmClient {
@@ -18,27 +22,89 @@ impl From<&LlmConfig> for LlmClient {
             api_base: config.api_base.clone(),
             api_key: config.api_key.clone(),
             embedding_model: config.embeddings_model.clone(),
+            chat_model: config.chat_model.clone(),
         }
     }
 }
 
 impl LlmClient {
-    pub async fn embed(&mut self, content: &str) -> Result<Vec<f32>, Box<dyn Error>> {
-        let client = Client::with_config(
+    /// The configured chat model, features that need one fail with this error without it.
+    pub fn chat_model(&self) -> Result<String, TshError> {
+        self.chat_model
+            .clone()
+            .ok_or(TshError::Config("No chat_model configured".to_string()))
+    }
+
+    fn client(&self) -> Result<Client<OpenAIConfig>, TshError> {
+        Ok(Client::with_config(
             OpenAIConfig::new()
                 .with_api_base(self.api_base.clone())
-                .with_api_key(self.api_key.clone()),
-        );
+                .with_api_key(self.api_key.resolve()?),
+        ))
+    }
 
+    #[instrument(skip_all, fields(model = %self.embedding_model))]
+    pub async fn embed(&mut self, content: &str) -> Result<Vec<f32>, TshError> {
         let request = CreateEmbeddingRequestArgs::default()
             .model(self.embedding_model.clone())
             .input([content])
-            .build()?;
+            .build()
+            .map_err(|e| TshError::Embedding(e.to_string()))?;
 
-        let response = client.embeddings().create(request).await?;
+        let response = self
+            .client()?
+            .embeddings()
+            .create(request)
+            .await
+            .map_err(|e| TshError::Embedding(e.to_string()))?;
 
-        let choice: Vec<f32> = response.data[0].embedding.clone();
+        let choice: Vec<f32> = response
+            .data
+            .first()
+            .map(|embedding| embedding.embedding.clone())
+            .ok_or(TshError::Embedding(
+                "Provider returned no embedding".to_string(),
+            ))?;
 
         Ok(choice)
     }
+
+    /// Sends a single system/user exchange to the configured chat model and returns the
+    /// content of the first choice.
+    #[instrument(skip_all, fields(model = ?self.chat_model))]
+    pub async fn complete(&mut self, system: &str, prompt: &str) -> Result<String, TshError> {
+        let chat_model = self.chat_model()?;
+
+        let request = CreateChatCompletionRequestArgs::default()
+            .model(chat_model)
+            .messages([
+                ChatCompletionRequestSystemMessageArgs::default()
+                    .content(system)
+                    .build()
+                    .map_err(|e| TshError::Chat(e.to_string()))?
+                    .into(),
+                ChatCompletionRequestUserMessageArgs::default()
+                    .content(prompt)
+                    .build()
+                    .map_err(|e| TshError::Chat(e.to_string()))?
+                    .into(),
+            ])
+            .build()
+            .map_err(|e| TshError::Chat(e.to_string()))?;
+
+        let response = self
+            .client()?
+            .chat()
+            .create(request)
+            .await
+            .map_err(|e| TshError::Chat(e.to_string()))?;
+
+        let answer = response
+            .choices
+            .first()
+            .and_then(|choice| choice.message.content.clone())
+            .ok_or(TshError::Chat("Chat model returned no content".to_string()))?;
+
+        Ok(answer)
+    }
 }
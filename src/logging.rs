@@ -0,0 +1,94 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use tracing::Level;
+use tracing_appender::{
+    non_blocking::WorkerGuard,
+    rolling::{RollingFileAppender, Rotation},
+};
+use tracing_subscriber::{EnvFilter, fmt, fmt::format::FmtSpan};
+
+use crate::error::TshError;
+
+/// Number of daily log files kept before the oldest one is deleted.
+const MAX_LOG_FILES: usize = 7;
+
+static LOG_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+pub fn log_dir(data_dir: &str) -> PathBuf {
+    Path::new(data_dir).join("logs")
+}
+
+/// Installs a global subscriber writing to a daily rotating file in `<data_dir>/logs`.
+/// The returned guard flushes pending entries when dropped, so keep it alive until exit.
+pub fn init_logging(data_dir: &str, level: &str) -> Result<WorkerGuard, TshError> {
+    let dir = log_dir(data_dir);
+    fs::create_dir_all(&dir)?;
+    let appender = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix("tsh")
+        .filename_suffix("log")
+        .max_log_files(MAX_LOG_FILES)
+        .build(&dir)
+        .map_err(|e| TshError::Config(format!("Could not set up logging: {}", e)))?;
+    let (writer, guard) = tracing_appender::non_blocking(appender);
+
+    // A plain level only applies to tsh itself, otherwise the HTTP client drowns out
+    // everything else. Full filter directives are passed through as they are.
+    let directives = match level.parse::<Level>() {
+        Ok(level) => format!("warn,tsh={}", level),
+        Err(_) => level.to_string(),
+    };
+    let filter = EnvFilter::try_new(&directives)
+        .map_err(|e| TshError::Config(format!("Invalid log_level '{}': {}", level, e)))?;
+
+    fmt()
+        .with_env_filter(filter)
+        .with_writer(writer)
+        .with_ansi(false)
+        // Closing a span logs its duration, which is how slow DB or embedding calls show up.
+        .with_span_events(FmtSpan::CLOSE)
+        .init();
+
+    let _ = LOG_DIR.set(dir);
+
+    Ok(guard)
+}
+
+/// Returns up to `n` of the most recent log lines, oldest first.
+pub fn read_recent_log_lines(dir: &Path, n: usize) -> Result<Vec<String>, TshError> {
+    let mut log_files: Vec<PathBuf> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "log"))
+            .collect(),
+        Err(_) => return Ok(vec![]),
+    };
+    // File names end in the date, so the newest file sorts last.
+    log_files.sort();
+
+    let mut lines: Vec<String> = vec![];
+    for path in log_files.iter().rev() {
+        let content = fs::read_to_string(path)?;
+        let mut file_lines: Vec<String> = content.lines().map(|line| line.to_string()).collect();
+        file_lines.append(&mut lines);
+        lines = file_lines;
+        if lines.len() >= n {
+            break;
+        }
+    }
+
+    let skip = lines.len().saturating_sub(n);
+    Ok(lines.split_off(skip))
+}
+
+/// Reads recent lines from the directory the global subscriber writes to.
+pub fn read_recent_app_log_lines(n: usize) -> Result<Vec<String>, TshError> {
+    match LOG_DIR.get() {
+        Some(dir) => read_recent_log_lines(dir, n),
+        None => Ok(vec![]),
+    }
+}
@@ -0,0 +1,711 @@
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::{
+    CodeAction, CodeActionOrCommand, CodeActionParams, CodeActionProviderCapability,
+    CompletionItem, CompletionItemKind, CompletionItemLabelDetails, CompletionList,
+    CompletionOptions, CompletionParams, CompletionResponse, CompletionTextEdit, Documentation,
+    GotoDefinitionParams, GotoDefinitionResponse, Hover, HoverContents, HoverParams,
+    HoverProviderCapability, Location, MarkupContent, MarkupKind, OneOf, Position, Range,
+    ServerCapabilities, TextDocumentContentChangeEvent, TextDocumentPositionParams,
+    TextDocumentSyncCapability, TextDocumentSyncKind, TextEdit, Url, WorkspaceEdit,
+    notification::{
+        DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Exit,
+        Notification as LspNotification,
+    },
+    request::{
+        CodeActionRequest, Completion, GotoDefinition, HoverRequest, Request as LspRequest,
+        Shutdown,
+    },
+};
+use serde::{Serialize, de::DeserializeOwned};
+use std::{collections::HashMap, fs, io::Write, thread};
+use tempfile::TempDir;
+use tokio::{sync::mpsc, task};
+use tracing::{debug, info, instrument, warn};
+
+use crate::{
+    api::{find_tags, find_zettels, get_n_recent_zettels, get_tags, get_zettel},
+    db::Db,
+    error::TshError,
+    model::Zettel,
+    tui::app::LlmConfig,
+};
+
+/// Number of recent zettels offered when completing a reference by id.
+const RECENT_ZETTELS: i64 = 50;
+/// Shorter queries don't say enough to search by meaning, and searching on every keystroke
+/// would call the embedding API for each one.
+const MIN_SEARCH_QUERY_CHARS: usize = 3;
+/// Number of related zettels offered as code actions.
+const RELATED_ZETTELS: usize = 5;
+const SHIM_WIDTH: usize = 60;
+
+/// Runs a language server on stdin/stdout until the client shuts it down. It completes
+/// `[[id]]` references and `#tags`, shows referenced zettels on hover, opens them on
+/// go-to-definition, and offers references to related zettels as code actions.
+pub async fn lsp(db: Db, llm_config: LlmConfig) -> Result<(), TshError> {
+    // Only readable by the user and removed when the server stops, as the copies are
+    // decrypted.
+    let notes_dir = tempfile::Builder::new().prefix("tsh-lsp-").tempdir()?;
+
+    let (connection, io_threads) = Connection::stdio();
+    // Initializing waits for the client, so it runs on a blocking thread.
+    let connection = task::spawn_blocking(move || {
+        let capabilities = serde_json::to_value(server_capabilities()).map_err(lsp_error)?;
+        connection.initialize(capabilities).map_err(lsp_error)?;
+        Ok::<_, TshError>(connection)
+    })
+    .await
+    .map_err(lsp_error)??;
+    info!("Language server initialized");
+
+    let mut server = LanguageServer {
+        db,
+        llm_config,
+        notes_dir,
+        documents: HashMap::new(),
+        searched: None,
+        related: None,
+    };
+    server.run(&connection).await?;
+
+    drop(connection);
+    task::spawn_blocking(move || io_threads.join())
+        .await
+        .map_err(lsp_error)??;
+    info!("Language server stopped");
+    Ok(())
+}
+
+struct LanguageServer {
+    db: Db,
+    llm_config: LlmConfig,
+    /// Where go-to-definition writes copies of the referenced zettels.
+    notes_dir: TempDir,
+    documents: HashMap<Url, String>,
+    /// The zettels found for the last reference query, as clients ask again while typing.
+    searched: Option<(String, Vec<Zettel>)>,
+    /// The related zettels for the last text, as clients ask for code actions a lot.
+    related: Option<(String, Vec<Zettel>)>,
+}
+
+impl LanguageServer {
+    /// Handles messages until the client sends `exit` or closes stdin.
+    async fn run(&mut self, connection: &Connection) -> Result<(), TshError> {
+        // Receiving from the connection blocks, so a thread passes the messages on.
+        let (sender, mut messages) = mpsc::unbounded_channel();
+        let receiver = connection.receiver.clone();
+        thread::spawn(move || {
+            for message in receiver {
+                if sender.send(message).is_err() {
+                    break;
+                }
+            }
+        });
+
+        while let Some(message) = messages.recv().await {
+            match message {
+                Message::Request(request) => {
+                    let response = if request.method == Shutdown::METHOD {
+                        Response::new_ok(request.id, ())
+                    } else {
+                        self.handle_request(request).await
+                    };
+                    connection
+                        .sender
+                        .send(Message::Response(response))
+                        .map_err(lsp_error)?;
+                }
+                Message::Notification(notification) if notification.method == Exit::METHOD => {
+                    break;
+                }
+                Message::Notification(notification) => self.handle_notification(notification),
+                Message::Response(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    async fn handle_request(&mut self, request: Request) -> Response {
+        match request.method.as_str() {
+            Completion::METHOD => {
+                respond(request, async |params| self.complete(params).await).await
+            }
+            HoverRequest::METHOD => respond(request, async |params| self.hover(params).await).await,
+            GotoDefinition::METHOD => {
+                respond(request, async |params| self.definition(params).await).await
+            }
+            CodeActionRequest::METHOD => {
+                respond(request, async |params| self.code_actions(params).await).await
+            }
+            method => Response::new_err(
+                request.id,
+                ErrorCode::MethodNotFound as i32,
+                format!("Unsupported method {}", method),
+            ),
+        }
+    }
+
+    fn handle_notification(&mut self, notification: Notification) {
+        match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                if let Some(params) = parse::<lsp_types::DidOpenTextDocumentParams>(notification) {
+                    self.documents
+                        .insert(params.text_document.uri, params.text_document.text);
+                }
+            }
+            DidChangeTextDocument::METHOD => {
+                if let Some(params) = parse::<lsp_types::DidChangeTextDocumentParams>(notification)
+                    && let Some(TextDocumentContentChangeEvent { text, .. }) =
+                        params.content_changes.into_iter().last()
+                {
+                    self.documents.insert(params.text_document.uri, text);
+                }
+            }
+            DidCloseTextDocument::METHOD => {
+                if let Some(params) = parse::<lsp_types::DidCloseTextDocumentParams>(notification) {
+                    self.documents.remove(&params.text_document.uri);
+                }
+            }
+            method => debug!(method, "Ignoring notification"),
+        }
+    }
+
+    /// Returns the line of the document the position is in.
+    fn line(&self, position: &TextDocumentPositionParams) -> Option<&str> {
+        self.documents
+            .get(&position.text_document.uri)?
+            .lines()
+            .nth(position.position.line as usize)
+    }
+
+    #[instrument(skip_all)]
+    async fn complete(
+        &mut self,
+        params: CompletionParams,
+    ) -> Result<Option<CompletionResponse>, TshError> {
+        let position = params.text_document_position.position;
+        let Some(line) = self.line(&params.text_document_position) else {
+            return Ok(None);
+        };
+        let cursor = byte_offset(line, position.character);
+        let (before, after) = line.split_at(cursor);
+        let closed = after.starts_with("]]");
+
+        let items = match completion_context(before) {
+            Some(CompletionContext::Reference { query, start }) => {
+                let range = Range::new(
+                    Position::new(position.line, utf16_len(&line[..start])),
+                    position,
+                );
+                let query = query.to_string();
+                let by_id = query.chars().all(|c| c.is_ascii_digit());
+                let zettels: Vec<Zettel> = if by_id {
+                    get_n_recent_zettels(&self.db, RECENT_ZETTELS)
+                        .await?
+                        .into_iter()
+                        .filter(|zettel| zettel.id.to_string().starts_with(&query))
+                        .collect()
+                } else if query.chars().count() < MIN_SEARCH_QUERY_CHARS {
+                    // Incomplete, so the client asks again once more is typed.
+                    return Ok(Some(CompletionResponse::List(CompletionList {
+                        is_incomplete: true,
+                        items: vec![],
+                    })));
+                } else {
+                    match &self.searched {
+                        Some((searched_query, zettels)) if *searched_query == query => {
+                            zettels.clone()
+                        }
+                        _ => {
+                            let zettels = find_zettels(&self.db, &self.llm_config, &query).await?;
+                            self.searched = Some((query.clone(), zettels.clone()));
+                            zettels
+                        }
+                    }
+                };
+                zettels
+                    .iter()
+                    .map(|zettel| CompletionItem {
+                        label: zettel.id.to_string(),
+                        label_details: Some(CompletionItemLabelDetails {
+                            detail: None,
+                            description: Some(zettel.get_shim(SHIM_WIDTH)),
+                        }),
+                        kind: Some(CompletionItemKind::REFERENCE),
+                        detail: Some(zettel.get_datetime_string()),
+                        documentation: Some(Documentation::MarkupContent(markdown(
+                            zettel.content.clone(),
+                        ))),
+                        // Results of a semantic search rarely contain what was typed.
+                        filter_text: (!by_id).then(|| query.clone()),
+                        text_edit: Some(CompletionTextEdit::Edit(TextEdit::new(
+                            range,
+                            if closed {
+                                zettel.id.to_string()
+                            } else {
+                                format!("{}]]", zettel.id)
+                            },
+                        ))),
+                        ..Default::default()
+                    })
+                    .collect()
+            }
+            Some(CompletionContext::Tag { prefix, start }) => {
+                let range = Range::new(
+                    Position::new(position.line, utf16_len(&line[..start])),
+                    position,
+                );
+                find_tags(&self.db, prefix)
+                    .await?
+                    .into_iter()
+                    .map(|tag| CompletionItem {
+                        label: format!("#{}", tag),
+                        kind: Some(CompletionItemKind::KEYWORD),
+                        text_edit: Some(CompletionTextEdit::Edit(TextEdit::new(range, tag))),
+                        ..Default::default()
+                    })
+                    .collect()
+            }
+            None => return Ok(None),
+        };
+
+        Ok(Some(CompletionResponse::Array(items)))
+    }
+
+    #[instrument(skip_all)]
+    async fn hover(&mut self, params: HoverParams) -> Result<Option<Hover>, TshError> {
+        let Some(zettel_id) = self.reference_at(&params.text_document_position_params) else {
+            return Ok(None);
+        };
+        let zettel = get_zettel(&self.db, zettel_id).await?;
+        let tags = get_tags(&self.db, zettel_id).await?;
+
+        let mut text = format!("**{}** · {}\n\n", zettel.id, zettel.get_datetime_string());
+        if !tags.is_empty() {
+            let tags: Vec<String> = tags.iter().map(|tag| format!("#{}", tag.tag)).collect();
+            text.push_str(&format!("{}\n\n", tags.join(" ")));
+        }
+        text.push_str(&zettel.content);
+
+        Ok(Some(Hover {
+            contents: HoverContents::Markup(markdown(text)),
+            range: None,
+        }))
+    }
+
+    /// Writes the referenced zettel to a file and points there. The file is a copy for
+    /// reading, edits to it are not stored, and it is removed when the server stops.
+    #[instrument(skip_all)]
+    async fn definition(
+        &mut self,
+        params: GotoDefinitionParams,
+    ) -> Result<Option<GotoDefinitionResponse>, TshError> {
+        let Some(zettel_id) = self.reference_at(&params.text_document_position_params) else {
+            return Ok(None);
+        };
+        let zettel = get_zettel(&self.db, zettel_id).await?;
+
+        let path = self.notes_dir.path().join(format!("{}.md", zettel.id));
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        options.open(&path)?.write_all(zettel.content.as_bytes())?;
+        let uri = Url::from_file_path(&path)
+            .map_err(|_| TshError::Lsp(format!("Invalid path {}", path.display())))?;
+
+        Ok(Some(GotoDefinitionResponse::Scalar(Location::new(
+            uri,
+            Range::default(),
+        ))))
+    }
+
+    /// Offers to reference zettels related to the selection, or to the whole document if
+    /// nothing is selected. The reference is inserted at the end of the range.
+    #[instrument(skip_all)]
+    async fn code_actions(
+        &mut self,
+        params: CodeActionParams,
+    ) -> Result<Option<Vec<CodeActionOrCommand>>, TshError> {
+        // The actions don't have a kind, so they don't match any filter.
+        if params.context.only.is_some() {
+            return Ok(None);
+        }
+        let uri = params.text_document.uri;
+        let Some(document) = self.documents.get(&uri) else {
+            return Ok(None);
+        };
+        let range = params.range;
+        let text = if range.start == range.end {
+            document.clone()
+        } else {
+            text_in_range(document, range)
+        };
+        if text.trim().is_empty() {
+            return Ok(None);
+        }
+        let referenced = references(document);
+
+        let related = match &self.related {
+            Some((cached_text, zettels)) if *cached_text == text => zettels.clone(),
+            _ => {
+                let zettels = find_zettels(&self.db, &self.llm_config, &text).await?;
+                self.related = Some((text, zettels.clone()));
+                zettels
+            }
+        };
+
+        let actions = related
+            .iter()
+            .filter(|zettel| !referenced.contains(&zettel.id))
+            .take(RELATED_ZETTELS)
+            .map(|zettel| {
+                let edit = TextEdit::new(
+                    Range::new(range.end, range.end),
+                    format!("[[{}]]", zettel.id),
+                );
+                CodeActionOrCommand::CodeAction(CodeAction {
+                    title: format!(
+                        "Reference related note {}: {}",
+                        zettel.id,
+                        zettel.get_shim(SHIM_WIDTH)
+                    ),
+                    edit: Some(WorkspaceEdit {
+                        changes: Some(HashMap::from([(uri.clone(), vec![edit])])),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                })
+            })
+            .collect();
+
+        Ok(Some(actions))
+    }
+
+    fn reference_at(&self, position: &TextDocumentPositionParams) -> Option<i64> {
+        let line = self.line(position)?;
+        reference_at(line, byte_offset(line, position.position.character))
+    }
+}
+
+fn server_capabilities() -> ServerCapabilities {
+    ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        completion_provider: Some(CompletionOptions {
+            trigger_characters: Some(vec!["[".to_string(), "#".to_string()]),
+            ..Default::default()
+        }),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        definition_provider: Some(OneOf::Left(true)),
+        code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+        ..Default::default()
+    }
+}
+
+async fn respond<P, R>(
+    request: Request,
+    handler: impl AsyncFnOnce(P) -> Result<R, TshError>,
+) -> Response
+where
+    P: DeserializeOwned,
+    R: Serialize,
+{
+    let params = match serde_json::from_value(request.params) {
+        Ok(params) => params,
+        Err(e) => {
+            return Response::new_err(request.id, ErrorCode::InvalidParams as i32, e.to_string());
+        }
+    };
+    match handler(params).await {
+        Ok(result) => Response::new_ok(request.id, result),
+        Err(e) => {
+            warn!(method = request.method, error = %e, "Request failed");
+            Response::new_err(request.id, ErrorCode::InternalError as i32, e.to_string())
+        }
+    }
+}
+
+fn parse<P: DeserializeOwned>(notification: Notification) -> Option<P> {
+    serde_json::from_value(notification.params)
+        .inspect_err(|e| warn!(method = notification.method, error = %e, "Invalid notification"))
+        .ok()
+}
+
+fn lsp_error(e: impl std::fmt::Display) -> TshError {
+    TshError::Lsp(e.to_string())
+}
+
+fn markdown(value: String) -> MarkupContent {
+    MarkupContent {
+        kind: MarkupKind::Markdown,
+        value,
+    }
+}
+
+enum CompletionContext<'a> {
+    /// Inside an unclosed `[[`, `start` is the byte offset right after it.
+    Reference { query: &'a str, start: usize },
+    /// A word starting with `#`, `start` is the byte offset right after the `#`.
+    Tag { prefix: &'a str, start: usize },
+}
+
+/// Decides what to complete from the text on the line before the cursor.
+fn completion_context(before: &str) -> Option<CompletionContext<'_>> {
+    if let Some(open) = before.rfind("[[")
+        && !before[open..].contains("]]")
+    {
+        return Some(CompletionContext::Reference {
+            query: before[open + 2..].trim(),
+            start: open + 2,
+        });
+    }
+
+    let word = before.rsplit(char::is_whitespace).next()?;
+    let prefix = word.strip_prefix('#')?;
+    Some(CompletionContext::Tag {
+        prefix,
+        start: before.len() - prefix.len(),
+    })
+}
+
+/// Returns the id of the `[[id]]` reference that contains the byte offset `offset`. Each
+/// `]]` closes the last `[[` before it, so an unclosed `[[` earlier on the line is skipped.
+fn reference_at(line: &str, offset: usize) -> Option<i64> {
+    let mut start = 0;
+    while let Some(close) = line[start..].find("]]") {
+        let close = start + close;
+        if let Some(open) = line[start..close].rfind("[[") {
+            let open = start + open;
+            if (open..close + 2).contains(&offset) {
+                return line[open + 2..close].trim().parse().ok();
+            }
+        }
+        start = close + 2;
+    }
+    None
+}
+
+/// Returns the ids of all `[[id]]` references in the text.
+fn references(text: &str) -> Vec<i64> {
+    text.split("[[")
+        .skip(1)
+        .filter_map(|part| part.split_once("]]"))
+        .filter_map(|(id, _)| id.trim().parse().ok())
+        .collect()
+}
+
+/// LSP positions count UTF-16 code units, returns the byte offset in `line` instead.
+fn byte_offset(line: &str, character: u32) -> usize {
+    let mut units = 0;
+    for (idx, c) in line.char_indices() {
+        if units >= character as usize {
+            return idx;
+        }
+        units += c.len_utf16();
+    }
+    line.len()
+}
+
+fn utf16_len(text: &str) -> u32 {
+    text.encode_utf16().count() as u32
+}
+
+fn text_in_range(document: &str, range: Range) -> String {
+    let lines: Vec<&str> = document.lines().collect();
+    let (first, last) = (range.start.line as usize, range.end.line as usize);
+    let mut text = String::new();
+    for (idx, line) in lines.iter().enumerate().take(last + 1).skip(first) {
+        let from = if idx == first {
+            byte_offset(line, range.start.character)
+        } else {
+            0
+        };
+        let to = if idx == last {
+            byte_offset(line, range.end.character)
+        } else {
+            line.len()
+        };
+        text.push_str(&line[from..to.max(from)]);
+        if idx != last {
+            text.push('\n');
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{Json, Router, routing::post};
+    use lsp_types::{PartialResultParams, TextDocumentIdentifier, WorkDoneProgressParams};
+    use std::sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    };
+
+    use super::*;
+    use crate::{
+        api::{add_tag_to_zettel, add_zettel},
+        test_support::{embedding_response, serve_stub, stub_llm_config, temp_db},
+    };
+
+    /// A server with a stub embeddings API that counts the embeddings it returns.
+    async fn test_server() -> (tempfile::TempDir, LanguageServer, Arc<AtomicUsize>) {
+        let embeddings = Arc::new(AtomicUsize::new(0));
+        let counter = embeddings.clone();
+        let api_base = serve_stub(Router::new().route(
+            "/embeddings",
+            post(move || async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Json(embedding_response())
+            }),
+        ))
+        .await;
+        let (dir, db) = temp_db().await;
+        let server = LanguageServer {
+            db,
+            llm_config: stub_llm_config(&api_base),
+            notes_dir: tempfile::tempdir().unwrap(),
+            documents: HashMap::new(),
+            searched: None,
+            related: None,
+        };
+        (dir, server, embeddings)
+    }
+
+    async fn store(server: &LanguageServer, content: &str) -> Zettel {
+        add_zettel(&server.db, &server.llm_config, &[], &[], content)
+            .await
+            .unwrap()
+            .unwrap()
+    }
+
+    /// Opens `line` as the only line of a document and returns the position at `character`.
+    fn open(server: &mut LanguageServer, line: &str, character: u32) -> TextDocumentPositionParams {
+        let uri = Url::parse("file:///note.md").unwrap();
+        server.documents.insert(uri.clone(), line.to_string());
+        TextDocumentPositionParams::new(
+            TextDocumentIdentifier::new(uri),
+            Position::new(0, character),
+        )
+    }
+
+    async fn complete_at_end(server: &mut LanguageServer, line: &str) -> Vec<CompletionItem> {
+        let params = CompletionParams {
+            text_document_position: open(server, line, utf16_len(line)),
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+            context: None,
+        };
+        match server.complete(params).await.unwrap() {
+            Some(CompletionResponse::Array(items)) => items,
+            Some(CompletionResponse::List(list)) => list.items,
+            None => vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn reference_completion_searches_only_longer_new_queries() {
+        let (_dir, mut server, embeddings) = test_server().await;
+
+        for line in ["See [[ru", "See [[rust", "See [[rust"] {
+            complete_at_end(&mut server, line).await;
+        }
+
+        assert_eq!(embeddings.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            server.searched.map(|(query, _)| query).as_deref(),
+            Some("rust")
+        );
+    }
+
+    #[tokio::test]
+    async fn completes_references_by_id_and_tags() {
+        let (_dir, mut server, _) = test_server().await;
+        let zettel = store(&server, "Ownership").await;
+        add_tag_to_zettel(&server.db, zettel.id, "rust".to_string())
+            .await
+            .unwrap();
+
+        let items = complete_at_end(&mut server, &format!("See [[{}", zettel.id)).await;
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].label, zettel.id.to_string());
+        let Some(CompletionTextEdit::Edit(edit)) = &items[0].text_edit else {
+            panic!("Expected a text edit");
+        };
+        assert_eq!(edit.new_text, format!("{}]]", zettel.id));
+        assert_eq!(edit.range.start, Position::new(0, 6));
+
+        let items = complete_at_end(&mut server, "About #ru").await;
+        let labels: Vec<&str> = items.iter().map(|item| item.label.as_str()).collect();
+        assert_eq!(labels, ["#rust"]);
+    }
+
+    #[tokio::test]
+    async fn hover_shows_the_referenced_zettel() {
+        let (_dir, mut server, _) = test_server().await;
+        let zettel = store(&server, "Ownership").await;
+        add_tag_to_zettel(&server.db, zettel.id, "rust".to_string())
+            .await
+            .unwrap();
+
+        let position = open(&mut server, &format!("See [[{}]] here", zettel.id), 7);
+        let hover = server
+            .hover(HoverParams {
+                text_document_position_params: position,
+                work_done_progress_params: WorkDoneProgressParams::default(),
+            })
+            .await
+            .unwrap()
+            .unwrap();
+        let HoverContents::Markup(content) = hover.contents else {
+            panic!("Expected markup");
+        };
+        assert!(content.value.contains("#rust"));
+        assert!(content.value.ends_with("Ownership"));
+
+        let position = open(&mut server, &format!("See [[{}]] here", zettel.id), 1);
+        let hover = server
+            .hover(HoverParams {
+                text_document_position_params: position,
+                work_done_progress_params: WorkDoneProgressParams::default(),
+            })
+            .await
+            .unwrap();
+        assert!(hover.is_none());
+    }
+
+    #[tokio::test]
+    async fn definition_points_to_a_copy_of_the_referenced_zettel() {
+        let (_dir, mut server, _) = test_server().await;
+        let zettel = store(&server, "Ownership").await;
+
+        let position = open(&mut server, &format!("[[ see [[{}]]", zettel.id), 10);
+        let response = server
+            .definition(GotoDefinitionParams {
+                text_document_position_params: position,
+                work_done_progress_params: WorkDoneProgressParams::default(),
+                partial_result_params: PartialResultParams::default(),
+            })
+            .await
+            .unwrap();
+
+        let Some(GotoDefinitionResponse::Scalar(location)) = response else {
+            panic!("Expected a location");
+        };
+        let path = location.uri.to_file_path().unwrap();
+        assert!(path.starts_with(server.notes_dir.path()));
+        assert_eq!(fs::read_to_string(path).unwrap(), "Ownership");
+    }
+
+    #[test]
+    fn reference_at_skips_an_unclosed_opening_before_the_reference() {
+        let line = "[[ unclosed, see [[12]] and [[34]]";
+        assert_eq!(reference_at(line, line.find("12").unwrap()), Some(12));
+        assert_eq!(reference_at(line, line.find("34").unwrap()), Some(34));
+        assert_eq!(reference_at(line, 1), None);
+        assert_eq!(reference_at(line, line.find(" and").unwrap()), None);
+    }
+}
@@ -1,35 +1,85 @@
+use clap::Parser;
 use std::{error::Error, fs::create_dir_all};
 use tsh::{
-    db::migrate_to_latest,
-    load_config,
-    tui::app::{App, LlmConfig},
+    cli::{
+        Cli, CliCommand, ConfigCommand, run_ask, run_clip, run_config_show, run_decrypt,
+        run_doctor, run_encrypt, run_log, run_sources,
+    },
+    config::load_config,
+    datetime::{DateTimeDisplay, init_datetime_display},
+    logging::init_logging,
+    lsp::lsp,
+    serve::{load_or_create_token, serve},
+    tui::app::App,
 };
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
-    let args: Vec<String> = std::env::args().collect();
-    let maybe_config_path: Option<String> = if args.len() == 2 {
-        Some(args[1].clone())
-    } else {
-        None
-    };
-
-    let config = load_config(maybe_config_path)?;
-    let llm_config = LlmConfig::from(&config);
-
-    let data_dir = match config.data_dir {
-        Some(path) => path,
-        None => {
-            let home_dir = std::env::var("HOME")?;
-            format!("{}/.local/share/tsh/", home_dir)
-        }
-    };
+    let cli = Cli::parse();
+
+    let resolved = load_config(cli.config)?;
+    if let Some(CliCommand::Config {
+        command: ConfigCommand::Show,
+    }) = cli.command
+    {
+        run_config_show(&resolved)?;
+        return Ok(());
+    }
+
+    let config = &resolved.config;
+    let data_dir = resolved.data_dir.to_string_lossy().to_string();
     create_dir_all(&data_dir)?;
-    let db_path = format!("{}/zettelkasten.db", data_dir);
-    migrate_to_latest(&db_path).await?;
+    let log_level = config.log_level.as_deref().unwrap_or("info");
+    let _log_guard = init_logging(&data_dir, log_level)?;
+    init_datetime_display(DateTimeDisplay::try_from(config)?);
+
+    if let Some(CliCommand::Log { lines }) = cli.command {
+        return Ok(run_log(&data_dir, lines)?);
+    }
+
+    let vaults = resolved.vaults()?;
+    let vault_idx = resolved.select_vault(&vaults, cli.vault.as_deref())?;
+    let vault = vaults[vault_idx].clone();
+
+    match cli.command {
+        Some(CliCommand::Encrypt) => return Ok(run_encrypt(&vault).await?),
+        Some(CliCommand::Decrypt) => return Ok(run_decrypt(&vault)?),
+        _ => {}
+    }
+
+    let db = vault.open().await?;
 
-    let mut tsh_app = App::new(db_path, llm_config);
-    tsh_app.run().await?;
+    match cli.command {
+        Some(CliCommand::Ask { question, save }) => {
+            run_ask(&db, &vault.llm_config, &question, save).await?;
+        }
+        Some(CliCommand::Clip { url, extract }) => {
+            run_clip(&db, &vault.llm_config, &url, extract).await?;
+        }
+        Some(CliCommand::Doctor { fix }) => {
+            run_doctor(&db, &vault.llm_config, fix).await?;
+        }
+        Some(CliCommand::Serve { port }) => {
+            let token = load_or_create_token(&data_dir)?;
+            serve(db, vault.llm_config.clone(), port, token).await?;
+        }
+        Some(CliCommand::Lsp) => {
+            lsp(db, vault.llm_config.clone()).await?;
+        }
+        Some(CliCommand::Sources { command }) => {
+            run_sources(&db, &vault.llm_config, command).await?;
+        }
+        Some(
+            CliCommand::Config { .. }
+            | CliCommand::Log { .. }
+            | CliCommand::Encrypt
+            | CliCommand::Decrypt,
+        ) => {}
+        None => {
+            let mut tsh_app = App::new(vaults, vault_idx, db, resolved.templates_dir());
+            tsh_app.run().await?;
+        }
+    }
 
     Ok(())
 }
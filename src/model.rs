@@ -1,6 +1,10 @@
-use chrono::DateTime;
+use serde::Serialize;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
-#[derive(Debug, Clone)]
+use crate::datetime::{format_relative, format_timestamp};
+
+#[derive(Debug, Clone, Serialize)]
 pub struct Zettel {
     pub id: i64,
     pub content: String,
@@ -8,20 +12,61 @@ pub struct Zettel {
 }
 
 impl Zettel {
-    pub fn get_shim(&self) -> String {
-        if self.content.len() < 77 {
-            self.content.to_string()
-        } else {
-            format!("{}...", self.content[..77].to_string())
+    /// Returns the content on a single line, truncated at grapheme boundaries so that it
+    /// fits into `max_width` terminal columns.
+    pub fn get_shim(&self, max_width: usize) -> String {
+        let collapsed = self
+            .content
+            .split_whitespace()
+            .collect::<Vec<&str>>()
+            .join(" ");
+        if collapsed.width() <= max_width {
+            return collapsed;
+        }
+
+        // Too narrow for text and dots, so it's only dots.
+        let ellipsis = &"..."[..max_width.min(3)];
+        let budget = max_width - ellipsis.len();
+        let mut shim = String::new();
+        let mut shim_width = 0;
+        for grapheme in collapsed.graphemes(true) {
+            let grapheme_width = grapheme.width();
+            if shim_width + grapheme_width > budget {
+                break;
+            }
+            shim.push_str(grapheme);
+            shim_width += grapheme_width;
         }
+        format!("{}{}", shim.trim_end(), ellipsis)
     }
 
-    // TODO: refactor once timestamps are retrieved as u128
-    // TODO: display in current timezone?
+    /// `created_at` in the configured timezone and format.
     pub fn get_datetime_string(&self) -> String {
-        let timestamp_seconds = self.created_at / 1000;
-        let datetime = DateTime::from_timestamp(timestamp_seconds, 0).unwrap();
-        datetime.to_string()
+        format_timestamp(self.created_at)
+    }
+
+    /// How long ago the zettel was created, e.g. `3 days ago`.
+    pub fn get_relative_time(&self) -> String {
+        format_relative(self.created_at)
+    }
+}
+
+/// An earlier content of an amended zettel, `created_at` is when it was replaced.
+#[derive(Debug, Clone)]
+pub struct ZettelRevision {
+    pub id: i64,
+    pub zettel_id: i64,
+    pub content: String,
+    pub created_at: i64,
+}
+
+impl ZettelRevision {
+    pub fn get_datetime_string(&self) -> String {
+        format_timestamp(self.created_at)
+    }
+
+    pub fn get_relative_time(&self) -> String {
+        format_relative(self.created_at)
     }
 }
 
@@ -31,7 +76,15 @@ pub struct ZettelEdge {
     pub parent_id: Option<i64>,
 }
 
-#[derive(Debug, Clone)]
+/// A row of `zettel_lineage`: `descendant_id` is `separation` edges below `ancestor_id`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineageEntry {
+    pub ancestor_id: i64,
+    pub descendant_id: i64,
+    pub separation: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct Article {
     pub id: i64,
     pub zettel_id: i64,
@@ -40,9 +93,141 @@ pub struct Article {
     pub created_at: i64, // TODO: look into how to make this u128
 }
 
+/// What is known about a book, paper or web page, e.g. from BibTeX or a DOI lookup.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceMetadata {
+    /// BibTeX entry type like `article` or `book`.
+    pub kind: String,
+    pub bibtex_key: Option<String>,
+    /// Authors in BibTeX form, separated by `and`.
+    pub author: Option<String>,
+    pub title: String,
+    pub year: Option<i64>,
+    pub doi: Option<String>,
+    pub url: Option<String>,
+    pub isbn: Option<String>,
+}
+
 #[derive(Debug, Clone)]
+pub struct Source {
+    pub id: i64,
+    pub metadata: SourceMetadata,
+    pub created_at: i64,
+}
+
+impl Source {
+    /// A short reference like `Doe et al. (2020) Title`.
+    pub fn get_citation(&self) -> String {
+        let metadata = &self.metadata;
+        let mut citation = String::new();
+        if let Some(author) = &metadata.author {
+            let authors: Vec<&str> = author.split(" and ").collect();
+            citation.push_str(last_name(authors[0]));
+            if authors.len() > 1 {
+                citation.push_str(" et al.");
+            }
+            citation.push(' ');
+        }
+        if let Some(year) = metadata.year {
+            citation.push_str(&format!("({}) ", year));
+        }
+        citation.push_str(&metadata.title);
+        citation
+    }
+}
+
+/// The last name of an author written as `Last, First` or `First Last`.
+pub fn last_name(author: &str) -> &str {
+    let author = author.trim();
+    match author.split_once(',') {
+        Some((last, _)) => last.trim(),
+        None => author.rsplit(' ').next().unwrap_or(author),
+    }
+}
+
+/// The readable parts of a web page.
+#[derive(Debug, Clone)]
+pub struct Page {
+    pub url: String,
+    pub title: String,
+    pub author: Option<String>,
+    pub description: Option<String>,
+    pub year: Option<i64>,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct ZettelTag {
     pub zettel_id: i64,
     pub tag: String,
     pub created_at: i64, // TODO: look into how to make this u128
 }
+
+#[derive(Debug, Clone)]
+pub struct TagSuggestion {
+    pub tag: String,
+    pub is_new: bool,
+    pub accepted: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Answer {
+    pub question: String,
+    pub text: String,
+    pub citations: Vec<Zettel>,
+}
+
+/// When a zettel is next due for review, see `crate::review`.
+#[derive(Debug, Clone)]
+pub struct ReviewSchedule {
+    pub zettel_id: i64,
+    pub repetitions: i64,
+    pub interval_days: i64,
+    pub ease_factor: f64,
+    pub due_at: i64,
+    pub archived: bool,
+}
+
+/// A zettel in the review queue. `schedule` is `None` if it was never reviewed.
+#[derive(Debug, Clone)]
+pub struct ReviewItem {
+    pub zettel: Zettel,
+    pub n_descendants: i64,
+    pub schedule: Option<ReviewSchedule>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn zettel(content: &str) -> Zettel {
+        Zettel {
+            id: 1,
+            content: content.to_string(),
+            created_at: 0,
+        }
+    }
+
+    #[test]
+    fn get_shim_truncates_at_grapheme_boundaries_within_the_width() {
+        let cases = [
+            ("short", 10, "short"),
+            ("line one\n\nline two", 20, "line one line two"),
+            ("Größenänderung der Übersicht", 10, "Größenä..."),
+            ("日本語のメモです", 9, "日本語..."),
+            ("👍🏽👍🏽👍🏽👍🏽👍🏽", 7, "👍🏽👍🏽..."),
+            (
+                "e\u{301}e\u{301}e\u{301}e\u{301}e\u{301}e\u{301}",
+                5,
+                "e\u{301}e\u{301}...",
+            ),
+            ("abcdef", 2, ".."),
+            ("abcdef", 0, ""),
+        ];
+        for (content, max_width, expected) in cases {
+            let shim = zettel(content).get_shim(max_width);
+            assert_eq!(shim, expected, "content {:?}", content);
+            assert!(shim.width() <= max_width, "{:?} is too wide", shim);
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use strum::{Display, EnumIter};
+
+use crate::model::ReviewSchedule;
+
+/// Zettels are first due this long after they were written.
+pub const FIRST_REVIEW_AFTER_DAYS: i64 = 7;
+/// At most this many zettels are shown for review at a time.
+pub const REVIEW_QUEUE_SIZE: i64 = 20;
+
+pub const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+const INITIAL_EASE_FACTOR: f64 = 2.5;
+const MIN_EASE_FACTOR: f64 = 1.3;
+
+/// How well a zettel held up on review, from forgotten or irrelevant to obvious.
+#[derive(Debug, Clone, Copy, Display, EnumIter, PartialEq, Eq)]
+pub enum ReviewRating {
+    Again,
+    Hard,
+    Good,
+    Easy,
+}
+
+impl ReviewRating {
+    /// The SM-2 quality of a response, between 0 and 5. Below 3 counts as a failure.
+    fn quality(self) -> f64 {
+        match self {
+            ReviewRating::Again => 1.0,
+            ReviewRating::Hard => 3.0,
+            ReviewRating::Good => 4.0,
+            ReviewRating::Easy => 5.0,
+        }
+    }
+}
+
+/// Schedules the next review with SM-2: the interval grows by the ease factor after every
+/// successful review, and a failed one starts over tomorrow.
+pub fn next_schedule(
+    zettel_id: i64,
+    previous: Option<&ReviewSchedule>,
+    rating: ReviewRating,
+    now: i64,
+) -> ReviewSchedule {
+    let (repetitions, interval_days, ease_factor) = match previous {
+        Some(schedule) => (
+            schedule.repetitions,
+            schedule.interval_days,
+            schedule.ease_factor,
+        ),
+        None => (0, 0, INITIAL_EASE_FACTOR),
+    };
+
+    let quality = rating.quality();
+    let (repetitions, interval_days) = if quality < 3.0 {
+        (0, 1)
+    } else {
+        let interval_days = match repetitions {
+            0 => 1,
+            1 => 6,
+            _ => (interval_days as f64 * ease_factor).round() as i64,
+        };
+        (repetitions + 1, interval_days)
+    };
+    let ease_factor = (ease_factor + 0.1 - (5.0 - quality) * (0.08 + (5.0 - quality) * 0.02))
+        .max(MIN_EASE_FACTOR);
+
+    ReviewSchedule {
+        zettel_id,
+        repetitions,
+        interval_days,
+        ease_factor,
+        due_at: now + interval_days * DAY_MS,
+        archived: false,
+    }
+}
+
+/// Keeps the schedule of an archived zettel, in case it is ever brought back.
+pub fn archived_schedule(
+    zettel_id: i64,
+    previous: Option<ReviewSchedule>,
+    now: i64,
+) -> ReviewSchedule {
+    match previous {
+        Some(schedule) => ReviewSchedule {
+            archived: true,
+            ..schedule
+        },
+        None => ReviewSchedule {
+            zettel_id,
+            repetitions: 0,
+            interval_days: 0,
+            ease_factor: INITIAL_EASE_FACTOR,
+            due_at: now,
+            archived: true,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule(repetitions: i64, interval_days: i64, ease_factor: f64) -> ReviewSchedule {
+        ReviewSchedule {
+            zettel_id: 1,
+            repetitions,
+            interval_days,
+            ease_factor,
+            due_at: 0,
+            archived: false,
+        }
+    }
+
+    #[test]
+    fn next_schedule_follows_sm2() {
+        use ReviewRating::*;
+        let cases = [
+            // previous, rating, (repetitions, interval_days, ease_factor)
+            (None, Good, (1, 1, 2.5)),
+            (None, Easy, (1, 1, 2.6)),
+            (None, Hard, (1, 1, 2.36)),
+            (None, Again, (0, 1, 1.96)),
+            (Some(schedule(1, 1, 2.5)), Good, (2, 6, 2.5)),
+            (Some(schedule(2, 6, 2.5)), Good, (3, 15, 2.5)),
+            // The interval grows by the ease factor from before the review.
+            (Some(schedule(3, 15, 2.36)), Hard, (4, 35, 2.22)),
+            (Some(schedule(5, 40, 2.5)), Again, (0, 1, 1.96)),
+            (Some(schedule(2, 6, 1.4)), Again, (0, 1, MIN_EASE_FACTOR)),
+        ];
+        let now = 1_000;
+        for (previous, rating, (repetitions, interval_days, ease_factor)) in cases {
+            let next = next_schedule(1, previous.as_ref(), rating, now);
+            let context = format!("{:?} after {:?}", rating, previous.map(|s| s.interval_days));
+            assert_eq!(next.repetitions, repetitions, "{}", context);
+            assert_eq!(next.interval_days, interval_days, "{}", context);
+            assert!((next.ease_factor - ease_factor).abs() < 1e-9, "{}", context);
+            assert_eq!(next.due_at, now + interval_days * DAY_MS, "{}", context);
+            assert!(!next.archived);
+        }
+    }
+
+    #[test]
+    fn archived_schedule_keeps_the_previous_one() {
+        let archived = archived_schedule(1, Some(schedule(3, 15, 2.2)), 5);
+        assert!(archived.archived);
+        assert_eq!(archived.interval_days, 15);
+
+        let archived = archived_schedule(1, None, 5);
+        assert!(archived.archived);
+        assert_eq!(archived.due_at, 5);
+    }
+}
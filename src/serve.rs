@@ -0,0 +1,413 @@
+use axum::{
+    Json, Router,
+    extract::{Path, Query, Request, State},
+    http::{StatusCode, header::AUTHORIZATION},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{delete, get, post},
+};
+use rand::Rng;
+use serde::Deserialize;
+use serde_json::json;
+use std::{
+    env, fs,
+    net::{Ipv4Addr, SocketAddr},
+    path::Path as FsPath,
+    sync::Arc,
+};
+use tokio::net::TcpListener;
+use tracing::{info, warn};
+
+use crate::{
+    api::{
+        add_tag_to_zettel, create_zettel, delete_tag_from_zettel, find_tags, find_zettels,
+        get_articles, get_lineage, get_n_recent_zettels, get_tags, get_zettel,
+    },
+    db::Db,
+    error::TshError,
+    model::{Article, Zettel, ZettelTag},
+    promote_zettel,
+    tui::app::LlmConfig,
+};
+
+/// Environment variable holding the API token, overrides the token file.
+pub const SERVE_TOKEN_ENV_VAR: &str = "TSH_SERVE_TOKEN";
+/// Name of the file in the data directory the API token is kept in.
+const SERVE_TOKEN_FILE: &str = "serve_token";
+
+const DEFAULT_RECENT_ZETTELS: i64 = 20;
+
+struct ServerState {
+    db: Db,
+    llm_config: LlmConfig,
+    token: String,
+}
+
+/// An error as returned to API clients: a status code and a JSON body with a message.
+struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    fn bad_request(message: &str) -> Self {
+        Self {
+            status: StatusCode::BAD_REQUEST,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl From<TshError> for ApiError {
+    fn from(e: TshError) -> Self {
+        let status = match &e {
+            TshError::Database(rusqlite::Error::QueryReturnedNoRows) => StatusCode::NOT_FOUND,
+            // Rejected edges, e.g. a parent that doesn't exist.
+            TshError::Integrity(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            TshError::Embedding(_) | TshError::Chat(_) => StatusCode::BAD_GATEWAY,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        if status.is_server_error() {
+            warn!(error = %e, "Request failed");
+        }
+        Self {
+            status,
+            message: e.to_string(),
+        }
+    }
+}
+
+impl From<rusqlite::Error> for ApiError {
+    fn from(e: rusqlite::Error) -> Self {
+        TshError::Database(e).into()
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status, Json(json!({ "error": self.message }))).into_response()
+    }
+}
+
+type ApiResult<T> = Result<Json<T>, ApiError>;
+
+/// Serves the vault as a JSON API on localhost until the process is stopped.
+/// Every request needs the header `Authorization: Bearer <token>`.
+pub async fn serve(
+    db: Db,
+    llm_config: LlmConfig,
+    port: u16,
+    token: String,
+) -> Result<(), TshError> {
+    let router = router(Arc::new(ServerState {
+        db,
+        llm_config,
+        token,
+    }));
+
+    // Never reachable from other machines, the token is only a guard against other local users
+    // and web pages.
+    let address = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    let listener = TcpListener::bind(address).await?;
+    info!(%address, "Serving API");
+    eprintln!("Listening on http://{}", address);
+    axum::serve(listener, router).await?;
+    Ok(())
+}
+
+fn router(state: Arc<ServerState>) -> Router {
+    Router::new()
+        .route("/zettels", get(recent).post(add))
+        .route("/zettels/search", get(search))
+        .route("/zettels/{id}", get(zettel))
+        .route("/zettels/{id}/lineage", get(lineage))
+        .route("/zettels/{id}/tags", get(tags).post(add_tag))
+        .route("/zettels/{id}/tags/{tag}", delete(delete_tag))
+        .route("/zettels/{id}/articles", post(promote))
+        .route("/tags", get(search_tags))
+        .route("/articles", get(articles))
+        .layer(middleware::from_fn_with_state(state.clone(), authorize))
+        .with_state(state)
+}
+
+/// Reads the token from `TSH_SERVE_TOKEN` or the token file in the data directory.
+/// The file is created with a random token on first use.
+pub fn load_or_create_token(data_dir: &str) -> Result<String, TshError> {
+    if let Ok(token) = env::var(SERVE_TOKEN_ENV_VAR)
+        && !token.is_empty()
+    {
+        return Ok(token);
+    }
+
+    let path = FsPath::new(data_dir).join(SERVE_TOKEN_FILE);
+    if path.exists() {
+        let token = fs::read_to_string(&path)?.trim().to_string();
+        if token.is_empty() {
+            return Err(TshError::Config(format!("{} is empty", path.display())));
+        }
+        return Ok(token);
+    }
+
+    let bytes: [u8; 32] = rand::rng().random();
+    let token: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    std::io::Write::write_all(&mut options.open(&path)?, token.as_bytes())?;
+    eprintln!("Created API token in {}", path.display());
+    Ok(token)
+}
+
+async fn authorize(
+    State(state): State<Arc<ServerState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let authorized = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|token| constant_time_eq(token.as_bytes(), state.token.as_bytes()));
+    if authorized {
+        next.run(request).await
+    } else {
+        ApiError {
+            status: StatusCode::UNAUTHORIZED,
+            message: "Missing or wrong token".to_string(),
+        }
+        .into_response()
+    }
+}
+
+/// Compares without returning early, so the token can't be guessed from response times.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+#[derive(Deserialize)]
+struct RecentParams {
+    n: Option<i64>,
+}
+
+async fn recent(
+    State(state): State<Arc<ServerState>>,
+    Query(params): Query<RecentParams>,
+) -> ApiResult<Vec<Zettel>> {
+    let n = params.n.unwrap_or(DEFAULT_RECENT_ZETTELS);
+    Ok(Json(get_n_recent_zettels(&state.db, n).await?))
+}
+
+#[derive(Deserialize)]
+struct NewZettel {
+    content: String,
+    #[serde(default)]
+    parent_ids: Vec<i64>,
+}
+
+async fn add(
+    State(state): State<Arc<ServerState>>,
+    Json(new_zettel): Json<NewZettel>,
+) -> Result<(StatusCode, Json<Zettel>), ApiError> {
+    if new_zettel.content.trim().is_empty() {
+        return Err(ApiError::bad_request("The content is empty"));
+    }
+    let zettel = create_zettel(
+        &state.db,
+        &state.llm_config,
+        &new_zettel.content,
+        new_zettel.parent_ids,
+    )
+    .await?;
+    Ok((StatusCode::CREATED, Json(zettel)))
+}
+
+#[derive(Deserialize)]
+struct SearchParams {
+    q: String,
+}
+
+async fn search(
+    State(state): State<Arc<ServerState>>,
+    Query(params): Query<SearchParams>,
+) -> ApiResult<Vec<Zettel>> {
+    if params.q.trim().is_empty() {
+        return Err(ApiError::bad_request("The query is empty"));
+    }
+    Ok(Json(
+        find_zettels(&state.db, &state.llm_config, &params.q).await?,
+    ))
+}
+
+async fn zettel(State(state): State<Arc<ServerState>>, Path(id): Path<i64>) -> ApiResult<Zettel> {
+    Ok(Json(get_zettel(&state.db, id).await?))
+}
+
+async fn lineage(
+    State(state): State<Arc<ServerState>>,
+    Path(id): Path<i64>,
+) -> ApiResult<Vec<Zettel>> {
+    let zettel = get_zettel(&state.db, id).await?;
+    Ok(Json(get_lineage(&state.db, &zettel).await?))
+}
+
+async fn tags(
+    State(state): State<Arc<ServerState>>,
+    Path(id): Path<i64>,
+) -> ApiResult<Vec<ZettelTag>> {
+    get_zettel(&state.db, id).await?;
+    Ok(Json(get_tags(&state.db, id).await?))
+}
+
+#[derive(Deserialize)]
+struct NewTag {
+    tag: String,
+}
+
+async fn add_tag(
+    State(state): State<Arc<ServerState>>,
+    Path(id): Path<i64>,
+    Json(new_tag): Json<NewTag>,
+) -> ApiResult<Vec<ZettelTag>> {
+    let tag = new_tag.tag.trim().to_string();
+    if tag.is_empty() {
+        return Err(ApiError::bad_request("The tag is empty"));
+    }
+    get_zettel(&state.db, id).await?;
+    add_tag_to_zettel(&state.db, id, tag).await?;
+    Ok(Json(get_tags(&state.db, id).await?))
+}
+
+async fn delete_tag(
+    State(state): State<Arc<ServerState>>,
+    Path((id, tag)): Path<(i64, String)>,
+) -> ApiResult<Vec<ZettelTag>> {
+    delete_tag_from_zettel(&state.db, id, &tag).await?;
+    Ok(Json(get_tags(&state.db, id).await?))
+}
+
+#[derive(Deserialize)]
+struct TagParams {
+    #[serde(default)]
+    q: String,
+}
+
+async fn search_tags(
+    State(state): State<Arc<ServerState>>,
+    Query(params): Query<TagParams>,
+) -> ApiResult<Vec<String>> {
+    Ok(Json(find_tags(&state.db, &params.q).await?))
+}
+
+async fn articles(State(state): State<Arc<ServerState>>) -> ApiResult<Vec<Article>> {
+    Ok(Json(get_articles(&state.db).await?))
+}
+
+#[derive(Deserialize)]
+struct NewArticle {
+    title: String,
+}
+
+async fn promote(
+    State(state): State<Arc<ServerState>>,
+    Path(id): Path<i64>,
+    Json(new_article): Json<NewArticle>,
+) -> Result<(StatusCode, Json<Article>), ApiError> {
+    let title = new_article.title.trim().to_string();
+    if title.is_empty() {
+        return Err(ApiError::bad_request("The title is empty"));
+    }
+    let zettel = get_zettel(&state.db, id).await?;
+    let article = promote_zettel(zettel, &title, &state.db).await?;
+    Ok((StatusCode::CREATED, Json(article)))
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::StatusCode as ClientStatus;
+
+    use super::*;
+    use crate::test_support::{embedding_response, serve_stub, stub_llm_config, temp_db};
+
+    const TOKEN: &str = "secret";
+
+    /// Serves the API next to a stub embeddings endpoint and returns its base URL.
+    async fn serve_api(db: Db) -> String {
+        let llm_base = serve_stub(
+            Router::new().route("/embeddings", post(|| async { Json(embedding_response()) })),
+        )
+        .await;
+        serve_stub(router(Arc::new(ServerState {
+            db,
+            llm_config: stub_llm_config(&llm_base),
+            token: TOKEN.to_string(),
+        })))
+        .await
+    }
+
+    async fn get_status(url: &str, token: Option<&str>) -> ClientStatus {
+        let mut request = reqwest::Client::new().get(url);
+        if let Some(token) = token {
+            request = request.bearer_auth(token);
+        }
+        request.send().await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn requests_need_the_token() {
+        let (_dir, db) = temp_db().await;
+        let url = format!("{}/zettels", serve_api(db).await);
+
+        assert_eq!(get_status(&url, None).await, ClientStatus::UNAUTHORIZED);
+        assert_eq!(
+            get_status(&url, Some("wrong")).await,
+            ClientStatus::UNAUTHORIZED
+        );
+        assert_eq!(get_status(&url, Some(TOKEN)).await, ClientStatus::OK);
+    }
+
+    #[tokio::test]
+    async fn zettels_can_be_added_listed_searched_and_fetched() {
+        let (_dir, db) = temp_db().await;
+        let base_url = serve_api(db).await;
+        let client = reqwest::Client::new();
+
+        let response = client
+            .post(format!("{}/zettels", base_url))
+            .bearer_auth(TOKEN)
+            .json(&serde_json::json!({ "content": "Served" }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), ClientStatus::CREATED);
+        let id = response.json::<serde_json::Value>().await.unwrap()["id"]
+            .as_i64()
+            .unwrap();
+
+        let recent: Vec<serde_json::Value> = client
+            .get(format!("{}/zettels", base_url))
+            .bearer_auth(TOKEN)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(recent.len(), 1);
+        assert_eq!(recent[0]["content"], "Served");
+
+        let search = format!("{}/zettels/search?q=served", base_url);
+        assert_eq!(get_status(&search, Some(TOKEN)).await, ClientStatus::OK);
+        let zettel = format!("{}/zettels/{}", base_url, id);
+        assert_eq!(get_status(&zettel, Some(TOKEN)).await, ClientStatus::OK);
+        let unknown = format!("{}/zettels/{}", base_url, id + 1);
+        assert_eq!(
+            get_status(&unknown, Some(TOKEN)).await,
+            ClientStatus::NOT_FOUND
+        );
+    }
+}
@@ -0,0 +1,143 @@
+use chrono::Local;
+use serde::Deserialize;
+use std::{fs, path::Path};
+
+use crate::{error::TshError, model::Source};
+
+/// Marks the start and end of a template's header.
+const HEADER_DELIMITER: &str = "+++";
+
+/// A note template, read from a Markdown file in the templates directory. The file may start
+/// with a TOML header between `+++` lines:
+///
+/// ```text
+/// +++
+/// tags = ["meeting"]
+/// +++
+/// # Meeting on {{date}}
+/// ```
+///
+/// The body may contain the placeholders `{{date}}`, `{{time}}`, `{{tags}}` and `{{source}}`.
+#[derive(Debug, Clone)]
+pub struct Template {
+    pub name: String,
+    /// Assigned to every zettel created from the template.
+    pub tags: Vec<String>,
+    pub body: String,
+}
+
+#[derive(Default, Deserialize)]
+struct TemplateHeader {
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+impl Template {
+    pub fn parse(name: &str, content: &str) -> Result<Self, TshError> {
+        let (header, body) = match content
+            .strip_prefix(HEADER_DELIMITER)
+            .and_then(|rest| rest.split_once(&format!("\n{}", HEADER_DELIMITER)))
+        {
+            Some((header, body)) => {
+                let header: TemplateHeader = toml::from_str(header).map_err(|e| {
+                    TshError::Config(format!("Could not parse template '{}': {}", name, e))
+                })?;
+                (header, body.strip_prefix('\n').unwrap_or(body))
+            }
+            None => (TemplateHeader::default(), content),
+        };
+
+        Ok(Template {
+            name: name.to_string(),
+            tags: header
+                .tags
+                .iter()
+                .map(|tag| tag.trim().trim_start_matches('#').to_string())
+                .filter(|tag| !tag.is_empty())
+                .collect(),
+            body: body.to_string(),
+        })
+    }
+
+    pub fn uses_source(&self) -> bool {
+        self.body.contains("{{source}}")
+    }
+
+    /// Fills in the placeholders. `{{source}}` becomes the citation of `source`, which the user
+    /// picks for the new zettel, or nothing if they picked none.
+    pub fn render(&self, source: Option<&Source>) -> String {
+        let now = Local::now();
+        let tags: Vec<String> = self.tags.iter().map(|tag| format!("#{}", tag)).collect();
+        self.body
+            .replace("{{date}}", &now.format("%Y-%m-%d").to_string())
+            .replace("{{time}}", &now.format("%H:%M").to_string())
+            .replace("{{tags}}", &tags.join(" "))
+            .replace(
+                "{{source}}",
+                &source.map(Source::get_citation).unwrap_or_default(),
+            )
+    }
+}
+
+/// Reads all `.md` files in `dir` as templates, named after the file. A missing directory
+/// means there are no templates.
+pub fn load_templates(dir: &Path) -> Result<Vec<Template>, TshError> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut templates = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_none_or(|extension| extension != "md") {
+            continue;
+        }
+        let Some(name) = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+        else {
+            continue;
+        };
+        templates.push(Template::parse(&name, &fs::read_to_string(&path)?)?);
+    }
+    templates.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(templates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::SourceMetadata;
+
+    #[test]
+    fn parse_reads_the_header_tags() {
+        let template = Template::parse(
+            "reading",
+            "+++\ntags = [\"#reading\", \" \"]\n+++\n# Notes on {{source}}\n",
+        )
+        .unwrap();
+
+        assert_eq!(template.tags, vec!["reading".to_string()]);
+        assert_eq!(template.body, "# Notes on {{source}}\n");
+        assert!(template.uses_source());
+    }
+
+    #[test]
+    fn render_cites_the_picked_source() {
+        let template = Template::parse("reading", "{{tags}} {{source}}").unwrap();
+        let source = Source {
+            id: 1,
+            metadata: SourceMetadata {
+                title: "Stub Paper".to_string(),
+                ..Default::default()
+            },
+            created_at: 0,
+        };
+
+        assert_eq!(
+            template.render(Some(&source)),
+            format!(" {}", source.get_citation())
+        );
+        assert_eq!(template.render(None), " ");
+    }
+}
@@ -0,0 +1,67 @@
+//! Helpers shared by the unit tests.
+
+use axum::Router;
+use tempfile::TempDir;
+use tokio::net::TcpListener;
+
+use crate::{config::ApiKey, db::Db, tui::app::LlmConfig};
+
+/// A migrated database in a temporary directory, removed when the `TempDir` is dropped.
+pub async fn temp_db() -> (TempDir, Db) {
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("test.db");
+    let db = Db::open(&db_path.to_string_lossy(), None).await.unwrap();
+    (dir, db)
+}
+
+/// Serves `router` on a free local port and returns its base URL.
+pub async fn serve_stub(router: Router) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+    format!("http://{}", addr)
+}
+
+/// Settings pointing at a stub OpenAI-compatible server.
+pub fn stub_llm_config(api_base: &str) -> LlmConfig {
+    LlmConfig {
+        api_base: api_base.to_string(),
+        api_key: ApiKey::from("test"),
+        embeddings_model: "test-embeddings".to_string(),
+        chat_model: Some("test-chat".to_string()),
+        doi_resolver_url: api_base.to_string(),
+    }
+}
+
+/// A chat completion response whose only choice says `content`.
+pub fn chat_completion(content: &str) -> serde_json::Value {
+    serde_json::json!({
+        "id": "chatcmpl-test",
+        "object": "chat.completion",
+        "created": 0,
+        "model": "test-chat",
+        "choices": [{
+            "index": 0,
+            "message": { "role": "assistant", "content": content },
+            "finish_reason": "stop"
+        }]
+    })
+}
+
+/// Inserts a zettel without an embedding, for tests that don't search.
+pub async fn insert_zettel(db: &Db, content: &str) -> i64 {
+    let conn = db.lock().await;
+    conn.execute("insert into zettel (content) values (?)", [content])
+        .unwrap();
+    conn.last_insert_rowid()
+}
+
+/// An embeddings response with a single vector of the dimension the `zettel` table expects.
+pub fn embedding_response() -> serde_json::Value {
+    serde_json::json!({
+        "object": "list",
+        "data": [{ "object": "embedding", "index": 0, "embedding": vec![0.1_f32; 384] }],
+        "model": "test-embeddings",
+        "usage": { "prompt_tokens": 0, "total_tokens": 0 }
+    })
+}
@@ -1,99 +1,360 @@
+use chrono::NaiveDate;
 use ratatui::{
     Frame,
     crossterm::event::{self, Event, KeyEvent},
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    widgets::{Clear, Paragraph},
 };
-use std::error::Error;
+use std::path::PathBuf;
+use tracing::{error, info};
 
 use crate::{
-    AppConfig,
-    api::add_zettel,
-    model::Zettel,
-    tui::{iterate::IterateZettelScreen, main_menu::MainMenuScreen, recent::RecentScreen},
+    api::{add_zettel, amend_zettel, get_journal_entry},
+    combine_zettel_contents,
+    config::{ApiKey, AppConfig, Vault},
+    db::Db,
+    doi::DEFAULT_DOI_RESOLVER_URL,
+    error::TshError,
+    journal::{journal_heading, journal_tag},
+    model::{Source, Zettel},
+    template::{Template, load_templates},
+    tui::{
+        ask::AskScreen,
+        calendar::CalendarScreen,
+        clip::ClipScreen,
+        editor::{EditTarget, EditorEvent, EditorPane},
+        history::HistoryScreen,
+        iterate::IterateZettelScreen,
+        lineage::LineageScreen,
+        log::LogScreen,
+        main_menu::MainMenuScreen,
+        recent::RecentScreen,
+        review::ReviewScreen,
+        sources::SourcesScreen,
+        template::TemplateScreen,
+        vault::VaultScreen,
+    },
 };
 
+#[allow(clippy::large_enum_variant)]
 pub enum ActiveScreenType {
     Main(MainMenuScreen),
     Iterate(IterateZettelScreen),
     Recent(RecentScreen),
+    Ask(AskScreen),
+    Lineage(LineageScreen),
+    Log(LogScreen),
+    Vault(VaultScreen),
+    History(HistoryScreen),
+    Sources(SourcesScreen),
+    Clip(ClipScreen),
+    Template(TemplateScreen),
+    Calendar(CalendarScreen),
+    Review(ReviewScreen),
 }
 
+#[allow(clippy::large_enum_variant)]
 pub enum AppCommand {
     Quit,
     AddZettel(Vec<Zettel>),
+    /// Starts a new zettel from a template, citing the source if one was picked.
+    AddZettelFromTemplate(Template, Option<Source>),
+    AmendZettel(Zettel),
+    /// Opens the journal entry of the day, or starts it.
+    OpenJournal(NaiveDate),
     SwitchScreen(ActiveScreenType),
+    ChooseTemplate,
+    ChooseVault,
+    SwitchVault(Vault),
 }
 
 #[trait_variant::make(ScreenMulti: Send)]
 pub trait Screen {
-    async fn handle_key_event(
-        &mut self,
-        key: KeyEvent,
-    ) -> Result<Option<AppCommand>, Box<dyn Error>>;
+    async fn handle_key_event(&mut self, key: KeyEvent) -> Result<Option<AppCommand>, TshError>;
     fn draw(&mut self, frame: &mut Frame);
 }
 
 #[derive(Debug, Clone)]
 pub struct LlmConfig {
     pub api_base: String,
-    pub api_key: String,
+    pub api_key: ApiKey,
     pub embeddings_model: String,
+    pub chat_model: Option<String>,
+    /// Not a model setting, but it goes wherever the LLM settings go.
+    pub doi_resolver_url: String,
 }
 
-impl From<&AppConfig> for LlmConfig {
-    fn from(config: &AppConfig) -> Self {
-        LlmConfig {
+impl TryFrom<&AppConfig> for LlmConfig {
+    type Error = TshError;
+
+    fn try_from(config: &AppConfig) -> Result<Self, Self::Error> {
+        Ok(LlmConfig {
             api_base: config.api_base.clone(),
-            api_key: config.api_key.clone(),
+            api_key: ApiKey::try_from(config)?,
             embeddings_model: config.embeddings_model.clone(),
-        }
+            chat_model: config.chat_model.clone(),
+            doi_resolver_url: config
+                .doi_resolver_url
+                .clone()
+                .unwrap_or(DEFAULT_DOI_RESOLVER_URL.to_string()),
+        })
     }
 }
 
 pub struct App {
     should_quit: bool,
     current_screen: ActiveScreenType,
-    db_path: String,
-    llm_config: LlmConfig,
+    vaults: Vec<Vault>,
+    current_vault: usize,
+    db: Db,
+    templates_dir: PathBuf,
+    editor: Option<EditorPane>,
+    error_message: Option<String>,
 }
 
 impl App {
-    pub fn new(db_path: String, llm_config: LlmConfig) -> Self {
+    pub fn new(vaults: Vec<Vault>, current_vault: usize, db: Db, templates_dir: PathBuf) -> Self {
+        let vault = &vaults[current_vault];
         Self {
             should_quit: false,
             current_screen: ActiveScreenType::Main(MainMenuScreen::new(
-                db_path.clone(),
-                llm_config.clone(),
+                db.clone(),
+                vault.llm_config.clone(),
             )),
-            db_path,
-            llm_config,
+            vaults,
+            current_vault,
+            db,
+            templates_dir,
+            editor: None,
+            error_message: None,
         }
     }
 
-    async fn handle_event(&mut self) -> Result<Option<AppCommand>, Box<dyn Error>> {
-        if event::poll(std::time::Duration::from_millis(50))? {
-            if let Event::Key(key) = event::read()? {
-                if key.kind == event::KeyEventKind::Press {
-                    return match &mut self.current_screen {
-                        ActiveScreenType::Main(screen) => {
-                            let maybe_action = screen.handle_key_event(key).await?;
-                            Ok(maybe_action)
-                        }
-                        ActiveScreenType::Iterate(screen) => {
-                            let maybe_action = screen.handle_key_event(key).await?;
-                            Ok(maybe_action)
-                        }
-                        ActiveScreenType::Recent(screen) => {
-                            let maybe_action = screen.handle_key_event(key).await?;
-                            Ok(maybe_action)
-                        }
-                    };
+    fn vault(&self) -> &Vault {
+        &self.vaults[self.current_vault]
+    }
+
+    /// Opens another vault and returns to the main menu with its database and LLM settings.
+    async fn switch_vault(&mut self, vault: Vault) -> Result<(), TshError> {
+        let idx = self
+            .vaults
+            .iter()
+            .position(|v| v.name == vault.name)
+            .ok_or_else(|| TshError::Config(format!("Unknown vault '{}'", vault.name)))?;
+        let db = vault.open().await?;
+        info!(vault = vault.name, "Switched vault");
+        self.current_vault = idx;
+        self.db = db.clone();
+        self.current_screen = ActiveScreenType::Main(MainMenuScreen::new(db, vault.llm_config));
+        Ok(())
+    }
+
+    /// Screens showing the amended zettel would still show its old content otherwise.
+    async fn show_amended_zettel(&mut self, zettel: Zettel) -> Result<(), TshError> {
+        match &mut self.current_screen {
+            ActiveScreenType::Recent(screen) => screen.replace_zettel(zettel),
+            ActiveScreenType::History(screen) => screen.reload(zettel).await?,
+            ActiveScreenType::Calendar(screen) => screen.replace_zettel(zettel),
+            ActiveScreenType::Ask(screen) => screen.replace_zettel(zettel),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Opens Neovim in a pane next to the current screen, which stays visible while editing.
+    async fn open_editor(&mut self, target: EditTarget, area: Rect) -> Result<(), TshError> {
+        let initial_content = match &target {
+            EditTarget::New { parents, .. } => combine_zettel_contents(parents.to_vec()),
+            EditTarget::Amend(zettel) => zettel.content.clone(),
+        };
+        self.editor = Some(EditorPane::open(target, &initial_content, area).await?);
+        Ok(())
+    }
+
+    /// Amends the day's journal entry, or starts a new one tagged with the date. A new entry
+    /// is only stored once there is more in it than its heading.
+    async fn open_journal(&mut self, date: NaiveDate, area: Rect) -> Result<(), TshError> {
+        let (target, initial_content) = journal_target(&self.db, date).await?;
+        self.editor = Some(EditorPane::open(target, &initial_content, area).await?);
+        Ok(())
+    }
+
+    /// Lets the user pick a template for a new zettel, unless there are none.
+    async fn choose_template(&mut self, area: Rect) -> Result<(), TshError> {
+        let templates = load_templates(&self.templates_dir)?;
+        if templates.is_empty() {
+            let target = EditTarget::New {
+                parents: vec![],
+                tags: vec![],
+            };
+            return self.open_editor(target, area).await;
+        }
+        self.current_screen = ActiveScreenType::Template(TemplateScreen::new(
+            templates,
+            self.db.clone(),
+            self.vault().llm_config.clone(),
+        ));
+        Ok(())
+    }
+
+    /// Starts a new zettel from a template. Like a journal heading, the rendered template is
+    /// only stored once something was added to it.
+    async fn open_template(
+        &mut self,
+        template: Template,
+        source: Option<Source>,
+        area: Rect,
+    ) -> Result<(), TshError> {
+        let initial_content = template.render(source.as_ref());
+        let target = EditTarget::New {
+            parents: vec![],
+            tags: template.tags,
+        };
+        self.editor = Some(EditorPane::open(target, &initial_content, area).await?);
+        Ok(())
+    }
+
+    /// Stores the buffer whenever Neovim writes it, and once more when Neovim is closed,
+    /// so a crash or a killed terminal loses nothing that was written.
+    async fn sync_editor(&mut self) -> Result<(), TshError> {
+        let Some(editor) = &mut self.editor else {
+            return Ok(());
+        };
+        match editor.poll()? {
+            None => Ok(()),
+            Some(EditorEvent::Written(content)) if editor.is_untouched_start(&content) => Ok(()),
+            Some(EditorEvent::Written(content)) => {
+                let mut target = editor.target.clone();
+                let result = self.store_buffer(&mut target, &content).await;
+                if let Some(editor) = &mut self.editor {
+                    editor.target = target;
                 }
+                result
             }
+            Some(EditorEvent::Closed(content)) => {
+                let Some(mut editor) = self.editor.take() else {
+                    return Ok(());
+                };
+                let result = match content {
+                    Ok(content)
+                        if editor.should_store_on_close(&content)
+                            && !editor.is_untouched_start(&content) =>
+                    {
+                        self.store_buffer(&mut editor.target, &content).await
+                    }
+                    Ok(_) => Ok(()),
+                    Err(e) => Err(e),
+                };
+                // The pane is gone, so the text is only left in the temporary file.
+                result.map_err(|e| match editor.keep_file() {
+                    Ok(path) => {
+                        TshError::Editor(format!("{}. The text was kept in {}", e, path.display()))
+                    }
+                    Err(_) => e,
+                })
+            }
+        }
+    }
+
+    /// The first write of a new zettel stores it, every later write amends it.
+    async fn store_buffer(
+        &mut self,
+        target: &mut EditTarget,
+        content: &str,
+    ) -> Result<(), TshError> {
+        let llm_config = &self.vault().llm_config;
+        match target {
+            EditTarget::New { parents, tags } => {
+                if let Some(zettel) =
+                    add_zettel(&self.db, llm_config, parents, tags, content).await?
+                {
+                    *target = EditTarget::Amend(zettel);
+                }
+            }
+            EditTarget::Amend(zettel) => {
+                if let Some(amended) = amend_zettel(&self.db, llm_config, zettel, content).await? {
+                    *zettel = amended.clone();
+                    self.show_amended_zettel(amended).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn show_error(&mut self, e: TshError) {
+        error!("{}", e);
+        self.error_message = Some(e.to_string());
+    }
+
+    fn read_key_event(&self) -> Result<Option<KeyEvent>, TshError> {
+        if event::poll(std::time::Duration::from_millis(50))?
+            && let Event::Key(key) = event::read()?
+            && key.kind == event::KeyEventKind::Press
+        {
+            return Ok(Some(key));
         }
 
         Ok(None)
     }
 
+    async fn handle_key_event(&mut self, key: KeyEvent) -> Result<Option<AppCommand>, TshError> {
+        match &mut self.current_screen {
+            ActiveScreenType::Main(screen) => {
+                let maybe_action = screen.handle_key_event(key).await?;
+                Ok(maybe_action)
+            }
+            ActiveScreenType::Iterate(screen) => {
+                let maybe_action = screen.handle_key_event(key).await?;
+                Ok(maybe_action)
+            }
+            ActiveScreenType::Recent(screen) => {
+                let maybe_action = screen.handle_key_event(key).await?;
+                Ok(maybe_action)
+            }
+            ActiveScreenType::Ask(screen) => {
+                let maybe_action = screen.handle_key_event(key).await?;
+                Ok(maybe_action)
+            }
+            ActiveScreenType::Lineage(screen) => {
+                let maybe_action = screen.handle_key_event(key).await?;
+                Ok(maybe_action)
+            }
+            ActiveScreenType::Log(screen) => {
+                let maybe_action = screen.handle_key_event(key).await?;
+                Ok(maybe_action)
+            }
+            ActiveScreenType::Vault(screen) => {
+                let maybe_action = screen.handle_key_event(key).await?;
+                Ok(maybe_action)
+            }
+            ActiveScreenType::History(screen) => {
+                let maybe_action = screen.handle_key_event(key).await?;
+                Ok(maybe_action)
+            }
+            ActiveScreenType::Sources(screen) => {
+                let maybe_action = screen.handle_key_event(key).await?;
+                Ok(maybe_action)
+            }
+            ActiveScreenType::Clip(screen) => {
+                let maybe_action = screen.handle_key_event(key).await?;
+                Ok(maybe_action)
+            }
+            ActiveScreenType::Template(screen) => {
+                let maybe_action = screen.handle_key_event(key).await?;
+                Ok(maybe_action)
+            }
+            ActiveScreenType::Calendar(screen) => {
+                let maybe_action = screen.handle_key_event(key).await?;
+                Ok(maybe_action)
+            }
+            ActiveScreenType::Review(screen) => {
+                let maybe_action = screen.handle_key_event(key).await?;
+                Ok(maybe_action)
+            }
+        }
+    }
+
     fn draw(&mut self, frame: &mut Frame) {
         match &mut self.current_screen {
             ActiveScreenType::Main(screen) => {
@@ -105,6 +366,64 @@ impl App {
             ActiveScreenType::Recent(screen) => {
                 screen.draw(frame);
             }
+            ActiveScreenType::Ask(screen) => {
+                screen.draw(frame);
+            }
+            ActiveScreenType::Lineage(screen) => {
+                screen.draw(frame);
+            }
+            ActiveScreenType::Log(screen) => {
+                screen.draw(frame);
+            }
+            ActiveScreenType::Vault(screen) => {
+                screen.draw(frame);
+            }
+            ActiveScreenType::History(screen) => {
+                screen.draw(frame);
+            }
+            ActiveScreenType::Sources(screen) => {
+                screen.draw(frame);
+            }
+            ActiveScreenType::Clip(screen) => {
+                screen.draw(frame);
+            }
+            ActiveScreenType::Template(screen) => {
+                screen.draw(frame);
+            }
+            ActiveScreenType::Calendar(screen) => {
+                screen.draw(frame);
+            }
+            ActiveScreenType::Review(screen) => {
+                screen.draw(frame);
+            }
+        }
+
+        if let Some(editor) = &self.editor {
+            editor.draw(frame);
+        }
+
+        // Only worth the space when there is something to tell apart.
+        if self.vaults.len() > 1 {
+            let area = frame.area();
+            let label = format!(" vault: {} ", self.vault().name);
+            let width = (label.len() as u16).min(area.width);
+            let label_area = Rect::new(area.right().saturating_sub(width + 2), area.y, width, 1);
+            let vault_label = Paragraph::new(label).style(Style::default().fg(Color::LightCyan));
+            frame.render_widget(Clear, label_area);
+            frame.render_widget(vault_label, label_area);
+        }
+
+        if let Some(message) = &self.error_message {
+            let area = frame.area();
+            let status_area = Rect::new(area.x, area.bottom().saturating_sub(1), area.width, 1);
+            let status_bar = Paragraph::new(format!(" {} ", message)).style(
+                Style::default()
+                    .bg(Color::Red)
+                    .fg(Color::White)
+                    .add_modifier(Modifier::BOLD),
+            );
+            frame.render_widget(Clear, status_area);
+            frame.render_widget(status_bar, status_area);
         }
     }
 
@@ -123,30 +442,130 @@ impl App {
                 ActiveScreenType::Recent(screen) => {
                     self.current_screen = ActiveScreenType::Recent(screen);
                 }
+                ActiveScreenType::Ask(screen) => {
+                    self.current_screen = ActiveScreenType::Ask(screen);
+                }
+                ActiveScreenType::Lineage(screen) => {
+                    self.current_screen = ActiveScreenType::Lineage(screen);
+                }
+                ActiveScreenType::Log(screen) => {
+                    self.current_screen = ActiveScreenType::Log(screen);
+                }
+                ActiveScreenType::Vault(screen) => {
+                    self.current_screen = ActiveScreenType::Vault(screen);
+                }
+                ActiveScreenType::History(screen) => {
+                    self.current_screen = ActiveScreenType::History(screen);
+                }
+                ActiveScreenType::Sources(screen) => {
+                    self.current_screen = ActiveScreenType::Sources(screen);
+                }
+                ActiveScreenType::Clip(screen) => {
+                    self.current_screen = ActiveScreenType::Clip(screen);
+                }
+                ActiveScreenType::Template(screen) => {
+                    self.current_screen = ActiveScreenType::Template(screen);
+                }
+                ActiveScreenType::Calendar(screen) => {
+                    self.current_screen = ActiveScreenType::Calendar(screen);
+                }
+                ActiveScreenType::Review(screen) => {
+                    self.current_screen = ActiveScreenType::Review(screen);
+                }
             },
+            AppCommand::ChooseVault => {
+                self.current_screen = ActiveScreenType::Vault(VaultScreen::new(
+                    self.vaults.clone(),
+                    self.vault().name.clone(),
+                    self.db.clone(),
+                ));
+            }
             _ => {}
         }
     }
 
-    pub async fn run(&mut self) -> Result<(), Box<dyn Error>> {
+    pub async fn run(&mut self) -> Result<(), TshError> {
         let mut terminal = ratatui::init();
 
         loop {
-            if let Some(command) = self.handle_event().await? {
-                match command {
-                    AppCommand::AddZettel(parents) => {
-                        // TODO: maybe use embedded neovim to avoid flickering (-> nvim-rs)
-                        // Open an empty Zettel in neovim buffer
-                        ratatui::restore();
-                        add_zettel(&self.db_path, &self.llm_config, &parents).await?;
-                        terminal = ratatui::init();
+            let size = terminal.size()?;
+            let area = Rect::new(0, 0, size.width, size.height);
+
+            if let Some(key) = self.read_key_event()? {
+                // Any key press dismisses the last error.
+                self.error_message = None;
+
+                if let Some(editor) = &self.editor {
+                    // Neovim has the keyboard until it is closed.
+                    if let Err(e) = editor.send_key(key).await {
+                        self.show_error(e);
                     }
-                    _ => {
-                        self.process_app_command(command);
+                } else {
+                    match self.handle_key_event(key).await {
+                        Ok(Some(AppCommand::AddZettel(parents))) => {
+                            let target = EditTarget::New {
+                                parents,
+                                tags: vec![],
+                            };
+                            if let Err(e) = self.open_editor(target, area).await {
+                                self.show_error(e);
+                            }
+                        }
+                        Ok(Some(AppCommand::AddZettelFromTemplate(template, source))) => {
+                            if let Err(e) = self.open_template(template, source, area).await {
+                                self.show_error(e);
+                            }
+                        }
+                        Ok(Some(AppCommand::OpenJournal(date))) => {
+                            if let Err(e) = self.open_journal(date, area).await {
+                                self.show_error(e);
+                            }
+                        }
+                        Ok(Some(AppCommand::ChooseTemplate)) => {
+                            if let Err(e) = self.choose_template(area).await {
+                                self.show_error(e);
+                            }
+                        }
+                        Ok(Some(AppCommand::AmendZettel(zettel))) => {
+                            if let Err(e) = self.open_editor(EditTarget::Amend(zettel), area).await
+                            {
+                                self.show_error(e);
+                            }
+                        }
+                        Ok(Some(AppCommand::SwitchVault(vault))) => {
+                            // The passphrase prompt needs the normal terminal.
+                            let needs_prompt = vault.needs_passphrase_prompt();
+                            if needs_prompt {
+                                ratatui::restore();
+                            }
+                            let result = self.switch_vault(vault).await;
+                            if needs_prompt {
+                                terminal = ratatui::init();
+                            }
+                            if let Err(e) = result {
+                                self.show_error(e);
+                            }
+                        }
+                        Ok(Some(command)) => {
+                            self.process_app_command(command);
+                        }
+                        Ok(None) => {}
+                        Err(e) => {
+                            self.show_error(e);
+                        }
                     }
                 }
             }
 
+            if let Err(e) = self.sync_editor().await {
+                self.show_error(e);
+            }
+            if let Some(editor) = &mut self.editor
+                && let Err(e) = editor.resize(area).await
+            {
+                self.show_error(e);
+            }
+
             if self.should_quit {
                 break;
             }
@@ -159,3 +578,59 @@ impl App {
         Ok(())
     }
 }
+
+/// What editing the journal of a day starts from: the day's entry if there is one, or a new
+/// entry tagged with the date that only has its heading.
+async fn journal_target(db: &Db, date: NaiveDate) -> Result<(EditTarget, String), TshError> {
+    if let Some(entry) = get_journal_entry(db, date).await? {
+        let content = entry.content.clone();
+        return Ok((EditTarget::Amend(entry), content));
+    }
+    let target = EditTarget::New {
+        parents: vec![],
+        tags: vec![journal_tag(date)],
+    };
+    Ok((target, journal_heading(date)))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{Json, Router, routing::post};
+
+    use super::*;
+    use crate::test_support::{embedding_response, serve_stub, stub_llm_config, temp_db};
+
+    #[tokio::test]
+    async fn journal_of_a_day_with_an_entry_amends_it() {
+        let api_base = serve_stub(
+            Router::new().route("/embeddings", post(|| async { Json(embedding_response()) })),
+        )
+        .await;
+        let (_dir, db) = temp_db().await;
+        let date = NaiveDate::from_ymd_opt(2025, 1, 2).unwrap();
+
+        let (target, content) = journal_target(&db, date).await.unwrap();
+        assert_eq!(content, "# 2025-01-02\n\n");
+        let EditTarget::New { parents, tags } = target else {
+            panic!("Expected a new entry");
+        };
+        let entry = add_zettel(
+            &db,
+            &stub_llm_config(&api_base),
+            &parents,
+            &tags,
+            "# 2025-01-02\n\nWrote tests.",
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+        let (target, content) = journal_target(&db, date).await.unwrap();
+        assert!(matches!(target, EditTarget::Amend(zettel) if zettel.id == entry.id));
+        assert_eq!(content, entry.content);
+
+        let next_day = date.succ_opt().unwrap();
+        let (target, _) = journal_target(&db, next_day).await.unwrap();
+        assert!(matches!(target, EditTarget::New { .. }));
+    }
+}
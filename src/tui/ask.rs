@@ -0,0 +1,259 @@
+use ratatui::{
+    Frame,
+    crossterm::event::{KeyCode, KeyEvent},
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    widgets::{Block, BorderType, Borders, List, ListItem, Paragraph, Wrap},
+};
+
+use crate::{
+    api::{ask, save_answer},
+    db::Db,
+    error::TshError,
+    model::{Answer, Zettel},
+    tui::{
+        app::{ActiveScreenType, AppCommand, LlmConfig, Screen},
+        common::{InputMode, ListWithState, zettel_list_item},
+        main_menu::MainMenuScreen,
+    },
+};
+
+pub struct AskScreen {
+    input_mode: InputMode,
+    question: String,
+    answer: Option<Answer>,
+    citations: ListWithState<Zettel>,
+    saved_as: Option<i64>,
+    db: Db,
+    llm_config: LlmConfig,
+}
+
+enum AskScreenMessage {
+    BackToMainMenu,
+    EnterInsertMode,
+    ExitInsertMode,
+    InsertChar(char),
+    DeleteChar,
+    SubmitQuestion(String),
+    CitationListMoveUp,
+    CitationListMoveDown,
+    SaveAnswer,
+    OpenZettel(Zettel),
+    IterateZettel(Zettel),
+}
+
+impl AskScreen {
+    pub fn new(db: Db, llm_config: LlmConfig) -> Self {
+        Self {
+            input_mode: InputMode::Normal,
+            question: String::new(),
+            answer: None,
+            citations: ListWithState::new(vec![]),
+            saved_as: None,
+            db,
+            llm_config,
+        }
+    }
+
+    /// Shows the new content of a cited zettel that was amended from this screen.
+    pub fn replace_zettel(&mut self, zettel: Zettel) {
+        if let Some(item) = self.citations.items.iter_mut().find(|z| z.id == zettel.id) {
+            *item = zettel;
+        }
+    }
+
+    fn handle_key_event_internal(&mut self, key: KeyEvent) -> Option<AskScreenMessage> {
+        match self.input_mode {
+            InputMode::Normal => match key.code {
+                KeyCode::Char('i') => Some(AskScreenMessage::EnterInsertMode),
+                KeyCode::Char('q') | KeyCode::Esc => Some(AskScreenMessage::BackToMainMenu),
+                KeyCode::Char('s') => Some(AskScreenMessage::SaveAnswer),
+                KeyCode::Up => Some(AskScreenMessage::CitationListMoveUp),
+                KeyCode::Down => Some(AskScreenMessage::CitationListMoveDown),
+                KeyCode::Enter => self
+                    .citations
+                    .get_selected_item()
+                    .map(AskScreenMessage::OpenZettel),
+                KeyCode::Char('n') => self
+                    .citations
+                    .get_selected_item()
+                    .map(AskScreenMessage::IterateZettel),
+                _ => None,
+            },
+            InputMode::Insert => match key.code {
+                KeyCode::Char(c) => Some(AskScreenMessage::InsertChar(c)),
+                KeyCode::Backspace => Some(AskScreenMessage::DeleteChar),
+                KeyCode::Enter => Some(AskScreenMessage::SubmitQuestion(self.question.clone())),
+                KeyCode::Esc => Some(AskScreenMessage::ExitInsertMode),
+                _ => None,
+            },
+        }
+    }
+
+    async fn update(&mut self, message: AskScreenMessage) -> Result<(), TshError> {
+        match message {
+            AskScreenMessage::EnterInsertMode => {
+                self.input_mode = InputMode::Insert;
+            }
+            AskScreenMessage::ExitInsertMode => {
+                self.input_mode = InputMode::Normal;
+            }
+            AskScreenMessage::InsertChar(c) => {
+                self.question.push(c);
+            }
+            AskScreenMessage::DeleteChar => {
+                self.question.pop();
+            }
+            AskScreenMessage::SubmitQuestion(question) => {
+                self.input_mode = InputMode::Normal;
+                if !question.is_empty() {
+                    let answer = ask(&self.db, &self.llm_config, &question).await?;
+                    self.citations = ListWithState::new(answer.citations.clone());
+                    self.answer = Some(answer);
+                    self.saved_as = None;
+                }
+            }
+            AskScreenMessage::CitationListMoveUp => {
+                self.citations.select_prev();
+            }
+            AskScreenMessage::CitationListMoveDown => {
+                self.citations.select_next();
+            }
+            AskScreenMessage::SaveAnswer => {
+                if let Some(answer) = &self.answer
+                    && self.saved_as.is_none()
+                {
+                    let zettel = save_answer(&self.db, &self.llm_config, answer).await?;
+                    self.saved_as = Some(zettel.id);
+                }
+            }
+            _ => {}
+        };
+        Ok(())
+    }
+}
+
+impl Screen for AskScreen {
+    async fn handle_key_event(&mut self, key: KeyEvent) -> Result<Option<AppCommand>, TshError> {
+        if let Some(msg) = self.handle_key_event_internal(key) {
+            match msg {
+                AskScreenMessage::BackToMainMenu => {
+                    Ok(Some(AppCommand::SwitchScreen(ActiveScreenType::Main(
+                        MainMenuScreen::new(self.db.clone(), self.llm_config.clone()),
+                    ))))
+                }
+                AskScreenMessage::OpenZettel(zettel) => Ok(Some(AppCommand::AmendZettel(zettel))),
+                AskScreenMessage::IterateZettel(zettel) => {
+                    Ok(Some(AppCommand::AddZettel(vec![zettel])))
+                }
+                _ => {
+                    self.update(msg).await?;
+                    Ok(None)
+                }
+            }
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn draw(&mut self, f: &mut Frame) {
+        let layout = Layout::new(
+            Direction::Vertical,
+            [Constraint::Length(3), Constraint::Min(0)],
+        )
+        .split(f.area());
+
+        let inner_layout = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(layout[1]);
+
+        let citation_layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
+            .split(inner_layout[1]);
+
+        let question_box_style: Style = match self.input_mode {
+            InputMode::Insert => Style::default()
+                .fg(Color::LightGreen)
+                .add_modifier(Modifier::BOLD),
+            InputMode::Normal => Style::default(),
+        };
+
+        let question_box = Paragraph::new(self.question.to_string())
+            .style(question_box_style)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded)
+                    .title("Question"),
+            );
+
+        let answer_text = match &self.answer {
+            Some(answer) => answer.text.to_string(),
+            None => String::new(),
+        };
+        let answer_title = match self.saved_as {
+            Some(id) => format!("Answer (saved as {})", id),
+            None => "Answer".to_string(),
+        };
+
+        let answer = Paragraph::new(answer_text).wrap(Wrap { trim: true }).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .title(answer_title),
+        );
+
+        let list_width = citation_layout[0].width.saturating_sub(2);
+        let citation_items: Vec<ListItem> = self
+            .citations
+            .items
+            .clone()
+            .iter()
+            .enumerate()
+            .map(|(i, zettel)| {
+                let mut item = zettel_list_item(zettel, list_width);
+                if let Some(idx) = self.citations.curr_idx()
+                    && i == idx
+                {
+                    item = item.style(
+                        Style::default()
+                            .bg(Color::DarkGray)
+                            .add_modifier(Modifier::BOLD),
+                    );
+                }
+                item
+            })
+            .collect();
+
+        let citations_list = List::new(citation_items).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .title("Citations (Enter: open, n: iterate)"),
+        );
+
+        let preview = if let Some(zettel) = self.citations.get_selected_item() {
+            Paragraph::new(zettel.content.to_string())
+        } else {
+            Paragraph::default()
+        }
+        .wrap(Wrap { trim: true })
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .title("Preview"),
+        );
+
+        f.render_widget(question_box, layout[0]);
+        f.render_widget(answer, inner_layout[0]);
+        f.render_stateful_widget(
+            citations_list,
+            citation_layout[0],
+            &mut self.citations.list_state,
+        );
+        f.render_widget(preview, citation_layout[1]);
+    }
+}
@@ -0,0 +1,164 @@
+use chrono::NaiveDate;
+use ratatui::{
+    Frame,
+    crossterm::event::{KeyCode, KeyEvent},
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Block, BorderType, Borders, List, ListItem, Paragraph, Wrap},
+};
+
+use crate::{
+    api::{get_journal_days, get_journal_entry},
+    datetime::today,
+    db::Db,
+    error::TshError,
+    model::Zettel,
+    tui::{
+        app::{ActiveScreenType, AppCommand, LlmConfig, Screen},
+        common::ListWithState,
+        main_menu::MainMenuScreen,
+    },
+};
+
+/// Lists the days with journal entries, the most recent first.
+pub struct CalendarScreen {
+    days: ListWithState<(NaiveDate, Zettel)>,
+    db: Db,
+    llm_config: LlmConfig,
+}
+
+enum CalendarScreenMessage {
+    BackToMainMenu,
+    SelectNext,
+    SelectPrevious,
+    OpenEntry,
+}
+
+impl CalendarScreen {
+    pub async fn new(db: Db, llm_config: LlmConfig) -> Result<Self, TshError> {
+        let mut days = Vec::new();
+        for date in get_journal_days(&db).await? {
+            if let Some(entry) = get_journal_entry(&db, date).await? {
+                days.push((date, entry));
+            }
+        }
+        Ok(Self {
+            days: ListWithState::new(days),
+            db,
+            llm_config,
+        })
+    }
+
+    fn handle_key_event_internal(&self, key: KeyEvent) -> Option<CalendarScreenMessage> {
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => Some(CalendarScreenMessage::BackToMainMenu),
+            KeyCode::Down => Some(CalendarScreenMessage::SelectNext),
+            KeyCode::Up => Some(CalendarScreenMessage::SelectPrevious),
+            KeyCode::Enter => Some(CalendarScreenMessage::OpenEntry),
+            _ => None,
+        }
+    }
+
+    fn update(&mut self, message: CalendarScreenMessage) {
+        match message {
+            CalendarScreenMessage::SelectNext => self.days.select_next(),
+            CalendarScreenMessage::SelectPrevious => self.days.select_prev(),
+            _ => {}
+        }
+    }
+
+    /// Shows the new content of an entry that was amended from this screen.
+    pub fn replace_zettel(&mut self, zettel: Zettel) {
+        if let Some((_, entry)) = self.days.items.iter_mut().find(|(_, z)| z.id == zettel.id) {
+            *entry = zettel;
+        }
+    }
+}
+
+impl Screen for CalendarScreen {
+    async fn handle_key_event(&mut self, key: KeyEvent) -> Result<Option<AppCommand>, TshError> {
+        if let Some(msg) = self.handle_key_event_internal(key) {
+            match msg {
+                CalendarScreenMessage::BackToMainMenu => {
+                    Ok(Some(AppCommand::SwitchScreen(ActiveScreenType::Main(
+                        MainMenuScreen::new(self.db.clone(), self.llm_config.clone()),
+                    ))))
+                }
+                CalendarScreenMessage::OpenEntry => match self.days.get_selected_item() {
+                    Some((date, _)) => Ok(Some(AppCommand::OpenJournal(date))),
+                    None => Ok(None),
+                },
+                _ => {
+                    self.update(msg);
+                    Ok(None)
+                }
+            }
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn draw(&mut self, f: &mut Frame) {
+        let layout = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(f.area());
+
+        let list_width = layout[0].width.saturating_sub(2) as usize;
+        let today = today();
+        let selected_idx = self.days.curr_idx();
+        let items: Vec<ListItem> = self
+            .days
+            .items
+            .iter()
+            .enumerate()
+            .map(|(i, (date, entry))| {
+                let mut heading = date.format("%Y-%m-%d %A").to_string();
+                if *date == today {
+                    heading.push_str(" (today)");
+                }
+                let lines = vec![
+                    Line::styled(
+                        heading,
+                        Style::default()
+                            .add_modifier(Modifier::ITALIC)
+                            .fg(Color::LightBlue),
+                    ),
+                    Line::styled(entry.get_shim(list_width), Style::default()),
+                ];
+                let mut item = ListItem::new(lines);
+                if selected_idx == Some(i) {
+                    item = item.style(
+                        Style::default()
+                            .bg(Color::DarkGray)
+                            .add_modifier(Modifier::BOLD),
+                    );
+                }
+                item
+            })
+            .collect();
+
+        let list = List::new(items).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Thick)
+                .title("Journal"),
+        );
+
+        let preview = match self.days.get_selected_item() {
+            Some((_, entry)) => Paragraph::new(entry.content),
+            None => Paragraph::new("No journal entries yet, use Today in the main menu."),
+        }
+        .wrap(Wrap { trim: true })
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Thick)
+                .title("Preview"),
+        );
+
+        f.render_stateful_widget(list, layout[0], &mut self.days.list_state);
+        f.render_widget(preview, layout[1]);
+    }
+}
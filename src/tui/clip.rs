@@ -0,0 +1,180 @@
+use ratatui::{
+    Frame,
+    crossterm::event::{KeyCode, KeyEvent},
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    widgets::{Block, BorderType, Borders, Paragraph, Wrap},
+};
+
+use crate::{
+    api::clip_page,
+    clip::HttpPageFetcher,
+    db::Db,
+    error::TshError,
+    model::Zettel,
+    tui::{
+        app::{ActiveScreenType, AppCommand, LlmConfig, Screen},
+        common::InputMode,
+        main_menu::MainMenuScreen,
+    },
+};
+
+pub struct ClipScreen {
+    input_mode: InputMode,
+    url: String,
+    summarise: bool,
+    clipped: Option<Zettel>,
+    db: Db,
+    llm_config: LlmConfig,
+}
+
+enum ClipScreenMessage {
+    BackToMainMenu,
+    EnterInsertMode,
+    ExitInsertMode,
+    InsertChar(char),
+    DeleteChar,
+    ToggleSummarise,
+    SubmitUrl(String),
+    IterateZettel(Zettel),
+}
+
+impl ClipScreen {
+    pub fn new(db: Db, llm_config: LlmConfig) -> Self {
+        Self {
+            input_mode: InputMode::Insert,
+            url: String::new(),
+            summarise: llm_config.chat_model.is_some(),
+            clipped: None,
+            db,
+            llm_config,
+        }
+    }
+
+    fn handle_key_event_internal(&mut self, key: KeyEvent) -> Option<ClipScreenMessage> {
+        match self.input_mode {
+            InputMode::Normal => match key.code {
+                KeyCode::Char('i') => Some(ClipScreenMessage::EnterInsertMode),
+                KeyCode::Char('q') | KeyCode::Esc => Some(ClipScreenMessage::BackToMainMenu),
+                KeyCode::Char('s') if self.llm_config.chat_model.is_some() => {
+                    Some(ClipScreenMessage::ToggleSummarise)
+                }
+                KeyCode::Enter => self.clipped.clone().map(ClipScreenMessage::IterateZettel),
+                _ => None,
+            },
+            InputMode::Insert => match key.code {
+                KeyCode::Char(c) => Some(ClipScreenMessage::InsertChar(c)),
+                KeyCode::Backspace => Some(ClipScreenMessage::DeleteChar),
+                KeyCode::Enter => Some(ClipScreenMessage::SubmitUrl(self.url.clone())),
+                KeyCode::Esc => Some(ClipScreenMessage::ExitInsertMode),
+                _ => None,
+            },
+        }
+    }
+
+    async fn update(&mut self, message: ClipScreenMessage) -> Result<(), TshError> {
+        match message {
+            ClipScreenMessage::EnterInsertMode => {
+                self.input_mode = InputMode::Insert;
+            }
+            ClipScreenMessage::ExitInsertMode => {
+                self.input_mode = InputMode::Normal;
+            }
+            ClipScreenMessage::InsertChar(c) => {
+                self.url.push(c);
+            }
+            ClipScreenMessage::DeleteChar => {
+                self.url.pop();
+            }
+            ClipScreenMessage::ToggleSummarise => {
+                self.summarise = !self.summarise;
+            }
+            ClipScreenMessage::SubmitUrl(url) => {
+                self.input_mode = InputMode::Normal;
+                let url = url.trim();
+                if !url.is_empty() {
+                    let fetcher = HttpPageFetcher::new();
+                    let zettel =
+                        clip_page(&self.db, &self.llm_config, &fetcher, url, self.summarise)
+                            .await?;
+                    self.clipped = Some(zettel);
+                    self.url.clear();
+                }
+            }
+            _ => {}
+        };
+        Ok(())
+    }
+}
+
+impl Screen for ClipScreen {
+    async fn handle_key_event(&mut self, key: KeyEvent) -> Result<Option<AppCommand>, TshError> {
+        if let Some(msg) = self.handle_key_event_internal(key) {
+            match msg {
+                ClipScreenMessage::BackToMainMenu => {
+                    Ok(Some(AppCommand::SwitchScreen(ActiveScreenType::Main(
+                        MainMenuScreen::new(self.db.clone(), self.llm_config.clone()),
+                    ))))
+                }
+                ClipScreenMessage::IterateZettel(zettel) => {
+                    Ok(Some(AppCommand::AddZettel(vec![zettel])))
+                }
+                _ => {
+                    self.update(msg).await?;
+                    Ok(None)
+                }
+            }
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn draw(&mut self, f: &mut Frame) {
+        let layout = Layout::new(
+            Direction::Vertical,
+            [Constraint::Length(3), Constraint::Min(0)],
+        )
+        .split(f.area());
+
+        let url_box_style: Style = match self.input_mode {
+            InputMode::Insert => Style::default()
+                .fg(Color::LightGreen)
+                .add_modifier(Modifier::BOLD),
+            InputMode::Normal => Style::default(),
+        };
+        let mode = match (&self.llm_config.chat_model, self.summarise) {
+            (Some(_), true) => "summary, s: store the text instead",
+            (Some(_), false) => "text, s: summarise instead",
+            (None, _) => "text",
+        };
+
+        let url_box = Paragraph::new(self.url.to_string())
+            .style(url_box_style)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded)
+                    .title(format!("URL ({})", mode)),
+            );
+
+        let (title, text) = match &self.clipped {
+            Some(zettel) => (
+                format!("Clipped as {} (Enter to iterate)", zettel.id),
+                zettel.content.to_string(),
+            ),
+            None => (
+                "Clip".to_string(),
+                "Enter the address of a page to store it as a zettel.".to_string(),
+            ),
+        };
+        let clipped = Paragraph::new(text).wrap(Wrap { trim: true }).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .title(title),
+        );
+
+        f.render_widget(url_box, layout[0]);
+        f.render_widget(clipped, layout[1]);
+    }
+}
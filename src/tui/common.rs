@@ -6,19 +6,23 @@ use ratatui::{
 
 use crate::model::{Zettel, ZettelTag};
 
-impl From<&Zettel> for ListItem<'_> {
-    fn from(zettel: &Zettel) -> Self {
-        let lines = vec![
-            Line::styled(
-                format!("{}: {}", zettel.id, zettel.get_datetime_string()),
-                Style::default()
-                    .add_modifier(Modifier::ITALIC)
-                    .fg(Color::LightBlue),
+/// Renders a zettel as a two-line list item whose preview fits into `width` columns.
+pub fn zettel_list_item(zettel: &Zettel, width: u16) -> ListItem<'static> {
+    let lines = vec![
+        Line::styled(
+            format!(
+                "{}: {} · {}",
+                zettel.id,
+                zettel.get_datetime_string(),
+                zettel.get_relative_time()
             ),
-            Line::styled(zettel.get_shim(), Style::default()),
-        ];
-        ListItem::new(lines)
-    }
+            Style::default()
+                .add_modifier(Modifier::ITALIC)
+                .fg(Color::LightBlue),
+        ),
+        Line::styled(zettel.get_shim(width as usize), Style::default()),
+    ];
+    ListItem::new(lines)
 }
 
 impl From<&ZettelTag> for ListItem<'_> {
@@ -47,7 +51,7 @@ where
 {
     pub fn new(items: Vec<T>) -> Self {
         let mut state = ListState::default();
-        if items.len() > 0 {
+        if !items.is_empty() {
             state.select_first();
         }
         ListWithState {
@@ -74,21 +78,21 @@ where
     }
 
     pub fn select_next(&mut self) {
-        if let Some(idx) = self.list_state.selected() {
-            if idx + 1 < self.items.len() {
-                self.list_state.select_next();
-            }
+        if let Some(idx) = self.list_state.selected()
+            && idx + 1 < self.items.len()
+        {
+            self.list_state.select_next();
         }
     }
 
     pub fn select_prev(&mut self) {
-        if let Some(_) = self.list_state.selected() {
+        if self.list_state.selected().is_some() {
             self.list_state.select_previous();
         }
     }
 
     pub fn select_first(&mut self) {
-        if self.items.len() > 0 {
+        if !self.items.is_empty() {
             self.list_state.select_first();
         }
     }
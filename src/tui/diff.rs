@@ -0,0 +1,201 @@
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Clear, Paragraph, Wrap},
+};
+
+use crate::{
+    api::get_parents,
+    db::Db,
+    diff::{DiffKind, attribute_lines, diff_lines},
+    error::TshError,
+    model::Zettel,
+};
+
+/// Lines scrolled by PgUp and PgDn.
+pub const SCROLL_PAGE_LINES: i32 = 10;
+
+/// Shows what changed between a zettel and each of its parents. For remixed zettels with
+/// several parents, an additional page shows which parent each line came from.
+pub struct DiffPanel {
+    zettel: Zettel,
+    parents: Vec<Zettel>,
+    page: usize,
+    /// The first line shown of the current page.
+    scroll: u16,
+}
+
+impl DiffPanel {
+    pub async fn new(db: &Db, zettel: Zettel) -> Result<Self, TshError> {
+        let parents = get_parents(db, zettel.id).await?;
+        Ok(Self {
+            zettel,
+            parents,
+            page: 0,
+            scroll: 0,
+        })
+    }
+
+    fn n_pages(&self) -> usize {
+        if self.parents.len() > 1 {
+            self.parents.len() + 1
+        } else {
+            self.parents.len().max(1)
+        }
+    }
+
+    pub fn next_page(&mut self) {
+        self.page = (self.page + 1) % self.n_pages();
+        self.scroll = 0;
+    }
+
+    pub fn prev_page(&mut self) {
+        self.page = (self.page + self.n_pages() - 1) % self.n_pages();
+        self.scroll = 0;
+    }
+
+    /// Scrolls down by `lines`, or up if negative, keeping at least the last line in view.
+    pub fn scroll_by(&mut self, lines: i32) {
+        let last_line = self.page_content().1.len().saturating_sub(1);
+        let max_scroll = i32::from(u16::try_from(last_line).unwrap_or(u16::MAX));
+        self.scroll = (i32::from(self.scroll) + lines).clamp(0, max_scroll) as u16;
+    }
+
+    pub fn render(&self, f: &mut Frame, area: Rect) {
+        let (title, lines) = self.page_content();
+        let paragraph = Paragraph::new(lines)
+            .wrap(Wrap { trim: false })
+            .scroll((self.scroll, 0))
+            .block(
+                Block::bordered()
+                    .border_type(BorderType::Double)
+                    .border_style(Style::default().add_modifier(Modifier::BOLD))
+                    .title(title),
+            );
+
+        f.render_widget(Clear, area);
+        f.render_widget(paragraph, area);
+    }
+
+    /// The title and lines of the current page.
+    fn page_content(&self) -> (String, Vec<Line<'static>>) {
+        match self.parents.get(self.page) {
+            Some(parent) => (
+                format!(
+                    "Diff {} -> {} ({}/{})",
+                    parent.id,
+                    self.zettel.id,
+                    self.page + 1,
+                    self.n_pages()
+                ),
+                self.diff_against(parent),
+            ),
+            None if self.parents.is_empty() => (
+                "Diff".to_string(),
+                vec![Line::from(format!(
+                    "Zettel {} is a root and has no parents.",
+                    self.zettel.id
+                ))],
+            ),
+            None => (
+                format!("Sources ({}/{})", self.page + 1, self.n_pages()),
+                self.attribution(),
+            ),
+        }
+    }
+
+    fn diff_against(&self, parent: &Zettel) -> Vec<Line<'static>> {
+        render_diff(&parent.content, &self.zettel.content)
+    }
+
+    fn attribution(&self) -> Vec<Line<'static>> {
+        let palette = [
+            Color::LightBlue,
+            Color::LightMagenta,
+            Color::LightYellow,
+            Color::LightCyan,
+        ];
+
+        attribute_lines(&self.zettel, &self.parents)
+            .into_iter()
+            .map(|attributed| {
+                let parent_idx = attributed
+                    .parent_id
+                    .and_then(|id| self.parents.iter().position(|parent| parent.id == id));
+                match parent_idx {
+                    Some(idx) => {
+                        let style = Style::default().fg(palette[idx % palette.len()]);
+                        Line::from(vec![
+                            Span::styled(format!("{:>6} ", self.parents[idx].id), style),
+                            Span::styled(attributed.line, style),
+                        ])
+                    }
+                    None if attributed.line.trim().is_empty() => Line::from(attributed.line),
+                    None => Line::from(vec![
+                        Span::styled("     + ", Style::default().fg(Color::LightGreen)),
+                        Span::raw(attributed.line),
+                    ]),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Renders the changes from `old` to `new` with `+`/`-` markers and changed words reversed.
+pub fn render_diff(old: &str, new: &str) -> Vec<Line<'static>> {
+    diff_lines(old, new)
+        .into_iter()
+        .map(|diff_line| {
+            let (prefix, style) = match diff_line.kind {
+                DiffKind::Equal => ("  ", Style::default()),
+                DiffKind::Insert => ("+ ", Style::default().fg(Color::LightGreen)),
+                DiffKind::Delete => ("- ", Style::default().fg(Color::LightRed)),
+            };
+            let mut spans = vec![Span::styled(prefix, style)];
+            spans.extend(diff_line.segments.into_iter().map(|(emphasized, text)| {
+                if emphasized {
+                    Span::styled(text, style.add_modifier(Modifier::REVERSED))
+                } else {
+                    Span::styled(text, style)
+                }
+            }));
+            Line::from(spans)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn zettel(id: i64, content: &str) -> Zettel {
+        Zettel {
+            id,
+            content: content.to_string(),
+            created_at: 0,
+        }
+    }
+
+    #[test]
+    fn scrolling_stays_within_the_page_and_resets_on_page_change() {
+        let mut panel = DiffPanel {
+            zettel: zettel(3, "a\nb\nc"),
+            parents: vec![zettel(1, "a\n"), zettel(2, "b")],
+            page: 0,
+            scroll: 0,
+        };
+
+        panel.scroll_by(-1);
+        assert_eq!(panel.scroll, 0);
+        panel.scroll_by(SCROLL_PAGE_LINES);
+        // "a" is kept, "b" and "c" are inserted.
+        assert_eq!(panel.scroll, 2);
+        panel.scroll_by(-1);
+        assert_eq!(panel.scroll, 1);
+
+        panel.next_page();
+        assert_eq!(panel.scroll, 0);
+    }
+}
@@ -0,0 +1,680 @@
+use async_trait::async_trait;
+use nvim_rs::{
+    Handler, Neovim, UiAttachOptions, Value, compat::tokio::Compat, create::tokio as create,
+};
+use ratatui::{
+    Frame,
+    buffer::Buffer,
+    crossterm::event::{KeyCode, KeyEvent, KeyModifiers},
+    layout::{Constraint, Direction, Layout, Position, Rect},
+    style::{Color, Modifier, Style},
+    widgets::{Block, BorderType, Borders, Clear},
+};
+use std::{
+    collections::HashMap,
+    fs,
+    io::Write,
+    path::PathBuf,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+use tempfile::NamedTempFile;
+use tokio::process::{Child, ChildStdin, Command};
+use tracing::{debug, info, instrument};
+
+use crate::{error::TshError, model::Zettel};
+
+type Writer = Compat<ChildStdin>;
+
+/// What the content of the editor is stored as when Neovim writes it. After the first write
+/// of a new zettel, the target becomes that zettel, so later writes amend it.
+#[derive(Clone)]
+pub enum EditTarget {
+    /// A new zettel with these parents, tagged with `tags` once stored.
+    New {
+        parents: Vec<Zettel>,
+        tags: Vec<String>,
+    },
+    /// The zettel, amended in place.
+    Amend(Zettel),
+}
+
+pub enum EditorEvent {
+    /// Neovim wrote the buffer and keeps running.
+    Written(String),
+    /// Neovim exited, with the final content of the buffer if it exited cleanly.
+    Closed(Result<String, TshError>),
+}
+
+/// Notifies tsh on every write of the temporary file, whichever buffer it is open in.
+const NOTIFY_ON_WRITE: &str = "
+local channel, path = ...
+vim.api.nvim_create_autocmd('BufWritePost', {
+  callback = function(args)
+    if vim.fn.fnamemodify(args.match, ':p') == vim.fn.fnamemodify(path, ':p') then
+      vim.rpcnotify(channel, 'tsh_buffer_written')
+    end
+  end,
+})
+";
+
+/// Neovim running embedded in a pane next to the current screen. It edits a temporary file
+/// and draws through its UI protocol, so the terminal is never handed over.
+pub struct EditorPane {
+    pub target: EditTarget,
+    nvim: Neovim<Writer>,
+    child: Child,
+    grid: Arc<Mutex<Grid>>,
+    written: Arc<AtomicBool>,
+    /// Whether Neovim wrote the buffer since it was opened.
+    has_written: bool,
+    initial_content: String,
+    temp_file: NamedTempFile,
+    size: (u16, u16),
+}
+
+impl EditorPane {
+    /// Starts Neovim on a temporary file holding `initial_content`, sized to fit the pane
+    /// for a terminal of `terminal_area`.
+    #[instrument(skip_all)]
+    pub async fn open(
+        target: EditTarget,
+        initial_content: &str,
+        terminal_area: Rect,
+    ) -> Result<Self, TshError> {
+        let mut temp_file = NamedTempFile::new()?;
+        temp_file.write_all(initial_content.as_bytes())?;
+        temp_file.flush()?; // ensure all data is written to disk before Neovim opens
+
+        let grid = Arc::new(Mutex::new(Grid::default()));
+        let written = Arc::new(AtomicBool::new(false));
+        let handler = EditorHandler {
+            grid: grid.clone(),
+            written: written.clone(),
+        };
+        let (nvim, _io_handle, child) = create::new_child_cmd(
+            Command::new("nvim")
+                .arg("--embed")
+                .arg(temp_file.path())
+                .kill_on_drop(true),
+            handler,
+        )
+        .await
+        .map_err(|e| TshError::Editor(format!("Could not start Neovim: {}", e)))?;
+
+        let area = editor_area(terminal_area);
+        let size = (area.width.max(1), area.height.max(1));
+        let mut options = UiAttachOptions::new();
+        options.set_rgb(true).set_linegrid_external(true);
+        nvim.ui_attach(size.0 as i64, size.1 as i64, &options)
+            .await
+            .map_err(|e| TshError::Editor(format!("Could not attach to Neovim: {}", e)))?;
+
+        let api_info = nvim
+            .get_api_info()
+            .await
+            .map_err(|e| TshError::Editor(e.to_string()))?;
+        let channel = api_info.first().cloned().unwrap_or(Value::from(0));
+        let path = Value::from(temp_file.path().to_string_lossy().as_ref());
+        nvim.exec_lua(NOTIFY_ON_WRITE, vec![channel, path])
+            .await
+            .map_err(|e| TshError::Editor(format!("Could not watch the buffer: {}", e)))?;
+        info!("Neovim started");
+
+        Ok(Self {
+            target,
+            nvim,
+            child,
+            grid,
+            written,
+            has_written: false,
+            initial_content: initial_content.to_string(),
+            temp_file,
+            size,
+        })
+    }
+
+    pub async fn send_key(&self, key: KeyEvent) -> Result<(), TshError> {
+        if let Some(keys) = to_nvim_keys(key) {
+            self.nvim
+                .input(&keys)
+                .await
+                .map_err(|e| TshError::Editor(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// Keeps Neovim's grid the size of the pane when the terminal is resized.
+    pub async fn resize(&mut self, terminal_area: Rect) -> Result<(), TshError> {
+        let area = editor_area(terminal_area);
+        let size = (area.width.max(1), area.height.max(1));
+        if size != self.size {
+            self.size = size;
+            self.nvim
+                .ui_try_resize(size.0 as i64, size.1 as i64)
+                .await
+                .map_err(|e| TshError::Editor(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// Returns the content of the buffer if Neovim wrote it or exited since the last call.
+    pub fn poll(&mut self) -> Result<Option<EditorEvent>, TshError> {
+        if let Some(status) = self.child.try_wait()? {
+            info!(%status, "Neovim exited");
+            let content = if status.success() {
+                self.read_buffer()
+            } else {
+                Err(TshError::Editor(format!(
+                    "Neovim exited with an error: {}",
+                    status
+                )))
+            };
+            return Ok(Some(EditorEvent::Closed(content)));
+        }
+
+        if self.written.swap(false, Ordering::SeqCst) {
+            debug!("Buffer written");
+            self.has_written = true;
+            return Ok(Some(EditorEvent::Written(self.read_buffer()?)));
+        }
+
+        Ok(None)
+    }
+
+    /// Whether closing Neovim with `content` in the buffer should store it: only if it was
+    /// written or edited, so quitting right away doesn't store the content it started with.
+    pub fn should_store_on_close(&self, content: &str) -> bool {
+        self.has_written || content != self.initial_content
+    }
+
+    /// Whether `content` is still what a new zettel without parents started with, like the
+    /// heading of a journal entry. That is as empty as a blank buffer.
+    pub fn is_untouched_start(&self, content: &str) -> bool {
+        is_untouched_start(&self.target, &self.initial_content, content)
+    }
+
+    /// Closes the pane but keeps the temporary file, so its text isn't lost when it could
+    /// not be stored. Returns where the file is.
+    pub fn keep_file(self) -> Result<PathBuf, TshError> {
+        let (_, path) = self.temp_file.keep().map_err(|e| TshError::Io(e.error))?;
+        Ok(path)
+    }
+
+    fn read_buffer(&self) -> Result<String, TshError> {
+        Ok(fs::read_to_string(self.temp_file.path())?)
+    }
+
+    pub fn draw(&self, frame: &mut Frame) {
+        let area = pane_area(frame.area());
+        let title = match &self.target {
+            EditTarget::New { .. } => "Neovim: new zettel".to_string(),
+            EditTarget::Amend(zettel) => format!("Neovim: amend zettel {}", zettel.id),
+        };
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Thick)
+            .title(title);
+        let inner_area = block.inner(area);
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(block, area);
+
+        let grid = self.grid.lock().unwrap();
+        grid.render(inner_area, frame.buffer_mut());
+        let (row, col) = grid.cursor;
+        if row < inner_area.height && col < inner_area.width {
+            frame.set_cursor_position(Position::new(inner_area.x + col, inner_area.y + row));
+        }
+    }
+}
+
+fn is_untouched_start(target: &EditTarget, initial_content: &str, content: &str) -> bool {
+    matches!(target, EditTarget::New { parents, .. } if parents.is_empty())
+        && content.trim() == initial_content.trim()
+}
+
+/// The right half of the screen, where screens show previews, so lists stay visible.
+fn pane_area(area: Rect) -> Rect {
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(area)[1]
+}
+
+fn editor_area(terminal_area: Rect) -> Rect {
+    Block::default()
+        .borders(Borders::ALL)
+        .inner(pane_area(terminal_area))
+}
+
+/// Translates a key press to Neovim's key notation, see `:help key-notation`.
+fn to_nvim_keys(key: KeyEvent) -> Option<String> {
+    let name = match key.code {
+        KeyCode::Char('<') => "lt".to_string(),
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::Enter => "CR".to_string(),
+        KeyCode::Esc => "Esc".to_string(),
+        KeyCode::Backspace => "BS".to_string(),
+        KeyCode::Tab => "Tab".to_string(),
+        KeyCode::BackTab => "Tab".to_string(),
+        KeyCode::Delete => "Del".to_string(),
+        KeyCode::Insert => "Insert".to_string(),
+        KeyCode::Up => "Up".to_string(),
+        KeyCode::Down => "Down".to_string(),
+        KeyCode::Left => "Left".to_string(),
+        KeyCode::Right => "Right".to_string(),
+        KeyCode::Home => "Home".to_string(),
+        KeyCode::End => "End".to_string(),
+        KeyCode::PageUp => "PageUp".to_string(),
+        KeyCode::PageDown => "PageDown".to_string(),
+        KeyCode::F(n) => format!("F{}", n),
+        _ => return None,
+    };
+
+    let mut modifiers = String::new();
+    if key.modifiers.contains(KeyModifiers::CONTROL) {
+        modifiers.push_str("C-");
+    }
+    if key.modifiers.contains(KeyModifiers::ALT) {
+        modifiers.push_str("M-");
+    }
+    // Shifted characters already arrive as the character they produce.
+    let shifted = key.modifiers.contains(KeyModifiers::SHIFT) || key.code == KeyCode::BackTab;
+    if shifted && !matches!(key.code, KeyCode::Char(_)) {
+        modifiers.push_str("S-");
+    }
+
+    let is_plain_char = matches!(key.code, KeyCode::Char(c) if c != '<');
+    if modifiers.is_empty() && is_plain_char {
+        Some(name)
+    } else {
+        Some(format!("<{}{}>", modifiers, name))
+    }
+}
+
+#[derive(Clone)]
+struct GridCell {
+    text: String,
+    hl_id: u64,
+}
+
+impl Default for GridCell {
+    fn default() -> Self {
+        Self {
+            text: " ".to_string(),
+            hl_id: 0,
+        }
+    }
+}
+
+/// The screen contents Neovim sends with the `ext_linegrid` UI events.
+#[derive(Default)]
+struct Grid {
+    width: usize,
+    height: usize,
+    cells: Vec<GridCell>,
+    cursor: (u16, u16),
+    default_style: Style,
+    highlights: HashMap<u64, Style>,
+}
+
+impl Grid {
+    fn resize(&mut self, width: usize, height: usize) {
+        self.width = width;
+        self.height = height;
+        self.cells = vec![GridCell::default(); width * height];
+    }
+
+    fn clear(&mut self) {
+        self.cells.fill(GridCell::default());
+    }
+
+    fn cell_mut(&mut self, row: usize, col: usize) -> Option<&mut GridCell> {
+        if row < self.height && col < self.width {
+            self.cells.get_mut(row * self.width + col)
+        } else {
+            None
+        }
+    }
+
+    /// Applies one batch of a `redraw` notification, e.g. `["grid_line", [...], [...]]`.
+    fn apply(&mut self, event: &[Value]) {
+        let Some((name, calls)) = event.split_first() else {
+            return;
+        };
+        let Some(name) = name.as_str() else {
+            return;
+        };
+
+        for call in calls {
+            let Some(args) = call.as_array() else {
+                continue;
+            };
+            match name {
+                "grid_resize" => {
+                    self.resize(int_arg(args, 1) as usize, int_arg(args, 2) as usize);
+                }
+                "grid_clear" => self.clear(),
+                "grid_cursor_goto" => {
+                    self.cursor = (int_arg(args, 1) as u16, int_arg(args, 2) as u16);
+                }
+                "grid_line" => self.apply_line(args),
+                "grid_scroll" => self.apply_scroll(args),
+                "default_colors_set" => {
+                    self.default_style = Style::default()
+                        .fg(rgb_color(int_arg(args, 0)).unwrap_or(Color::Reset))
+                        .bg(rgb_color(int_arg(args, 1)).unwrap_or(Color::Reset));
+                }
+                "hl_attr_define" => {
+                    let style = args
+                        .get(1)
+                        .and_then(Value::as_map)
+                        .map(|attrs| highlight_style(attrs))
+                        .unwrap_or_default();
+                    self.highlights.insert(int_arg(args, 0) as u64, style);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// `[grid, row, col_start, cells]`, where a cell is `[text, hl_id?, repeat?]` and an
+    /// omitted `hl_id` repeats the previous one.
+    fn apply_line(&mut self, args: &[Value]) {
+        let row = int_arg(args, 1) as usize;
+        let mut col = int_arg(args, 2) as usize;
+        let Some(cells) = args.get(3).and_then(Value::as_array) else {
+            return;
+        };
+
+        let mut hl_id = 0;
+        for cell in cells {
+            let Some(cell) = cell.as_array() else {
+                continue;
+            };
+            let text = cell.first().and_then(Value::as_str).unwrap_or(" ");
+            if let Some(id) = cell.get(1).and_then(Value::as_u64) {
+                hl_id = id;
+            }
+            let repeat = cell.get(2).and_then(Value::as_u64).unwrap_or(1);
+            for _ in 0..repeat {
+                if let Some(grid_cell) = self.cell_mut(row, col) {
+                    grid_cell.text = text.to_string();
+                    grid_cell.hl_id = hl_id;
+                }
+                col += 1;
+            }
+        }
+    }
+
+    /// `[grid, top, bot, left, right, rows, cols]`: moves the region up by `rows` (down if
+    /// negative). Neovim redraws the rows that scrolled in afterwards.
+    fn apply_scroll(&mut self, args: &[Value]) {
+        let top = int_arg(args, 1);
+        let bottom = int_arg(args, 2);
+        let left = int_arg(args, 3) as usize;
+        let right = int_arg(args, 4) as usize;
+        let rows = int_arg(args, 5);
+
+        let source_rows: Vec<i64> = if rows > 0 {
+            (top..bottom - rows).collect()
+        } else {
+            (top - rows..bottom).rev().collect()
+        };
+        for row in source_rows {
+            let from = (row + rows) as usize;
+            for col in left..right.min(self.width) {
+                if let Some(cell) = self.cell_mut(from, col).cloned()
+                    && let Some(target) = self.cell_mut(row as usize, col)
+                {
+                    *target = cell;
+                }
+            }
+        }
+    }
+
+    fn render(&self, area: Rect, buf: &mut Buffer) {
+        for row in 0..self.height.min(area.height as usize) {
+            for col in 0..self.width.min(area.width as usize) {
+                let cell = &self.cells[row * self.width + col];
+                let style = self.default_style.patch(
+                    self.highlights
+                        .get(&cell.hl_id)
+                        .copied()
+                        .unwrap_or_default(),
+                );
+                let position = (area.x + col as u16, area.y + row as u16);
+                // The right half of a double-width character is sent as an empty cell.
+                if cell.text.is_empty() {
+                    buf[position].set_skip(true);
+                } else {
+                    buf[position].set_symbol(&cell.text).set_style(style);
+                }
+            }
+        }
+    }
+}
+
+fn int_arg(args: &[Value], idx: usize) -> i64 {
+    args.get(idx).and_then(Value::as_i64).unwrap_or(0)
+}
+
+/// Neovim sends -1 for colors that aren't set.
+fn rgb_color(rgb: i64) -> Option<Color> {
+    if rgb < 0 {
+        return None;
+    }
+    Some(Color::Rgb((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8))
+}
+
+fn highlight_style(attrs: &[(Value, Value)]) -> Style {
+    let mut style = Style::default();
+    for (key, value) in attrs {
+        match (key.as_str(), value) {
+            (Some("foreground"), value) => {
+                if let Some(color) = value.as_i64().and_then(rgb_color) {
+                    style = style.fg(color);
+                }
+            }
+            (Some("background"), value) => {
+                if let Some(color) = value.as_i64().and_then(rgb_color) {
+                    style = style.bg(color);
+                }
+            }
+            (Some("bold"), Value::Boolean(true)) => style = style.add_modifier(Modifier::BOLD),
+            (Some("italic"), Value::Boolean(true)) => style = style.add_modifier(Modifier::ITALIC),
+            (Some("underline"), Value::Boolean(true)) => {
+                style = style.add_modifier(Modifier::UNDERLINED)
+            }
+            (Some("strikethrough"), Value::Boolean(true)) => {
+                style = style.add_modifier(Modifier::CROSSED_OUT)
+            }
+            (Some("reverse"), Value::Boolean(true)) => {
+                style = style.add_modifier(Modifier::REVERSED)
+            }
+            _ => {}
+        }
+    }
+    style
+}
+
+#[derive(Clone)]
+struct EditorHandler {
+    grid: Arc<Mutex<Grid>>,
+    written: Arc<AtomicBool>,
+}
+
+#[async_trait]
+impl Handler for EditorHandler {
+    type Writer = Writer;
+
+    async fn handle_notify(&self, name: String, args: Vec<Value>, _neovim: Neovim<Writer>) {
+        match name.as_str() {
+            "redraw" => {
+                let mut grid = self.grid.lock().unwrap();
+                for event in args.iter().filter_map(Value::as_array) {
+                    grid.apply(event);
+                }
+            }
+            "tsh_buffer_written" => self.written.store(true, Ordering::SeqCst),
+            _ => debug!(name, "Ignoring Neovim notification"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_new_entry_with_only_its_heading_is_untouched() {
+        let heading = "# 2025-01-02\n\n";
+        let journal = EditTarget::New {
+            parents: vec![],
+            tags: vec!["journal/2025-01-02".to_string()],
+        };
+        assert!(is_untouched_start(&journal, heading, heading));
+        assert!(is_untouched_start(&journal, heading, "# 2025-01-02\n"));
+        assert!(!is_untouched_start(
+            &journal,
+            heading,
+            "# 2025-01-02\n\nWrote tests."
+        ));
+
+        let entry = Zettel {
+            id: 1,
+            content: heading.to_string(),
+            created_at: 0,
+        };
+        let iteration = EditTarget::New {
+            parents: vec![entry.clone()],
+            tags: vec![],
+        };
+        assert!(!is_untouched_start(&iteration, heading, heading));
+        assert!(!is_untouched_start(
+            &EditTarget::Amend(entry),
+            heading,
+            heading
+        ));
+    }
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> Option<String> {
+        to_nvim_keys(KeyEvent::new(code, modifiers))
+    }
+
+    #[test]
+    fn keys_are_translated_to_nvim_notation() {
+        let cases = [
+            (KeyCode::Char('a'), KeyModifiers::NONE, "a"),
+            (KeyCode::Char('A'), KeyModifiers::SHIFT, "A"),
+            (KeyCode::Char('x'), KeyModifiers::CONTROL, "<C-x>"),
+            (KeyCode::Char('<'), KeyModifiers::NONE, "<lt>"),
+            (KeyCode::Char('<'), KeyModifiers::ALT, "<M-lt>"),
+            (KeyCode::Esc, KeyModifiers::NONE, "<Esc>"),
+            (KeyCode::Enter, KeyModifiers::NONE, "<CR>"),
+            (KeyCode::Up, KeyModifiers::NONE, "<Up>"),
+            (KeyCode::Down, KeyModifiers::NONE, "<Down>"),
+            (KeyCode::Left, KeyModifiers::SHIFT, "<S-Left>"),
+            (KeyCode::Right, KeyModifiers::CONTROL, "<C-Right>"),
+            (KeyCode::BackTab, KeyModifiers::SHIFT, "<S-Tab>"),
+        ];
+        for (code, modifiers, expected) in cases {
+            assert_eq!(
+                key(code, modifiers).as_deref(),
+                Some(expected),
+                "{:?}",
+                code
+            );
+        }
+        assert_eq!(key(KeyCode::CapsLock, KeyModifiers::NONE), None);
+    }
+
+    fn event(name: &str, calls: Vec<Vec<Value>>) -> Vec<Value> {
+        let mut event = vec![Value::from(name)];
+        event.extend(calls.into_iter().map(Value::Array));
+        event
+    }
+
+    fn ints(values: &[i64]) -> Vec<Value> {
+        values.iter().map(|value| Value::from(*value)).collect()
+    }
+
+    /// A `grid_line` call writing one cell per character of `text` at the start of `row`.
+    fn line(row: i64, text: &str) -> Vec<Value> {
+        let cells = text
+            .chars()
+            .map(|c| Value::Array(vec![Value::from(c.to_string())]))
+            .collect();
+        let mut args = ints(&[1, row, 0]);
+        args.push(Value::Array(cells));
+        args
+    }
+
+    fn rows(grid: &Grid) -> Vec<String> {
+        grid.cells
+            .chunks(grid.width)
+            .map(|row| row.iter().map(|cell| cell.text.as_str()).collect())
+            .collect()
+    }
+
+    /// A 3x3 grid with the rows `aaa`, `bbb` and `ccc`.
+    fn filled_grid() -> Grid {
+        let mut grid = Grid::default();
+        grid.apply(&event("grid_resize", vec![ints(&[1, 3, 3])]));
+        grid.apply(&event(
+            "grid_line",
+            vec![line(0, "aaa"), line(1, "bbb"), line(2, "ccc")],
+        ));
+        grid
+    }
+
+    #[test]
+    fn grid_line_repeats_cells_and_highlights() {
+        let mut grid = Grid::default();
+        grid.apply(&event("grid_resize", vec![ints(&[1, 6, 1])]));
+
+        let cells = vec![
+            Value::Array(vec!["a".into(), 1.into(), 2.into()]),
+            Value::Array(vec!["b".into()]),
+            Value::Array(vec!["-".into(), 2.into(), 5.into()]),
+        ];
+        let mut args = ints(&[1, 0, 0]);
+        args.push(Value::Array(cells));
+        grid.apply(&event("grid_line", vec![args]));
+
+        // The last repeat runs past the width and is cut off.
+        assert_eq!(rows(&grid), ["aab---"]);
+        let hl_ids: Vec<u64> = grid.cells.iter().map(|cell| cell.hl_id).collect();
+        assert_eq!(hl_ids, [1, 1, 1, 2, 2, 2]);
+    }
+
+    #[test]
+    fn grid_scroll_moves_rows_up_and_down() {
+        let mut grid = filled_grid();
+        grid.apply(&event("grid_scroll", vec![ints(&[1, 0, 3, 0, 3, 1, 0])]));
+        assert_eq!(rows(&grid), ["bbb", "ccc", "ccc"]);
+
+        let mut grid = filled_grid();
+        grid.apply(&event("grid_scroll", vec![ints(&[1, 0, 3, 0, 3, -1, 0])]));
+        assert_eq!(rows(&grid), ["aaa", "aaa", "bbb"]);
+
+        let mut grid = filled_grid();
+        grid.apply(&event("grid_scroll", vec![ints(&[1, 1, 3, 1, 2, 1, 0])]));
+        assert_eq!(rows(&grid), ["aaa", "bcb", "ccc"]);
+    }
+
+    #[test]
+    fn grid_resize_clears_the_grid() {
+        let mut grid = filled_grid();
+
+        grid.apply(&event("grid_resize", vec![ints(&[1, 2, 2])]));
+
+        assert_eq!((grid.width, grid.height), (2, 2));
+        assert_eq!(rows(&grid), ["  ", "  "]);
+        grid.apply(&event("grid_line", vec![line(1, "xyz"), line(2, "x")]));
+        assert_eq!(rows(&grid), ["  ", "xy"]);
+    }
+}
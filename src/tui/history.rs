@@ -0,0 +1,216 @@
+use ratatui::{
+    Frame,
+    crossterm::event::{KeyCode, KeyEvent},
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Block, BorderType, Borders, List, ListItem, Paragraph, Wrap},
+};
+
+use crate::{
+    api::{get_revisions, restore_revision},
+    db::Db,
+    error::TshError,
+    model::{Zettel, ZettelRevision},
+    tui::{
+        app::{ActiveScreenType, AppCommand, LlmConfig, Screen},
+        common::ListWithState,
+        diff::render_diff,
+        recent::RecentScreen,
+    },
+};
+
+#[derive(Clone)]
+enum Version {
+    Current(Zettel),
+    Revision(ZettelRevision),
+}
+
+impl Version {
+    fn content(&self) -> &str {
+        match self {
+            Version::Current(zettel) => &zettel.content,
+            Version::Revision(revision) => &revision.content,
+        }
+    }
+}
+
+/// Lists the earlier contents of an amended zettel, which can be compared with the current
+/// one and restored.
+pub struct HistoryScreen {
+    zettel: Zettel,
+    versions: ListWithState<Version>,
+    db: Db,
+    llm_config: LlmConfig,
+}
+
+enum HistoryScreenMessage {
+    BackToRecent,
+    VersionListMoveUp,
+    VersionListMoveDown,
+    RestoreSelected,
+    AmendZettel(Zettel),
+}
+
+impl HistoryScreen {
+    pub async fn new(db: Db, llm_config: LlmConfig, zettel: Zettel) -> Result<Self, TshError> {
+        let versions = load_versions(&db, &zettel).await?;
+        Ok(Self {
+            zettel,
+            versions: ListWithState::new(versions),
+            db,
+            llm_config,
+        })
+    }
+
+    /// Picks up a new current content, e.g. after the zettel was amended from this screen.
+    pub async fn reload(&mut self, zettel: Zettel) -> Result<(), TshError> {
+        self.versions = ListWithState::new(load_versions(&self.db, &zettel).await?);
+        self.zettel = zettel;
+        Ok(())
+    }
+
+    fn handle_key_event_internal(&mut self, key: KeyEvent) -> Option<HistoryScreenMessage> {
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => Some(HistoryScreenMessage::BackToRecent),
+            KeyCode::Up => Some(HistoryScreenMessage::VersionListMoveUp),
+            KeyCode::Down => Some(HistoryScreenMessage::VersionListMoveDown),
+            KeyCode::Char('r') => Some(HistoryScreenMessage::RestoreSelected),
+            KeyCode::Char('e') => Some(HistoryScreenMessage::AmendZettel(self.zettel.clone())),
+            _ => None,
+        }
+    }
+
+    async fn update(&mut self, message: HistoryScreenMessage) -> Result<(), TshError> {
+        match message {
+            HistoryScreenMessage::VersionListMoveUp => self.versions.select_prev(),
+            HistoryScreenMessage::VersionListMoveDown => self.versions.select_next(),
+            HistoryScreenMessage::RestoreSelected => {
+                if let Some(Version::Revision(revision)) = self.versions.get_selected_item() {
+                    let zettel = restore_revision(&self.db, &self.llm_config, &revision).await?;
+                    self.reload(zettel).await?;
+                }
+            }
+            _ => {}
+        };
+        Ok(())
+    }
+}
+
+async fn load_versions(db: &Db, zettel: &Zettel) -> Result<Vec<Version>, TshError> {
+    let revisions = get_revisions(db, zettel.id).await?;
+    Ok(std::iter::once(Version::Current(zettel.clone()))
+        .chain(revisions.into_iter().map(Version::Revision))
+        .collect())
+}
+
+impl Screen for HistoryScreen {
+    async fn handle_key_event(&mut self, key: KeyEvent) -> Result<Option<AppCommand>, TshError> {
+        if let Some(msg) = self.handle_key_event_internal(key) {
+            match msg {
+                HistoryScreenMessage::BackToRecent => {
+                    Ok(Some(AppCommand::SwitchScreen(ActiveScreenType::Recent(
+                        RecentScreen::new(self.db.clone(), self.llm_config.clone()).await?,
+                    ))))
+                }
+                HistoryScreenMessage::AmendZettel(zettel) => {
+                    Ok(Some(AppCommand::AmendZettel(zettel)))
+                }
+                _ => {
+                    self.update(msg).await?;
+                    Ok(None)
+                }
+            }
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn draw(&mut self, f: &mut Frame) {
+        let layout = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
+            .split(f.area());
+
+        let inner_layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(layout[1]);
+
+        let selected_idx = self.versions.curr_idx();
+        let version_list_items: Vec<ListItem> = self
+            .versions
+            .items
+            .iter()
+            .enumerate()
+            .map(|(i, version)| {
+                let label = match version {
+                    Version::Current(zettel) => {
+                        format!("current (created {})", zettel.get_datetime_string())
+                    }
+                    Version::Revision(revision) => {
+                        format!("replaced {}", revision.get_datetime_string())
+                    }
+                };
+                let mut item = ListItem::new(Line::styled(
+                    label,
+                    Style::default()
+                        .add_modifier(Modifier::ITALIC)
+                        .fg(Color::LightBlue),
+                ));
+                if selected_idx == Some(i) {
+                    item = item.style(
+                        Style::default()
+                            .bg(Color::DarkGray)
+                            .add_modifier(Modifier::BOLD),
+                    );
+                }
+                item
+            })
+            .collect();
+
+        let version_list = List::new(version_list_items).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Thick)
+                .title(format!("History of {}", self.zettel.id)),
+        );
+
+        let selected = self.versions.get_selected_item();
+
+        let preview = Paragraph::new(
+            selected
+                .as_ref()
+                .map(|version| version.content().to_string())
+                .unwrap_or_default(),
+        )
+        .wrap(Wrap { trim: true })
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Thick)
+                .title("Preview"),
+        );
+
+        let changes = match &selected {
+            Some(Version::Revision(revision)) => {
+                Paragraph::new(render_diff(&revision.content, &self.zettel.content))
+            }
+            Some(Version::Current(_)) if self.versions.items.len() == 1 => {
+                Paragraph::new("This zettel has never been amended. Press e to amend it.")
+            }
+            _ => Paragraph::new("Select a revision to see what changed since, r restores it."),
+        }
+        .wrap(Wrap { trim: false })
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Thick)
+                .title("Changes since"),
+        );
+
+        f.render_stateful_widget(version_list, layout[0], &mut self.versions.list_state);
+        f.render_widget(preview, inner_layout[0]);
+        f.render_widget(changes, inner_layout[1]);
+    }
+}
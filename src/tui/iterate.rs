@@ -1,18 +1,19 @@
-use std::error::Error;
-
 use ratatui::{
     Frame,
     crossterm::event::{KeyCode, KeyEvent},
     layout::{Constraint, Direction, Layout},
     style::{Color, Modifier, Style},
-    widgets::{Block, BorderType, Borders, List, ListItem, Paragraph},
+    widgets::{Block, BorderType, Borders, List, ListItem, Paragraph, Wrap},
 };
 
 use crate::{
     api::find_zettels,
+    db::Db,
+    error::TshError,
     model::Zettel,
     tui::{
         app::{ActiveScreenType, AppCommand, LlmConfig, Screen},
+        common::zettel_list_item,
         main_menu::MainMenuScreen,
     },
 };
@@ -22,7 +23,7 @@ pub struct IterateZettelScreen {
     search_query: String,
     search_results: Vec<Zettel>,
     selected_result: Option<usize>,
-    db_path: String,
+    db: Db,
     llm_config: LlmConfig,
 }
 
@@ -44,13 +45,13 @@ enum IterateScreenMessage {
 }
 
 impl IterateZettelScreen {
-    pub fn new(db_path: String, llm_config: LlmConfig) -> Self {
+    pub fn new(db: Db, llm_config: LlmConfig) -> Self {
         Self {
             input_mode: InputMode::Normal,
             search_query: String::new(),
             search_results: vec![],
             selected_result: None,
-            db_path,
+            db,
             llm_config,
         }
     }
@@ -84,7 +85,7 @@ impl IterateZettelScreen {
         }
     }
 
-    async fn update(&mut self, message: IterateScreenMessage) -> Result<(), Box<dyn Error>> {
+    async fn update(&mut self, message: IterateScreenMessage) -> Result<(), TshError> {
         match message {
             IterateScreenMessage::EnterInsertMode => {
                 self.input_mode = InputMode::Insert;
@@ -99,8 +100,8 @@ impl IterateZettelScreen {
                 self.search_query.pop();
             }
             IterateScreenMessage::SubmitQuery(query) => {
-                self.search_results = find_zettels(&self.db_path, &self.llm_config, &query).await?;
-                if self.search_results.len() != 0 {
+                self.search_results = find_zettels(&self.db, &self.llm_config, &query).await?;
+                if !self.search_results.is_empty() {
                     self.selected_result = Some(0);
                 }
                 self.input_mode = InputMode::Normal;
@@ -112,10 +113,10 @@ impl IterateZettelScreen {
                 }
             }
             IterateScreenMessage::ResultListMoveDown => {
-                if let Some(idx) = self.selected_result {
-                    if idx + 1 < self.search_results.len() {
-                        self.selected_result = Some(idx + 1);
-                    }
+                if let Some(idx) = self.selected_result
+                    && idx + 1 < self.search_results.len()
+                {
+                    self.selected_result = Some(idx + 1);
                 }
             }
             _ => {}
@@ -125,15 +126,12 @@ impl IterateZettelScreen {
 }
 
 impl Screen for IterateZettelScreen {
-    async fn handle_key_event(
-        &mut self,
-        key: KeyEvent,
-    ) -> Result<Option<AppCommand>, Box<dyn Error>> {
+    async fn handle_key_event(&mut self, key: KeyEvent) -> Result<Option<AppCommand>, TshError> {
         if let Some(msg) = self.handle_key_event_internal(key) {
             match msg {
                 IterateScreenMessage::BackToMainMenu => {
                     Ok(Some(AppCommand::SwitchScreen(ActiveScreenType::Main(
-                        MainMenuScreen::new(self.db_path.clone(), self.llm_config.clone()),
+                        MainMenuScreen::new(self.db.clone(), self.llm_config.clone()),
                     ))))
                 }
                 IterateScreenMessage::IterateZettel(zettel) => {
@@ -177,20 +175,21 @@ impl Screen for IterateZettelScreen {
                     .title("Query"),
             );
 
+        let list_width = inner_layout[0].width.saturating_sub(2);
         let search_results: Vec<ListItem> = self
             .search_results
             .iter()
             .enumerate()
             .map(|(i, zettel)| {
-                let mut item = ListItem::from(zettel);
-                if let Some(idx) = self.selected_result {
-                    if i == idx {
-                        item = item.style(
-                            Style::default()
-                                .bg(Color::DarkGray)
-                                .add_modifier(Modifier::BOLD),
-                        );
-                    }
+                let mut item = zettel_list_item(zettel, list_width);
+                if let Some(idx) = self.selected_result
+                    && i == idx
+                {
+                    item = item.style(
+                        Style::default()
+                            .bg(Color::DarkGray)
+                            .add_modifier(Modifier::BOLD),
+                    );
                 }
                 item
             })
@@ -211,7 +210,7 @@ impl Screen for IterateZettelScreen {
             None => Paragraph::default(),
         };
 
-        let preview = preview_paragraph.block(
+        let preview = preview_paragraph.wrap(Wrap { trim: true }).block(
             Block::default()
                 .borders(Borders::ALL)
                 .border_type(BorderType::Rounded)
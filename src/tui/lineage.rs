@@ -0,0 +1,243 @@
+use ratatui::{
+    Frame,
+    crossterm::event::{KeyCode, KeyEvent},
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    widgets::{Block, BorderType, Borders, List, ListItem, Paragraph, Wrap},
+};
+
+use crate::{
+    api::{get_cached_lineage_summary, get_lineage, summarise_lineage},
+    db::Db,
+    error::TshError,
+    model::Zettel,
+    tui::{
+        app::{ActiveScreenType, AppCommand, LlmConfig, Screen},
+        common::{ListWithState, zettel_list_item},
+        diff::{DiffPanel, SCROLL_PAGE_LINES},
+        recent::RecentScreen,
+    },
+};
+
+pub struct LineageScreen {
+    zettel: Zettel,
+    lineage: ListWithState<Zettel>,
+    summary: Option<String>,
+    diff_panel: Option<DiffPanel>,
+    db: Db,
+    llm_config: LlmConfig,
+}
+
+enum LineageScreenMessage {
+    BackToRecent,
+    LineageListMoveUp,
+    LineageListMoveDown,
+    Summarise,
+    ToggleDiff,
+    DiffNextPage,
+    DiffPrevPage,
+    DiffScroll(i32),
+    IterateZettel(Zettel),
+}
+
+impl LineageScreen {
+    pub async fn new(db: Db, llm_config: LlmConfig, zettel: Zettel) -> Result<Self, TshError> {
+        let lineage = get_lineage(&db, &zettel).await?;
+        let summary = get_cached_lineage_summary(&db, &llm_config, zettel.id).await?;
+        let n_versions = lineage.len();
+        let mut lineage = ListWithState::new(lineage);
+        lineage.list_state.select(n_versions.checked_sub(1));
+        Ok(Self {
+            zettel,
+            lineage,
+            summary,
+            diff_panel: None,
+            db,
+            llm_config,
+        })
+    }
+
+    fn handle_key_event_internal(&mut self, key: KeyEvent) -> Option<LineageScreenMessage> {
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => Some(LineageScreenMessage::BackToRecent),
+            KeyCode::Char('s') if self.llm_config.chat_model.is_some() => {
+                Some(LineageScreenMessage::Summarise)
+            }
+            KeyCode::Char('d') => Some(LineageScreenMessage::ToggleDiff),
+            KeyCode::Right | KeyCode::Tab if self.diff_panel.is_some() => {
+                Some(LineageScreenMessage::DiffNextPage)
+            }
+            KeyCode::Left | KeyCode::BackTab if self.diff_panel.is_some() => {
+                Some(LineageScreenMessage::DiffPrevPage)
+            }
+            KeyCode::Char('j') if self.diff_panel.is_some() => {
+                Some(LineageScreenMessage::DiffScroll(1))
+            }
+            KeyCode::Char('k') if self.diff_panel.is_some() => {
+                Some(LineageScreenMessage::DiffScroll(-1))
+            }
+            KeyCode::PageDown if self.diff_panel.is_some() => {
+                Some(LineageScreenMessage::DiffScroll(SCROLL_PAGE_LINES))
+            }
+            KeyCode::PageUp if self.diff_panel.is_some() => {
+                Some(LineageScreenMessage::DiffScroll(-SCROLL_PAGE_LINES))
+            }
+            KeyCode::Up => Some(LineageScreenMessage::LineageListMoveUp),
+            KeyCode::Down => Some(LineageScreenMessage::LineageListMoveDown),
+            KeyCode::Enter => self
+                .lineage
+                .get_selected_item()
+                .map(LineageScreenMessage::IterateZettel),
+            _ => None,
+        }
+    }
+
+    async fn update(&mut self, message: LineageScreenMessage) -> Result<(), TshError> {
+        match message {
+            LineageScreenMessage::LineageListMoveUp => {
+                self.lineage.select_prev();
+                self.refresh_diff_panel().await?;
+            }
+            LineageScreenMessage::LineageListMoveDown => {
+                self.lineage.select_next();
+                self.refresh_diff_panel().await?;
+            }
+            LineageScreenMessage::ToggleDiff => {
+                if self.diff_panel.is_some() {
+                    self.diff_panel = None;
+                } else if let Some(zettel) = self.lineage.get_selected_item() {
+                    self.diff_panel = Some(DiffPanel::new(&self.db, zettel).await?);
+                }
+            }
+            LineageScreenMessage::DiffNextPage => {
+                if let Some(panel) = &mut self.diff_panel {
+                    panel.next_page();
+                }
+            }
+            LineageScreenMessage::DiffPrevPage => {
+                if let Some(panel) = &mut self.diff_panel {
+                    panel.prev_page();
+                }
+            }
+            LineageScreenMessage::DiffScroll(lines) => {
+                if let Some(panel) = &mut self.diff_panel {
+                    panel.scroll_by(lines);
+                }
+            }
+            LineageScreenMessage::Summarise => {
+                let summary = summarise_lineage(&self.db, &self.llm_config, &self.zettel).await?;
+                self.summary = Some(summary);
+            }
+            _ => {}
+        };
+        Ok(())
+    }
+
+    async fn refresh_diff_panel(&mut self) -> Result<(), TshError> {
+        if self.diff_panel.is_some()
+            && let Some(zettel) = self.lineage.get_selected_item()
+        {
+            self.diff_panel = Some(DiffPanel::new(&self.db, zettel).await?);
+        }
+        Ok(())
+    }
+}
+
+impl Screen for LineageScreen {
+    async fn handle_key_event(&mut self, key: KeyEvent) -> Result<Option<AppCommand>, TshError> {
+        if let Some(msg) = self.handle_key_event_internal(key) {
+            match msg {
+                LineageScreenMessage::BackToRecent => {
+                    Ok(Some(AppCommand::SwitchScreen(ActiveScreenType::Recent(
+                        RecentScreen::new(self.db.clone(), self.llm_config.clone()).await?,
+                    ))))
+                }
+                LineageScreenMessage::IterateZettel(zettel) => {
+                    Ok(Some(AppCommand::AddZettel(vec![zettel])))
+                }
+                _ => {
+                    self.update(msg).await?;
+                    Ok(None)
+                }
+            }
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn draw(&mut self, f: &mut Frame) {
+        let layout = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(f.area());
+
+        let inner_layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(layout[1]);
+
+        let list_width = layout[0].width.saturating_sub(2);
+        let lineage_list_items: Vec<ListItem> = self
+            .lineage
+            .items
+            .clone()
+            .iter()
+            .enumerate()
+            .map(|(i, zettel)| {
+                let mut item = zettel_list_item(zettel, list_width);
+                if let Some(idx) = self.lineage.curr_idx()
+                    && i == idx
+                {
+                    item = item.style(
+                        Style::default()
+                            .bg(Color::DarkGray)
+                            .add_modifier(Modifier::BOLD),
+                    );
+                }
+                item
+            })
+            .collect();
+
+        let lineage_list = List::new(lineage_list_items).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Thick)
+                .title("Lineage"),
+        );
+
+        let preview = if let Some(zettel) = self.lineage.get_selected_item() {
+            Paragraph::new(zettel.content.to_string())
+        } else {
+            Paragraph::default()
+        }
+        .wrap(Wrap { trim: true })
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Thick)
+                .title("Preview"),
+        );
+
+        let summary_text = match (&self.summary, &self.llm_config.chat_model) {
+            (Some(summary), _) => summary.to_string(),
+            (None, Some(_)) => "Press s to summarise how this thought evolved.".to_string(),
+            (None, None) => "Set a chat_model in your config to summarise lineages.".to_string(),
+        };
+
+        let summary = Paragraph::new(summary_text)
+            .wrap(Wrap { trim: true })
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Thick)
+                    .title("Evolution"),
+            );
+
+        f.render_stateful_widget(lineage_list, layout[0], &mut self.lineage.list_state);
+        match &self.diff_panel {
+            Some(panel) => panel.render(f, inner_layout[0]),
+            None => f.render_widget(preview, inner_layout[0]),
+        }
+        f.render_widget(summary, inner_layout[1]);
+    }
+}
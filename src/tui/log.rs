@@ -0,0 +1,126 @@
+use ratatui::{
+    Frame,
+    crossterm::event::{KeyCode, KeyEvent},
+    style::{Color, Style},
+    text::Line,
+    widgets::{Block, BorderType, Borders, Paragraph},
+};
+
+use crate::{
+    db::Db,
+    error::TshError,
+    logging::read_recent_app_log_lines,
+    tui::{
+        app::{ActiveScreenType, AppCommand, LlmConfig, Screen},
+        main_menu::MainMenuScreen,
+    },
+};
+
+/// Number of log lines loaded into the viewer.
+const LOG_VIEWER_LINES: usize = 500;
+
+pub struct LogScreen {
+    lines: Vec<String>,
+    scroll_from_bottom: usize,
+    db: Db,
+    llm_config: LlmConfig,
+}
+
+enum LogScreenMessage {
+    BackToMainMenu,
+    ScrollUp,
+    ScrollDown,
+    Reload,
+}
+
+impl LogScreen {
+    pub fn new(db: Db, llm_config: LlmConfig) -> Result<Self, TshError> {
+        Ok(Self {
+            lines: read_recent_app_log_lines(LOG_VIEWER_LINES)?,
+            scroll_from_bottom: 0,
+            db,
+            llm_config,
+        })
+    }
+
+    fn handle_key_event_internal(&mut self, key: KeyEvent) -> Option<LogScreenMessage> {
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => Some(LogScreenMessage::BackToMainMenu),
+            KeyCode::Char('r') => Some(LogScreenMessage::Reload),
+            KeyCode::Up => Some(LogScreenMessage::ScrollUp),
+            KeyCode::Down => Some(LogScreenMessage::ScrollDown),
+            _ => None,
+        }
+    }
+
+    fn update(&mut self, message: LogScreenMessage) -> Result<(), TshError> {
+        match message {
+            LogScreenMessage::ScrollUp => {
+                if self.scroll_from_bottom + 1 < self.lines.len() {
+                    self.scroll_from_bottom += 1;
+                }
+            }
+            LogScreenMessage::ScrollDown => {
+                self.scroll_from_bottom = self.scroll_from_bottom.saturating_sub(1);
+            }
+            LogScreenMessage::Reload => {
+                self.lines = read_recent_app_log_lines(LOG_VIEWER_LINES)?;
+                self.scroll_from_bottom = 0;
+            }
+            _ => {}
+        };
+        Ok(())
+    }
+}
+
+impl Screen for LogScreen {
+    async fn handle_key_event(&mut self, key: KeyEvent) -> Result<Option<AppCommand>, TshError> {
+        if let Some(msg) = self.handle_key_event_internal(key) {
+            match msg {
+                LogScreenMessage::BackToMainMenu => {
+                    Ok(Some(AppCommand::SwitchScreen(ActiveScreenType::Main(
+                        MainMenuScreen::new(self.db.clone(), self.llm_config.clone()),
+                    ))))
+                }
+                _ => {
+                    self.update(msg)?;
+                    Ok(None)
+                }
+            }
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn draw(&mut self, f: &mut Frame) {
+        let area = f.area();
+        let visible_lines = area.height.saturating_sub(2) as usize;
+
+        // Follow the end of the log unless the user scrolled up.
+        let end = self.lines.len().saturating_sub(self.scroll_from_bottom);
+        let start = end.saturating_sub(visible_lines);
+
+        let lines: Vec<Line> = self.lines[start..end]
+            .iter()
+            .map(|line| {
+                let style = if line.contains(" ERROR ") {
+                    Style::default().fg(Color::LightRed)
+                } else if line.contains(" WARN ") {
+                    Style::default().fg(Color::LightYellow)
+                } else {
+                    Style::default()
+                };
+                Line::styled(line.to_string(), style)
+            })
+            .collect();
+
+        let log = Paragraph::new(lines).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .title("Log"),
+        );
+
+        f.render_widget(log, area);
+    }
+}
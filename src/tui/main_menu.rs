@@ -1,5 +1,3 @@
-use std::error::Error;
-
 use ratatui::{
     Frame,
     crossterm::event::{KeyCode, KeyEvent},
@@ -9,15 +7,25 @@ use ratatui::{
 };
 use strum::{Display, EnumIter, FromRepr, IntoEnumIterator};
 
-use crate::tui::{
-    app::{ActiveScreenType, AppCommand, LlmConfig, Screen},
-    iterate::IterateZettelScreen,
-    recent::RecentScreen,
+use crate::{
+    datetime::today,
+    db::Db,
+    error::TshError,
+    tui::{
+        app::{ActiveScreenType, AppCommand, LlmConfig, Screen},
+        ask::AskScreen,
+        calendar::CalendarScreen,
+        clip::ClipScreen,
+        iterate::IterateZettelScreen,
+        log::LogScreen,
+        recent::RecentScreen,
+        review::ReviewScreen,
+    },
 };
 
 pub struct MainMenuScreen {
     selected_action: Action,
-    db_path: String,
+    db: Db,
     llm_config: LlmConfig,
 }
 
@@ -26,10 +34,24 @@ enum Action {
     #[default]
     #[strum(to_string = "Add")]
     AddZettel,
+    #[strum(to_string = "Today")]
+    Today,
+    #[strum(to_string = "Calendar")]
+    Calendar,
     #[strum(to_string = "Iterate")]
     IterateZettel,
     #[strum(to_string = "Recent")]
     RecentZettel,
+    #[strum(to_string = "Review")]
+    Review,
+    #[strum(to_string = "Ask")]
+    Ask,
+    #[strum(to_string = "Clip")]
+    Clip,
+    #[strum(to_string = "Log")]
+    Log,
+    #[strum(to_string = "Switch vault")]
+    Vault,
 }
 
 impl Action {
@@ -54,10 +76,10 @@ enum MainMenuMessage {
 }
 
 impl MainMenuScreen {
-    pub fn new(db_path: String, llm_config: LlmConfig) -> Self {
+    pub fn new(db: Db, llm_config: LlmConfig) -> Self {
         Self {
             selected_action: Action::AddZettel,
-            db_path,
+            db,
             llm_config,
         }
     }
@@ -72,7 +94,7 @@ impl MainMenuScreen {
         }
     }
 
-    async fn update(&mut self, msg: MainMenuMessage) -> Result<(), Box<dyn Error>> {
+    async fn update(&mut self, msg: MainMenuMessage) -> Result<(), TshError> {
         match msg {
             MainMenuMessage::MoveDown => {
                 self.selected_action = self.selected_action.next();
@@ -87,26 +109,41 @@ impl MainMenuScreen {
 }
 
 impl Screen for MainMenuScreen {
-    async fn handle_key_event(
-        &mut self,
-        key: KeyEvent,
-    ) -> Result<Option<AppCommand>, Box<dyn Error>> {
+    async fn handle_key_event(&mut self, key: KeyEvent) -> Result<Option<AppCommand>, TshError> {
         if let Some(msg) = self.handle_key_event_internal(key) {
             match msg {
                 MainMenuMessage::QuitApp => Ok(Some(AppCommand::Quit)),
                 MainMenuMessage::DoAction(action) => match action {
-                    Action::AddZettel => Ok(Some(AppCommand::AddZettel(vec![]))),
+                    Action::AddZettel => Ok(Some(AppCommand::ChooseTemplate)),
+                    Action::Today => Ok(Some(AppCommand::OpenJournal(today()))),
+                    Action::Calendar => {
+                        Ok(Some(AppCommand::SwitchScreen(ActiveScreenType::Calendar(
+                            CalendarScreen::new(self.db.clone(), self.llm_config.clone()).await?,
+                        ))))
+                    }
                     Action::IterateZettel => {
                         Ok(Some(AppCommand::SwitchScreen(ActiveScreenType::Iterate(
-                            IterateZettelScreen::new(self.db_path.clone(), self.llm_config.clone()),
+                            IterateZettelScreen::new(self.db.clone(), self.llm_config.clone()),
                         ))))
                     }
                     Action::RecentZettel => {
                         Ok(Some(AppCommand::SwitchScreen(ActiveScreenType::Recent(
-                            RecentScreen::new(self.db_path.clone(), self.llm_config.clone())
-                                .await?,
+                            RecentScreen::new(self.db.clone(), self.llm_config.clone()).await?,
                         ))))
                     }
+                    Action::Review => Ok(Some(AppCommand::SwitchScreen(ActiveScreenType::Review(
+                        ReviewScreen::new(self.db.clone(), self.llm_config.clone()).await?,
+                    )))),
+                    Action::Ask => Ok(Some(AppCommand::SwitchScreen(ActiveScreenType::Ask(
+                        AskScreen::new(self.db.clone(), self.llm_config.clone()),
+                    )))),
+                    Action::Clip => Ok(Some(AppCommand::SwitchScreen(ActiveScreenType::Clip(
+                        ClipScreen::new(self.db.clone(), self.llm_config.clone()),
+                    )))),
+                    Action::Log => Ok(Some(AppCommand::SwitchScreen(ActiveScreenType::Log(
+                        LogScreen::new(self.db.clone(), self.llm_config.clone())?,
+                    )))),
+                    Action::Vault => Ok(Some(AppCommand::ChooseVault)),
                 },
                 _ => {
                     self.update(msg).await?;
@@ -119,7 +156,9 @@ impl Screen for MainMenuScreen {
     }
 
     fn draw(&mut self, f: &mut Frame) {
-        let layout = Layout::new(Direction::Vertical, [Constraint::Length(4)]).split(f.area());
+        let n_actions = Action::iter().count() as u16;
+        let layout =
+            Layout::new(Direction::Vertical, [Constraint::Length(n_actions)]).split(f.area());
 
         let menu_items: Vec<ListItem> = Action::iter()
             .map(|action| {
@@ -1,39 +1,48 @@
-use ratatui::{
-    Frame,
-    crossterm::event::{KeyCode, KeyEvent},
-    layout::{Constraint, Direction, Flex, Layout, Rect},
-    style::{Color, Modifier, Style},
-    text::Line,
-    widgets::{Block, BorderType, Borders, Clear, List, ListItem, Paragraph, Wrap},
-};
-use std::error::Error;
-
 use crate::{
     api::{
         add_tag_to_zettel, delete_tag_from_zettel, find_tags, get_n_recent_zettels, get_tags,
-        get_zettels_by_tags,
+        get_zettels_by_tags, suggest_tags,
     },
-    model::{Zettel, ZettelTag},
+    db::Db,
+    error::TshError,
+    model::{TagSuggestion, Zettel, ZettelTag},
     tui::{
         app::{ActiveScreenType, AppCommand, LlmConfig, Screen},
-        common::{InputMode, ListWithState},
+        common::{InputMode, ListWithState, zettel_list_item},
+        diff::{DiffPanel, SCROLL_PAGE_LINES},
+        history::HistoryScreen,
+        lineage::LineageScreen,
         main_menu::MainMenuScreen,
+        sources::SourcesScreen,
     },
 };
+use ratatui::{
+    Frame,
+    crossterm::event::{KeyCode, KeyEvent},
+    layout::{Constraint, Direction, Flex, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Block, BorderType, Borders, Clear, List, ListItem, Paragraph, Wrap},
+};
 
+#[allow(clippy::enum_variant_names)]
 enum View {
     ListView,
     TagView,
     TagSearchView,
+    TagSuggestionView,
+    DiffView,
 }
 
 pub struct RecentScreen {
-    db_path: String,
+    db: Db,
     llm_config: LlmConfig,
     view: View,
     zettels: ListWithState<Zettel>,
     tag_view_state: Option<TagViewState>,
     tag_search_view_state: Option<TagSearchViewState>,
+    tag_suggestion_view_state: Option<TagSuggestionViewState>,
+    diff_panel: Option<DiffPanel>,
 }
 
 struct TagViewState {
@@ -43,6 +52,10 @@ struct TagViewState {
     input: String,
 }
 
+struct TagSuggestionViewState {
+    suggestions: ListWithState<TagSuggestion>,
+}
+
 struct TagSearchViewState {
     tag_search_results: ListWithState<String>,
     selected_tags: Vec<String>,
@@ -64,6 +77,10 @@ enum RecentScreenMessage {
     ResultListMoveUp,
     ResultListMoveDown,
     IterateZettel(Zettel),
+    AmendZettel(Zettel),
+    ShowHistory(Zettel),
+    ShowSources(Zettel),
+    ShowLineage(Zettel),
     EnterTagSearchInsertMode,
     ExitTagSearchInsertMode,
     InsertTagSearchInputChar(char),
@@ -73,27 +90,63 @@ enum RecentScreenMessage {
     TagSearchResultListMoveDown,
     TagSearchResultAddToSelected,
     SubmitSelectedTagsForFiltering,
+    SuggestTags,
+    TagSuggestionListMoveUp,
+    TagSuggestionListMoveDown,
+    ToggleTagSuggestion,
+    ConfirmTagSuggestions,
+    DiscardTagSuggestions,
+    ShowDiff,
+    DiffNextPage,
+    DiffPrevPage,
+    DiffScroll(i32),
 }
 
 impl RecentScreen {
-    pub async fn new(db_path: String, llm_config: LlmConfig) -> Result<Self, Box<dyn Error>> {
-        let recent_zettels = get_n_recent_zettels(&db_path, 100).await?;
+    pub async fn new(db: Db, llm_config: LlmConfig) -> Result<Self, TshError> {
+        let recent_zettels = get_n_recent_zettels(&db, 100).await?;
         Ok(Self {
-            db_path,
+            db,
             llm_config,
             view: View::ListView,
             zettels: ListWithState::new(recent_zettels),
             tag_view_state: None,
             tag_search_view_state: None,
+            tag_suggestion_view_state: None,
+            diff_panel: None,
         })
     }
 
+    /// Shows the new content of a zettel that was amended from this screen.
+    pub fn replace_zettel(&mut self, zettel: Zettel) {
+        if let Some(item) = self.zettels.items.iter_mut().find(|z| z.id == zettel.id) {
+            *item = zettel;
+        }
+    }
+
     fn handle_key_event_internal(&mut self, key: KeyEvent) -> Option<RecentScreenMessage> {
         match self.view {
             View::ListView => match key.code {
                 KeyCode::Char('q') => Some(RecentScreenMessage::BackToMainMenu),
                 KeyCode::Char('t') => Some(RecentScreenMessage::SwitchView(View::TagView)),
                 KeyCode::Char('s') => Some(RecentScreenMessage::SwitchView(View::TagSearchView)),
+                KeyCode::Char('d') => Some(RecentScreenMessage::ShowDiff),
+                KeyCode::Char('l') => self
+                    .zettels
+                    .get_selected_item()
+                    .map(RecentScreenMessage::ShowLineage),
+                KeyCode::Char('e') => self
+                    .zettels
+                    .get_selected_item()
+                    .map(RecentScreenMessage::AmendZettel),
+                KeyCode::Char('h') => self
+                    .zettels
+                    .get_selected_item()
+                    .map(RecentScreenMessage::ShowHistory),
+                KeyCode::Char('b') => self
+                    .zettels
+                    .get_selected_item()
+                    .map(RecentScreenMessage::ShowSources),
                 KeyCode::Up => Some(RecentScreenMessage::ResultListMoveUp),
                 KeyCode::Down => Some(RecentScreenMessage::ResultListMoveDown),
                 KeyCode::Enter => {
@@ -114,6 +167,9 @@ impl RecentScreen {
                         KeyCode::Up => Some(RecentScreenMessage::TagListMoveUp),
                         KeyCode::Down => Some(RecentScreenMessage::TagListMoveDown),
                         KeyCode::Char('d') => Some(RecentScreenMessage::DeleteTag),
+                        KeyCode::Char('a') if self.llm_config.chat_model.is_some() => {
+                            Some(RecentScreenMessage::SuggestTags)
+                        }
                         _ => None,
                     },
                     InputMode::Insert => match key.code {
@@ -147,22 +203,46 @@ impl RecentScreen {
                 },
                 None => None,
             },
+            View::TagSuggestionView => match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => {
+                    Some(RecentScreenMessage::DiscardTagSuggestions)
+                }
+                KeyCode::Up => Some(RecentScreenMessage::TagSuggestionListMoveUp),
+                KeyCode::Down => Some(RecentScreenMessage::TagSuggestionListMoveDown),
+                KeyCode::Char(' ') => Some(RecentScreenMessage::ToggleTagSuggestion),
+                KeyCode::Enter => Some(RecentScreenMessage::ConfirmTagSuggestions),
+                _ => None,
+            },
+            View::DiffView => match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => {
+                    Some(RecentScreenMessage::SwitchView(View::ListView))
+                }
+                KeyCode::Right | KeyCode::Tab => Some(RecentScreenMessage::DiffNextPage),
+                KeyCode::Left | KeyCode::BackTab => Some(RecentScreenMessage::DiffPrevPage),
+                KeyCode::Char('j') | KeyCode::Down => Some(RecentScreenMessage::DiffScroll(1)),
+                KeyCode::Char('k') | KeyCode::Up => Some(RecentScreenMessage::DiffScroll(-1)),
+                KeyCode::PageDown => Some(RecentScreenMessage::DiffScroll(SCROLL_PAGE_LINES)),
+                KeyCode::PageUp => Some(RecentScreenMessage::DiffScroll(-SCROLL_PAGE_LINES)),
+                _ => None,
+            },
         }
     }
 
-    async fn update(&mut self, message: RecentScreenMessage) -> Result<(), Box<dyn Error>> {
+    async fn update(&mut self, message: RecentScreenMessage) -> Result<(), TshError> {
         match message {
             RecentScreenMessage::SwitchView(view) => match view {
                 View::ListView => {
                     self.tag_view_state = None;
                     self.tag_search_view_state = None;
+                    self.tag_suggestion_view_state = None;
+                    self.diff_panel = None;
                     self.view = View::ListView;
                 }
                 View::TagView => {
                     if let Some(idx) = self.zettels.curr_idx() {
                         self.view = View::TagView;
                         let zettel_id = self.zettels.items[idx].id;
-                        let tags = get_tags(&self.db_path, zettel_id).await?;
+                        let tags = get_tags(&self.db, zettel_id).await?;
                         self.tag_view_state = Some(TagViewState {
                             zettel_id,
                             tags: ListWithState::new(tags),
@@ -181,14 +261,15 @@ impl RecentScreen {
                     });
                     self.view = View::TagSearchView;
                 }
+                View::TagSuggestionView | View::DiffView => {}
             },
             RecentScreenMessage::EnterTagInputInsertMode => {
-                if let View::TagView = self.view {
-                    if let Some(state) = &mut self.tag_view_state {
-                        state.tags.unselect();
-                        state.input.clear();
-                        state.input_mode = InputMode::Insert;
-                    }
+                if let View::TagView = self.view
+                    && let Some(state) = &mut self.tag_view_state
+                {
+                    state.tags.unselect();
+                    state.input.clear();
+                    state.input_mode = InputMode::Insert;
                 }
             }
             RecentScreenMessage::ExitTagInputInsertMode => {
@@ -210,25 +291,20 @@ impl RecentScreen {
             }
             RecentScreenMessage::SubmitTag => {
                 if let Some(state) = &mut self.tag_view_state {
-                    add_tag_to_zettel(&self.db_path, state.zettel_id, state.input.clone()).await?;
-                    let upd_tags = get_tags(&self.db_path, state.zettel_id).await?;
+                    add_tag_to_zettel(&self.db, state.zettel_id, state.input.clone()).await?;
+                    let upd_tags = get_tags(&self.db, state.zettel_id).await?;
                     state.tags = ListWithState::new(upd_tags);
                     state.input = String::new();
                     state.input_mode = InputMode::Normal;
                 }
             }
             RecentScreenMessage::DeleteTag => {
-                if let Some(state) = &mut self.tag_view_state {
-                    if let Some(zettel_tag) = state.tags.get_selected_item() {
-                        delete_tag_from_zettel(
-                            &self.db_path,
-                            zettel_tag.zettel_id,
-                            &zettel_tag.tag,
-                        )
-                        .await?;
-                        let upd_tags = get_tags(&self.db_path, zettel_tag.zettel_id).await?;
-                        state.tags = ListWithState::new(upd_tags);
-                    }
+                if let Some(state) = &mut self.tag_view_state
+                    && let Some(zettel_tag) = state.tags.get_selected_item()
+                {
+                    delete_tag_from_zettel(&self.db, zettel_tag.zettel_id, &zettel_tag.tag).await?;
+                    let upd_tags = get_tags(&self.db, zettel_tag.zettel_id).await?;
+                    state.tags = ListWithState::new(upd_tags);
                 }
             }
             RecentScreenMessage::TagListMoveUp => {
@@ -275,7 +351,7 @@ impl RecentScreen {
                 if let Some(state) = &mut self.tag_search_view_state {
                     state.input_mode = InputMode::Normal;
                     if !state.input.is_empty() {
-                        let search_results = find_tags(&self.db_path, &state.input).await?;
+                        let search_results = find_tags(&self.db, &state.input).await?;
                         state.tag_search_results = ListWithState::new(search_results);
                     }
                     state.input.clear();
@@ -292,24 +368,95 @@ impl RecentScreen {
                 }
             }
             RecentScreenMessage::TagSearchResultAddToSelected => {
-                if let Some(state) = &mut self.tag_search_view_state {
-                    if let Some(selected_tag) = state.tag_search_results.get_selected_item() {
-                        if !state.selected_tags.contains(&selected_tag) {
-                            state.selected_tags.push(selected_tag);
-                        }
-                    }
+                if let Some(state) = &mut self.tag_search_view_state
+                    && let Some(selected_tag) = state.tag_search_results.get_selected_item()
+                    && !state.selected_tags.contains(&selected_tag)
+                {
+                    state.selected_tags.push(selected_tag);
                 }
             }
             RecentScreenMessage::SubmitSelectedTagsForFiltering => {
                 if let Some(state) = &mut self.tag_search_view_state {
                     let zettels_by_tag =
-                        get_zettels_by_tags(&self.db_path, state.selected_tags.clone()).await?;
+                        get_zettels_by_tags(&self.db, state.selected_tags.clone()).await?;
                     self.zettels = ListWithState::new(zettels_by_tag);
                     self.tag_view_state = None;
                     self.tag_search_view_state = None;
                     self.view = View::ListView;
                 }
             }
+            RecentScreenMessage::SuggestTags => {
+                if let Some(zettel) = self.zettels.get_selected_item() {
+                    let suggestions = suggest_tags(&self.db, &self.llm_config, &zettel).await?;
+                    self.tag_suggestion_view_state = Some(TagSuggestionViewState {
+                        suggestions: ListWithState::new(suggestions),
+                    });
+                    self.view = View::TagSuggestionView;
+                }
+            }
+            RecentScreenMessage::TagSuggestionListMoveUp => {
+                if let Some(state) = &mut self.tag_suggestion_view_state {
+                    state.suggestions.select_prev();
+                }
+            }
+            RecentScreenMessage::TagSuggestionListMoveDown => {
+                if let Some(state) = &mut self.tag_suggestion_view_state {
+                    state.suggestions.select_next();
+                }
+            }
+            RecentScreenMessage::ToggleTagSuggestion => {
+                if let Some(state) = &mut self.tag_suggestion_view_state
+                    && let Some(idx) = state.suggestions.curr_idx()
+                {
+                    let suggestion = &mut state.suggestions.items[idx];
+                    suggestion.accepted = !suggestion.accepted;
+                }
+            }
+            RecentScreenMessage::ConfirmTagSuggestions => {
+                if let Some(suggestion_state) = &self.tag_suggestion_view_state
+                    && let Some(tag_state) = &mut self.tag_view_state
+                {
+                    for suggestion in suggestion_state.suggestions.items.iter() {
+                        if suggestion.accepted {
+                            add_tag_to_zettel(
+                                &self.db,
+                                tag_state.zettel_id,
+                                suggestion.tag.clone(),
+                            )
+                            .await?;
+                        }
+                    }
+                    let upd_tags = get_tags(&self.db, tag_state.zettel_id).await?;
+                    tag_state.tags = ListWithState::new(upd_tags);
+                }
+                self.tag_suggestion_view_state = None;
+                self.view = View::TagView;
+            }
+            RecentScreenMessage::DiscardTagSuggestions => {
+                self.tag_suggestion_view_state = None;
+                self.view = View::TagView;
+            }
+            RecentScreenMessage::ShowDiff => {
+                if let Some(zettel) = self.zettels.get_selected_item() {
+                    self.diff_panel = Some(DiffPanel::new(&self.db, zettel).await?);
+                    self.view = View::DiffView;
+                }
+            }
+            RecentScreenMessage::DiffNextPage => {
+                if let Some(panel) = &mut self.diff_panel {
+                    panel.next_page();
+                }
+            }
+            RecentScreenMessage::DiffPrevPage => {
+                if let Some(panel) = &mut self.diff_panel {
+                    panel.prev_page();
+                }
+            }
+            RecentScreenMessage::DiffScroll(lines) => {
+                if let Some(panel) = &mut self.diff_panel {
+                    panel.scroll_by(lines);
+                }
+            }
             _ => {}
         };
         Ok(())
@@ -317,20 +464,38 @@ impl RecentScreen {
 }
 
 impl Screen for RecentScreen {
-    async fn handle_key_event(
-        &mut self,
-        key: KeyEvent,
-    ) -> Result<Option<AppCommand>, Box<dyn Error>> {
+    async fn handle_key_event(&mut self, key: KeyEvent) -> Result<Option<AppCommand>, TshError> {
         if let Some(msg) = self.handle_key_event_internal(key) {
             match msg {
                 RecentScreenMessage::BackToMainMenu => {
                     Ok(Some(AppCommand::SwitchScreen(ActiveScreenType::Main(
-                        MainMenuScreen::new(self.db_path.clone(), self.llm_config.clone()),
+                        MainMenuScreen::new(self.db.clone(), self.llm_config.clone()),
                     ))))
                 }
                 RecentScreenMessage::IterateZettel(zettel) => {
                     Ok(Some(AppCommand::AddZettel(vec![zettel])))
                 }
+                RecentScreenMessage::AmendZettel(zettel) => {
+                    Ok(Some(AppCommand::AmendZettel(zettel)))
+                }
+                RecentScreenMessage::ShowHistory(zettel) => {
+                    Ok(Some(AppCommand::SwitchScreen(ActiveScreenType::History(
+                        HistoryScreen::new(self.db.clone(), self.llm_config.clone(), zettel)
+                            .await?,
+                    ))))
+                }
+                RecentScreenMessage::ShowSources(zettel) => {
+                    Ok(Some(AppCommand::SwitchScreen(ActiveScreenType::Sources(
+                        SourcesScreen::new(self.db.clone(), self.llm_config.clone(), zettel)
+                            .await?,
+                    ))))
+                }
+                RecentScreenMessage::ShowLineage(zettel) => {
+                    Ok(Some(AppCommand::SwitchScreen(ActiveScreenType::Lineage(
+                        LineageScreen::new(self.db.clone(), self.llm_config.clone(), zettel)
+                            .await?,
+                    ))))
+                }
                 _ => {
                     self.update(msg).await?;
                     Ok(None)
@@ -347,6 +512,7 @@ impl Screen for RecentScreen {
             .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
             .split(f.area());
 
+        let list_width = layout[0].width.saturating_sub(2);
         let zettels_list_items: Vec<ListItem> = self
             .zettels
             .items
@@ -354,15 +520,15 @@ impl Screen for RecentScreen {
             .iter()
             .enumerate()
             .map(|(i, zettel)| {
-                let mut item = ListItem::from(zettel);
-                if let Some(idx) = self.zettels.curr_idx() {
-                    if i == idx {
-                        item = item.style(
-                            Style::default()
-                                .bg(Color::DarkGray)
-                                .add_modifier(Modifier::BOLD),
-                        );
-                    }
+                let mut item = zettel_list_item(zettel, list_width);
+                if let Some(idx) = self.zettels.curr_idx()
+                    && i == idx
+                {
+                    item = item.style(
+                        Style::default()
+                            .bg(Color::DarkGray)
+                            .add_modifier(Modifier::BOLD),
+                    );
                 }
                 item
             })
@@ -402,6 +568,19 @@ impl Screen for RecentScreen {
                     render_tag_search_view(f, state);
                 }
             }
+            View::TagSuggestionView => {
+                if let Some(state) = &mut self.tag_view_state {
+                    render_tag_view(f, state);
+                }
+                if let Some(state) = &mut self.tag_suggestion_view_state {
+                    render_tag_suggestion_view(f, state);
+                }
+            }
+            View::DiffView => {
+                if let Some(panel) = &self.diff_panel {
+                    panel.render(f, popup_area(f.area(), 80, 80));
+                }
+            }
             _ => {}
         }
     }
@@ -438,14 +617,14 @@ fn render_tag_view(f: &mut Frame, state: &mut TagViewState) {
         .enumerate()
         .map(|(i, zettel_tag)| {
             let mut item = ListItem::from(zettel_tag);
-            if let Some(idx) = state.tags.curr_idx() {
-                if i == idx {
-                    item = item.style(
-                        Style::default()
-                            .fg(Color::LightGreen)
-                            .add_modifier(Modifier::BOLD),
-                    );
-                }
+            if let Some(idx) = state.tags.curr_idx()
+                && i == idx
+            {
+                item = item.style(
+                    Style::default()
+                        .fg(Color::LightGreen)
+                        .add_modifier(Modifier::BOLD),
+                );
             }
             item
         })
@@ -496,14 +675,14 @@ fn render_tag_search_view(f: &mut Frame, state: &mut TagSearchViewState) {
         .map(|(i, tag)| {
             let line = Line::styled(format!("#{}", tag), Style::default());
             let mut item = ListItem::new(line);
-            if let Some(idx) = state.tag_search_results.curr_idx() {
-                if i == idx {
-                    item = item.style(
-                        Style::default()
-                            .fg(Color::LightGreen)
-                            .add_modifier(Modifier::BOLD),
-                    );
-                }
+            if let Some(idx) = state.tag_search_results.curr_idx()
+                && i == idx
+            {
+                item = item.style(
+                    Style::default()
+                        .fg(Color::LightGreen)
+                        .add_modifier(Modifier::BOLD),
+                );
             }
             item
         })
@@ -536,6 +715,47 @@ fn render_tag_search_view(f: &mut Frame, state: &mut TagSearchViewState) {
     f.render_widget(selected_tags_list, inner_layout[1]);
 }
 
+fn render_tag_suggestion_view(f: &mut Frame, state: &mut TagSuggestionViewState) {
+    let area = popup_area(f.area(), 50, 30);
+
+    let block = Block::bordered()
+        .border_type(BorderType::Double)
+        .border_style(Style::default().add_modifier(Modifier::BOLD))
+        .title("Suggested Tags");
+
+    let suggestion_list_items: Vec<ListItem> = state
+        .suggestions
+        .items
+        .clone()
+        .iter()
+        .enumerate()
+        .map(|(i, suggestion)| {
+            let checkbox = if suggestion.accepted { "[x]" } else { "[ ]" };
+            let marker = if suggestion.is_new { " (new)" } else { "" };
+            let line = Line::styled(
+                format!("{} #{}{}", checkbox, suggestion.tag, marker),
+                Style::default(),
+            );
+            let mut item = ListItem::new(line);
+            if let Some(idx) = state.suggestions.curr_idx()
+                && i == idx
+            {
+                item = item.style(
+                    Style::default()
+                        .fg(Color::LightGreen)
+                        .add_modifier(Modifier::BOLD),
+                );
+            }
+            item
+        })
+        .collect();
+
+    let suggestion_list = List::new(suggestion_list_items).block(block);
+
+    f.render_widget(Clear, area);
+    f.render_stateful_widget(suggestion_list, area, &mut state.suggestions.list_state);
+}
+
 // https://ratatui.rs/examples/apps/popup/
 fn popup_area(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
     let vertical = Layout::vertical([Constraint::Percentage(percent_y)]).flex(Flex::Center);
@@ -0,0 +1,223 @@
+use ratatui::{
+    Frame,
+    crossterm::event::{KeyCode, KeyEvent},
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Block, BorderType, Borders, List, ListItem, Paragraph, Wrap},
+};
+use strum::IntoEnumIterator;
+
+use crate::{
+    api::{archive_zettel, get_review_queue, review_zettel},
+    db::Db,
+    error::TshError,
+    model::ReviewItem,
+    review::ReviewRating,
+    tui::{
+        app::{ActiveScreenType, AppCommand, LlmConfig, Screen},
+        common::{ListWithState, zettel_list_item},
+        main_menu::MainMenuScreen,
+    },
+};
+
+/// Resurfaces old zettels on a spaced-repetition schedule. Each one is rated, iterated on,
+/// or archived, after which it leaves the queue until it is due again.
+pub struct ReviewScreen {
+    items: ListWithState<ReviewItem>,
+    /// Only review zettels that nothing was derived from yet.
+    leaves_only: bool,
+    db: Db,
+    llm_config: LlmConfig,
+}
+
+enum ReviewScreenMessage {
+    BackToMainMenu,
+    SelectNext,
+    SelectPrevious,
+    Rate(ReviewRating),
+    Iterate,
+    Archive,
+    ToggleLeavesOnly,
+}
+
+impl ReviewScreen {
+    pub async fn new(db: Db, llm_config: LlmConfig) -> Result<Self, TshError> {
+        let items = get_review_queue(&db, false).await?;
+        Ok(Self {
+            items: ListWithState::new(items),
+            leaves_only: false,
+            db,
+            llm_config,
+        })
+    }
+
+    fn handle_key_event_internal(&self, key: KeyEvent) -> Option<ReviewScreenMessage> {
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => Some(ReviewScreenMessage::BackToMainMenu),
+            KeyCode::Down => Some(ReviewScreenMessage::SelectNext),
+            KeyCode::Up => Some(ReviewScreenMessage::SelectPrevious),
+            KeyCode::Char(c @ '1'..='4') => ReviewRating::iter()
+                .nth(c as usize - '1' as usize)
+                .map(ReviewScreenMessage::Rate),
+            KeyCode::Enter => Some(ReviewScreenMessage::Iterate),
+            KeyCode::Char('a') => Some(ReviewScreenMessage::Archive),
+            KeyCode::Char('l') => Some(ReviewScreenMessage::ToggleLeavesOnly),
+            _ => None,
+        }
+    }
+
+    async fn update(&mut self, message: ReviewScreenMessage) -> Result<(), TshError> {
+        match message {
+            ReviewScreenMessage::SelectNext => self.items.select_next(),
+            ReviewScreenMessage::SelectPrevious => self.items.select_prev(),
+            ReviewScreenMessage::Rate(rating) => {
+                if let Some(item) = self.items.get_selected_item() {
+                    review_zettel(&self.db, item.zettel.id, rating).await?;
+                    self.remove_selected();
+                }
+            }
+            ReviewScreenMessage::Archive => {
+                if let Some(item) = self.items.get_selected_item() {
+                    archive_zettel(&self.db, item.zettel.id).await?;
+                    self.remove_selected();
+                }
+            }
+            ReviewScreenMessage::ToggleLeavesOnly => {
+                self.leaves_only = !self.leaves_only;
+                let items = get_review_queue(&self.db, self.leaves_only).await?;
+                self.items = ListWithState::new(items);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Drops the reviewed zettel and selects the one that took its place.
+    fn remove_selected(&mut self) {
+        let Some(idx) = self.items.curr_idx() else {
+            return;
+        };
+        self.items.items.remove(idx);
+        if self.items.items.is_empty() {
+            self.items.unselect();
+        } else if idx >= self.items.items.len() {
+            self.items
+                .list_state
+                .select(Some(self.items.items.len() - 1));
+        }
+    }
+}
+
+impl Screen for ReviewScreen {
+    async fn handle_key_event(&mut self, key: KeyEvent) -> Result<Option<AppCommand>, TshError> {
+        if let Some(msg) = self.handle_key_event_internal(key) {
+            match msg {
+                ReviewScreenMessage::BackToMainMenu => {
+                    Ok(Some(AppCommand::SwitchScreen(ActiveScreenType::Main(
+                        MainMenuScreen::new(self.db.clone(), self.llm_config.clone()),
+                    ))))
+                }
+                // Building on a zettel is the best review it can get, so it is recorded as a
+                // good one before the editor opens and the zettel leaves the queue.
+                ReviewScreenMessage::Iterate => match self.items.get_selected_item() {
+                    Some(item) => {
+                        review_zettel(&self.db, item.zettel.id, ReviewRating::Good).await?;
+                        self.remove_selected();
+                        Ok(Some(AppCommand::AddZettel(vec![item.zettel])))
+                    }
+                    None => Ok(None),
+                },
+                _ => {
+                    self.update(msg).await?;
+                    Ok(None)
+                }
+            }
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn draw(&mut self, f: &mut Frame) {
+        let layout = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(f.area());
+
+        let list_width = layout[0].width.saturating_sub(2);
+        let selected_idx = self.items.curr_idx();
+        let items: Vec<ListItem> = self
+            .items
+            .items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| {
+                let mut list_item = zettel_list_item(&item.zettel, list_width);
+                if selected_idx == Some(i) {
+                    list_item = list_item.style(
+                        Style::default()
+                            .bg(Color::DarkGray)
+                            .add_modifier(Modifier::BOLD),
+                    );
+                }
+                list_item
+            })
+            .collect();
+
+        let title = if self.leaves_only {
+            "Review (leaves only)"
+        } else {
+            "Review"
+        };
+        let list = List::new(items).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Thick)
+                .title(title),
+        );
+
+        let preview_layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(4)])
+            .split(layout[1]);
+
+        let preview = match self.items.get_selected_item() {
+            Some(item) => Paragraph::new(item.zettel.content),
+            None => Paragraph::new("Nothing to review today."),
+        }
+        .wrap(Wrap { trim: true })
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Thick)
+                .title("Preview"),
+        );
+
+        let status = match self.items.get_selected_item() {
+            Some(ReviewItem {
+                n_descendants,
+                schedule: Some(schedule),
+                ..
+            }) => format!(
+                "{} descendants, reviewed {} times in a row, last interval {} days",
+                n_descendants, schedule.repetitions, schedule.interval_days
+            ),
+            Some(item) => format!("{} descendants, never reviewed", item.n_descendants),
+            None => String::new(),
+        };
+        let hints = "1 again  2 hard  3 good  4 easy  enter iterate  a archive  l leaves only";
+        let info = Paragraph::new(vec![
+            Line::styled(status, Style::default().fg(Color::LightBlue)),
+            Line::styled(hints, Style::default().add_modifier(Modifier::ITALIC)),
+        ])
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Thick),
+        );
+
+        f.render_stateful_widget(list, layout[0], &mut self.items.list_state);
+        f.render_widget(preview, preview_layout[0]);
+        f.render_widget(info, preview_layout[1]);
+    }
+}
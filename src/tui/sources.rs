@@ -0,0 +1,263 @@
+use ratatui::{
+    Frame,
+    crossterm::event::{KeyCode, KeyEvent},
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Block, BorderType, Borders, List, ListItem, Paragraph, Wrap},
+};
+
+use crate::{
+    api::{add_source_by_doi, attach_source, detach_source, get_sources, get_sources_for_zettel},
+    db::Db,
+    doi::HttpDoiResolver,
+    error::TshError,
+    model::{Source, Zettel},
+    tui::{
+        app::{ActiveScreenType, AppCommand, LlmConfig, Screen},
+        common::{InputMode, ListWithState},
+        recent::RecentScreen,
+    },
+};
+
+/// Lists all sources and attaches them to or detaches them from one zettel.
+/// New sources are added by DOI.
+pub struct SourcesScreen {
+    zettel: Zettel,
+    sources: ListWithState<Source>,
+    attached: Vec<i64>,
+    input_mode: InputMode,
+    input: String,
+    db: Db,
+    llm_config: LlmConfig,
+}
+
+enum SourcesScreenMessage {
+    BackToRecent,
+    SourceListMoveUp,
+    SourceListMoveDown,
+    ToggleAttached,
+    EnterDoiInsertMode,
+    ExitDoiInsertMode,
+    InsertDoiChar(char),
+    DeleteDoiChar,
+    SubmitDoi,
+}
+
+impl SourcesScreen {
+    pub async fn new(db: Db, llm_config: LlmConfig, zettel: Zettel) -> Result<Self, TshError> {
+        let sources = get_sources(&db).await?;
+        let attached = attached_ids(&db, zettel.id).await?;
+        Ok(Self {
+            zettel,
+            sources: ListWithState::new(sources),
+            attached,
+            input_mode: InputMode::Normal,
+            input: String::new(),
+            db,
+            llm_config,
+        })
+    }
+
+    fn handle_key_event_internal(&mut self, key: KeyEvent) -> Option<SourcesScreenMessage> {
+        match self.input_mode {
+            InputMode::Normal => match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => Some(SourcesScreenMessage::BackToRecent),
+                KeyCode::Up => Some(SourcesScreenMessage::SourceListMoveUp),
+                KeyCode::Down => Some(SourcesScreenMessage::SourceListMoveDown),
+                KeyCode::Char(' ') | KeyCode::Enter => Some(SourcesScreenMessage::ToggleAttached),
+                KeyCode::Char('i') => Some(SourcesScreenMessage::EnterDoiInsertMode),
+                _ => None,
+            },
+            InputMode::Insert => match key.code {
+                KeyCode::Esc => Some(SourcesScreenMessage::ExitDoiInsertMode),
+                KeyCode::Char(c) => Some(SourcesScreenMessage::InsertDoiChar(c)),
+                KeyCode::Backspace => Some(SourcesScreenMessage::DeleteDoiChar),
+                KeyCode::Enter => Some(SourcesScreenMessage::SubmitDoi),
+                _ => None,
+            },
+        }
+    }
+
+    async fn update(&mut self, message: SourcesScreenMessage) -> Result<(), TshError> {
+        match message {
+            SourcesScreenMessage::SourceListMoveUp => self.sources.select_prev(),
+            SourcesScreenMessage::SourceListMoveDown => self.sources.select_next(),
+            SourcesScreenMessage::ToggleAttached => {
+                if let Some(source) = self.sources.get_selected_item() {
+                    if self.attached.contains(&source.id) {
+                        detach_source(&self.db, self.zettel.id, source.id).await?;
+                    } else {
+                        attach_source(&self.db, self.zettel.id, source.id).await?;
+                    }
+                    self.attached = attached_ids(&self.db, self.zettel.id).await?;
+                }
+            }
+            SourcesScreenMessage::EnterDoiInsertMode => {
+                self.input.clear();
+                self.input_mode = InputMode::Insert;
+            }
+            SourcesScreenMessage::ExitDoiInsertMode => {
+                self.input.clear();
+                self.input_mode = InputMode::Normal;
+            }
+            SourcesScreenMessage::InsertDoiChar(c) => self.input.push(c),
+            SourcesScreenMessage::DeleteDoiChar => {
+                self.input.pop();
+            }
+            SourcesScreenMessage::SubmitDoi => {
+                self.input_mode = InputMode::Normal;
+                let doi = std::mem::take(&mut self.input);
+                if !doi.trim().is_empty() {
+                    // Looking up a DOI is for citing it, so attach it right away.
+                    let resolver = HttpDoiResolver::new(&self.llm_config.doi_resolver_url);
+                    let source = add_source_by_doi(&self.db, &resolver, &doi).await?;
+                    attach_source(&self.db, self.zettel.id, source.id).await?;
+                    self.attached = attached_ids(&self.db, self.zettel.id).await?;
+                    self.sources = ListWithState::new(get_sources(&self.db).await?);
+                    let idx = self.sources.items.iter().position(|s| s.id == source.id);
+                    self.sources.list_state.select(idx);
+                }
+            }
+            _ => {}
+        };
+        Ok(())
+    }
+}
+
+async fn attached_ids(db: &Db, zettel_id: i64) -> Result<Vec<i64>, TshError> {
+    Ok(get_sources_for_zettel(db, zettel_id)
+        .await?
+        .iter()
+        .map(|source| source.id)
+        .collect())
+}
+
+fn source_details(source: &Source) -> Vec<Line<'static>> {
+    let metadata = &source.metadata;
+    let fields = [
+        ("Type", Some(metadata.kind.clone())),
+        ("Key", metadata.bibtex_key.clone()),
+        ("Author", metadata.author.clone()),
+        ("Title", Some(metadata.title.clone())),
+        ("Year", metadata.year.map(|year| year.to_string())),
+        ("DOI", metadata.doi.clone()),
+        ("URL", metadata.url.clone()),
+        ("ISBN", metadata.isbn.clone()),
+    ];
+    fields
+        .into_iter()
+        .filter_map(|(name, value)| value.map(|value| Line::from(format!("{}: {}", name, value))))
+        .collect()
+}
+
+impl Screen for SourcesScreen {
+    async fn handle_key_event(&mut self, key: KeyEvent) -> Result<Option<AppCommand>, TshError> {
+        if let Some(msg) = self.handle_key_event_internal(key) {
+            match msg {
+                SourcesScreenMessage::BackToRecent => {
+                    Ok(Some(AppCommand::SwitchScreen(ActiveScreenType::Recent(
+                        RecentScreen::new(self.db.clone(), self.llm_config.clone()).await?,
+                    ))))
+                }
+                _ => {
+                    self.update(msg).await?;
+                    Ok(None)
+                }
+            }
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn draw(&mut self, f: &mut Frame) {
+        let layout = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(f.area());
+
+        let left_layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(0)])
+            .split(layout[0]);
+
+        let right_layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(layout[1]);
+
+        let input_field = match self.input_mode {
+            InputMode::Insert => Paragraph::new(format!("DOI> {}", self.input)).style(
+                Style::default()
+                    .add_modifier(Modifier::BOLD)
+                    .bg(Color::DarkGray)
+                    .fg(Color::LightGreen),
+            ),
+            InputMode::Normal => Paragraph::new("i: add by DOI, space: attach/detach")
+                .style(Style::default().bg(Color::DarkGray)),
+        };
+
+        let selected_idx = self.sources.curr_idx();
+        let source_list_items: Vec<ListItem> = self
+            .sources
+            .items
+            .iter()
+            .enumerate()
+            .map(|(i, source)| {
+                let checkbox = if self.attached.contains(&source.id) {
+                    "[x]"
+                } else {
+                    "[ ]"
+                };
+                let mut item = ListItem::new(Line::from(format!(
+                    "{} {}",
+                    checkbox,
+                    source.get_citation()
+                )));
+                if selected_idx == Some(i) {
+                    item = item.style(
+                        Style::default()
+                            .bg(Color::DarkGray)
+                            .add_modifier(Modifier::BOLD),
+                    );
+                }
+                item
+            })
+            .collect();
+
+        let source_list = List::new(source_list_items).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Thick)
+                .title(format!("Sources of {}", self.zettel.id)),
+        );
+
+        let details = match self.sources.get_selected_item() {
+            Some(source) => Paragraph::new(source_details(&source)),
+            None => Paragraph::new(
+                "No sources yet. Press i to add one by DOI or run `tsh sources import`.",
+            ),
+        }
+        .wrap(Wrap { trim: true })
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Thick)
+                .title("Details"),
+        );
+
+        let preview = Paragraph::new(self.zettel.content.to_string())
+            .wrap(Wrap { trim: true })
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Thick)
+                    .title("Zettel"),
+            );
+
+        f.render_widget(input_field, left_layout[0]);
+        f.render_stateful_widget(source_list, left_layout[1], &mut self.sources.list_state);
+        f.render_widget(details, right_layout[0]);
+        f.render_widget(preview, right_layout[1]);
+    }
+}
@@ -0,0 +1,210 @@
+use ratatui::{
+    Frame,
+    crossterm::event::{KeyCode, KeyEvent},
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    widgets::{Block, BorderType, Borders, List, ListItem, Paragraph, Wrap},
+};
+
+use crate::{
+    api::get_sources,
+    db::Db,
+    error::TshError,
+    model::Source,
+    template::Template,
+    tui::{
+        app::{ActiveScreenType, AppCommand, LlmConfig, Screen},
+        common::ListWithState,
+        main_menu::MainMenuScreen,
+    },
+};
+
+/// Lets the user pick a template for a new zettel, or start with an empty one. Templates
+/// citing a source ask which one next.
+pub struct TemplateScreen {
+    /// `None` stands for an empty zettel.
+    templates: ListWithState<Option<Template>>,
+    /// Set while picking the source for the chosen template. `None` leaves it out.
+    sources: Option<(Template, ListWithState<Option<Source>>)>,
+    db: Db,
+    llm_config: LlmConfig,
+}
+
+enum TemplateScreenMessage {
+    Back,
+    SelectNext,
+    SelectPrevious,
+    AddZettel,
+}
+
+impl TemplateScreen {
+    pub fn new(templates: Vec<Template>, db: Db, llm_config: LlmConfig) -> Self {
+        let items = std::iter::once(None)
+            .chain(templates.into_iter().map(Some))
+            .collect();
+        Self {
+            templates: ListWithState::new(items),
+            sources: None,
+            db,
+            llm_config,
+        }
+    }
+
+    fn handle_key_event_internal(&self, key: KeyEvent) -> Option<TemplateScreenMessage> {
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => Some(TemplateScreenMessage::Back),
+            KeyCode::Down => Some(TemplateScreenMessage::SelectNext),
+            KeyCode::Up => Some(TemplateScreenMessage::SelectPrevious),
+            KeyCode::Enter => Some(TemplateScreenMessage::AddZettel),
+            _ => None,
+        }
+    }
+
+    fn update(&mut self, message: TemplateScreenMessage) {
+        match (message, &mut self.sources) {
+            (TemplateScreenMessage::SelectNext, Some((_, sources))) => sources.select_next(),
+            (TemplateScreenMessage::SelectPrevious, Some((_, sources))) => sources.select_prev(),
+            (TemplateScreenMessage::SelectNext, None) => self.templates.select_next(),
+            (TemplateScreenMessage::SelectPrevious, None) => self.templates.select_prev(),
+            _ => {}
+        }
+    }
+
+    async fn choose_template(&mut self) -> Result<Option<AppCommand>, TshError> {
+        match self.templates.get_selected_item() {
+            Some(Some(template)) if template.uses_source() => {
+                let sources = std::iter::once(None)
+                    .chain(get_sources(&self.db).await?.into_iter().map(Some))
+                    .collect();
+                self.sources = Some((template, ListWithState::new(sources)));
+                Ok(None)
+            }
+            Some(Some(template)) => Ok(Some(AppCommand::AddZettelFromTemplate(template, None))),
+            Some(None) => Ok(Some(AppCommand::AddZettel(vec![]))),
+            None => Ok(None),
+        }
+    }
+}
+
+impl Screen for TemplateScreen {
+    async fn handle_key_event(&mut self, key: KeyEvent) -> Result<Option<AppCommand>, TshError> {
+        if let Some(msg) = self.handle_key_event_internal(key) {
+            match msg {
+                TemplateScreenMessage::Back if self.sources.is_some() => {
+                    self.sources = None;
+                    Ok(None)
+                }
+                TemplateScreenMessage::Back => {
+                    Ok(Some(AppCommand::SwitchScreen(ActiveScreenType::Main(
+                        MainMenuScreen::new(self.db.clone(), self.llm_config.clone()),
+                    ))))
+                }
+                TemplateScreenMessage::AddZettel => match &mut self.sources {
+                    Some((template, sources)) => match sources.get_selected_item() {
+                        Some(source) => Ok(Some(AppCommand::AddZettelFromTemplate(
+                            template.clone(),
+                            source,
+                        ))),
+                        None => Ok(None),
+                    },
+                    None => self.choose_template().await,
+                },
+                _ => {
+                    self.update(msg);
+                    Ok(None)
+                }
+            }
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn draw(&mut self, f: &mut Frame) {
+        let layout = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(f.area());
+
+        let (labels, list_state, list_title, title, body) = match &mut self.sources {
+            Some((template, sources)) => {
+                let labels: Vec<String> = sources
+                    .items
+                    .iter()
+                    .map(|source| match source {
+                        Some(source) => source.get_citation(),
+                        None => "No source".to_string(),
+                    })
+                    .collect();
+                let body = template.render(sources.get_selected_item().flatten().as_ref());
+                (
+                    labels,
+                    &mut sources.list_state,
+                    format!("Source for {}", template.name),
+                    "Preview".to_string(),
+                    body,
+                )
+            }
+            None => {
+                let labels: Vec<String> = self
+                    .templates
+                    .items
+                    .iter()
+                    .map(|template| match template {
+                        Some(template) => template.name.clone(),
+                        None => "Empty".to_string(),
+                    })
+                    .collect();
+                let (title, body) = match self.templates.get_selected_item().flatten() {
+                    Some(template) if !template.tags.is_empty() => {
+                        let tags: Vec<String> =
+                            template.tags.iter().map(|t| format!("#{}", t)).collect();
+                        (format!("Preview ({})", tags.join(" ")), template.body)
+                    }
+                    Some(template) => ("Preview".to_string(), template.body),
+                    None => ("Preview".to_string(), String::new()),
+                };
+                (
+                    labels,
+                    &mut self.templates.list_state,
+                    "Templates".to_string(),
+                    title,
+                    body,
+                )
+            }
+        };
+
+        let selected_idx = list_state.selected();
+        let items: Vec<ListItem> = labels
+            .into_iter()
+            .enumerate()
+            .map(|(i, label)| {
+                let mut item = ListItem::new(label);
+                if selected_idx == Some(i) {
+                    item = item.style(
+                        Style::default()
+                            .bg(Color::DarkGray)
+                            .add_modifier(Modifier::BOLD),
+                    );
+                }
+                item
+            })
+            .collect();
+
+        let list = List::new(items).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Thick)
+                .title(list_title),
+        );
+
+        let preview = Paragraph::new(body).wrap(Wrap { trim: false }).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Thick)
+                .title(title),
+        );
+
+        f.render_stateful_widget(list, layout[0], list_state);
+        f.render_widget(preview, layout[1]);
+    }
+}
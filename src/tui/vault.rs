@@ -0,0 +1,136 @@
+use ratatui::{
+    Frame,
+    crossterm::event::{KeyCode, KeyEvent},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Block, BorderType, Borders, List, ListItem},
+};
+
+use crate::{
+    config::Vault,
+    db::Db,
+    error::TshError,
+    tui::{
+        app::{ActiveScreenType, AppCommand, LlmConfig, Screen},
+        common::ListWithState,
+        main_menu::MainMenuScreen,
+    },
+};
+
+pub struct VaultScreen {
+    vaults: ListWithState<Vault>,
+    current_vault: String,
+    db: Db,
+    llm_config: LlmConfig,
+}
+
+enum VaultScreenMessage {
+    BackToMainMenu,
+    SelectNext,
+    SelectPrevious,
+    SwitchToSelected,
+}
+
+impl VaultScreen {
+    pub fn new(vaults: Vec<Vault>, current_vault: String, db: Db) -> Self {
+        let current = vaults
+            .iter()
+            .find(|vault| vault.name == current_vault)
+            .cloned()
+            .unwrap_or_else(|| vaults[0].clone());
+        let current_idx = vaults
+            .iter()
+            .position(|vault| vault.name == current.name)
+            .unwrap_or(0);
+
+        let mut vaults = ListWithState::new(vaults);
+        vaults.list_state.select(Some(current_idx));
+
+        Self {
+            vaults,
+            current_vault: current.name,
+            db,
+            llm_config: current.llm_config,
+        }
+    }
+
+    fn handle_key_event_internal(&self, key: KeyEvent) -> Option<VaultScreenMessage> {
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => Some(VaultScreenMessage::BackToMainMenu),
+            KeyCode::Down => Some(VaultScreenMessage::SelectNext),
+            KeyCode::Up => Some(VaultScreenMessage::SelectPrevious),
+            KeyCode::Enter => Some(VaultScreenMessage::SwitchToSelected),
+            _ => None,
+        }
+    }
+
+    fn update(&mut self, message: VaultScreenMessage) {
+        match message {
+            VaultScreenMessage::SelectNext => self.vaults.select_next(),
+            VaultScreenMessage::SelectPrevious => self.vaults.select_prev(),
+            _ => {}
+        }
+    }
+}
+
+impl Screen for VaultScreen {
+    async fn handle_key_event(&mut self, key: KeyEvent) -> Result<Option<AppCommand>, TshError> {
+        if let Some(msg) = self.handle_key_event_internal(key) {
+            match msg {
+                VaultScreenMessage::BackToMainMenu => {
+                    Ok(Some(AppCommand::SwitchScreen(ActiveScreenType::Main(
+                        MainMenuScreen::new(self.db.clone(), self.llm_config.clone()),
+                    ))))
+                }
+                VaultScreenMessage::SwitchToSelected => match self.vaults.get_selected_item() {
+                    Some(vault) => Ok(Some(AppCommand::SwitchVault(vault))),
+                    None => Ok(None),
+                },
+                _ => {
+                    self.update(msg);
+                    Ok(None)
+                }
+            }
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn draw(&mut self, f: &mut Frame) {
+        let selected_idx = self.vaults.curr_idx();
+        let items: Vec<ListItem> = self
+            .vaults
+            .items
+            .iter()
+            .enumerate()
+            .map(|(i, vault)| {
+                let marker = if vault.name == self.current_vault {
+                    "* "
+                } else {
+                    "  "
+                };
+                let mut item = ListItem::new(Line::from(format!(
+                    "{}{}  ({})",
+                    marker, vault.name, vault.db_path
+                )));
+                if selected_idx == Some(i) {
+                    item = item.style(
+                        Style::default()
+                            .bg(Color::DarkGray)
+                            .add_modifier(Modifier::BOLD),
+                    );
+                }
+                item
+            })
+            .collect();
+
+        let list = List::new(items).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .title("Vaults"),
+        );
+
+        f.render_stateful_widget(list, f.area(), &mut self.vaults.list_state);
+    }
+}